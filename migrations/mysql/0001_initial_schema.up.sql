@@ -0,0 +1,113 @@
+-- 初始表结构
+-- user、category、tag、article、article_to_tag 在引入迁移之前由手工建表，已存在时保留原表，
+-- 再补齐之后新增的列
+
+CREATE TABLE IF NOT EXISTS user (
+    id INT UNSIGNED NOT NULL AUTO_INCREMENT,
+    username VARCHAR(64) NOT NULL,
+    password VARCHAR(255) NOT NULL,
+    nickname VARCHAR(64) DEFAULT NULL,
+    PRIMARY KEY (id),
+    UNIQUE KEY uk_user_username (username)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+ALTER TABLE user
+    ADD COLUMN role VARCHAR(16) DEFAULT NULL,
+    ADD COLUMN avatar VARCHAR(255) DEFAULT NULL,
+    ADD COLUMN bio TEXT,
+    ADD COLUMN email VARCHAR(255) DEFAULT NULL,
+    ADD COLUMN website VARCHAR(255) DEFAULT NULL,
+    ADD COLUMN totp_secret VARCHAR(64) DEFAULT NULL,
+    ADD COLUMN totp_pending_secret VARCHAR(64) DEFAULT NULL,
+    ADD COLUMN totp_last_step BIGINT DEFAULT NULL,
+    ADD COLUMN created_at BIGINT DEFAULT NULL,
+    ADD COLUMN updated_at BIGINT DEFAULT NULL;
+
+-- 引入角色之前的账号拥有全部权限
+UPDATE user SET role = 'admin' WHERE role IS NULL;
+
+CREATE TABLE IF NOT EXISTS category (
+    id INT UNSIGNED NOT NULL AUTO_INCREMENT,
+    name VARCHAR(64) NOT NULL,
+    created_at BIGINT DEFAULT NULL,
+    updated_at BIGINT DEFAULT NULL,
+    PRIMARY KEY (id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS tag (
+    id INT UNSIGNED NOT NULL AUTO_INCREMENT,
+    name VARCHAR(64) NOT NULL,
+    created_at BIGINT DEFAULT NULL,
+    updated_at BIGINT DEFAULT NULL,
+    PRIMARY KEY (id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS article (
+    id INT UNSIGNED NOT NULL AUTO_INCREMENT,
+    title VARCHAR(255) NOT NULL,
+    description TEXT,
+    content LONGTEXT,
+    cate_id INT UNSIGNED DEFAULT NULL,
+    istop TINYINT(1) NOT NULL DEFAULT 0,
+    created_at BIGINT DEFAULT NULL,
+    updated_at BIGINT DEFAULT NULL,
+    PRIMARY KEY (id),
+    KEY idx_article_cate_id (cate_id),
+    KEY idx_article_created_at (created_at)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+ALTER TABLE article
+    ADD COLUMN author_id INT UNSIGNED DEFAULT NULL,
+    ADD KEY idx_article_author_id (author_id);
+
+CREATE TABLE IF NOT EXISTS article_to_tag (
+    article_id INT UNSIGNED NOT NULL,
+    tag_id INT UNSIGNED NOT NULL,
+    PRIMARY KEY (article_id, tag_id),
+    KEY idx_article_to_tag_tag_id (tag_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE refresh_token (
+    id INT UNSIGNED NOT NULL AUTO_INCREMENT,
+    user_id INT UNSIGNED NOT NULL,
+    family_id VARCHAR(64) NOT NULL,
+    token_hash VARCHAR(64) NOT NULL,
+    expires_at BIGINT NOT NULL,
+    revoked_at BIGINT DEFAULT NULL,
+    created_at BIGINT DEFAULT NULL,
+    PRIMARY KEY (id),
+    UNIQUE KEY uk_refresh_token_hash (token_hash),
+    KEY idx_refresh_token_user_id (user_id),
+    KEY idx_refresh_token_family_id (family_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE token_revocation (
+    id INT UNSIGNED NOT NULL AUTO_INCREMENT,
+    jti VARCHAR(64) DEFAULT NULL,
+    user_id INT UNSIGNED DEFAULT NULL,
+    revoked_at BIGINT NOT NULL,
+    expires_at BIGINT NOT NULL,
+    PRIMARY KEY (id),
+    KEY idx_token_revocation_jti (jti),
+    KEY idx_token_revocation_user_id (user_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE login_event (
+    id INT UNSIGNED NOT NULL AUTO_INCREMENT,
+    username VARCHAR(64) DEFAULT NULL,
+    ip VARCHAR(64) DEFAULT NULL,
+    event VARCHAR(32) NOT NULL,
+    created_at BIGINT NOT NULL,
+    PRIMARY KEY (id),
+    KEY idx_login_event_created_at (created_at)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE recovery_code (
+    id INT UNSIGNED NOT NULL AUTO_INCREMENT,
+    user_id INT UNSIGNED NOT NULL,
+    code_hash VARCHAR(64) NOT NULL,
+    used_at BIGINT DEFAULT NULL,
+    created_at BIGINT DEFAULT NULL,
+    PRIMARY KEY (id),
+    KEY idx_recovery_code_user_id (user_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
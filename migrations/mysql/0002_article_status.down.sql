@@ -0,0 +1,5 @@
+DROP INDEX idx_article_status_published_at ON article;
+
+ALTER TABLE article
+    DROP COLUMN published_at,
+    DROP COLUMN status;
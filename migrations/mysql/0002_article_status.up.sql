@@ -0,0 +1,9 @@
+-- 文章状态及发布时间，已有文章视为已发布
+
+ALTER TABLE article
+    ADD COLUMN status VARCHAR(16) NOT NULL DEFAULT 'published',
+    ADD COLUMN published_at BIGINT DEFAULT NULL;
+
+UPDATE article SET published_at = created_at;
+
+CREATE INDEX idx_article_status_published_at ON article (status, published_at);
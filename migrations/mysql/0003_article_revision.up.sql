@@ -0,0 +1,17 @@
+-- 文章历史版本：每次保存文章都记录一个版本，已有文章记录为第 1 个版本
+
+CREATE TABLE article_revision (
+    id INT UNSIGNED NOT NULL AUTO_INCREMENT,
+    article_id INT UNSIGNED NOT NULL,
+    revision INT UNSIGNED NOT NULL,
+    title VARCHAR(255) NOT NULL,
+    description TEXT,
+    content LONGTEXT,
+    author_id INT UNSIGNED DEFAULT NULL,
+    created_at BIGINT DEFAULT NULL,
+    PRIMARY KEY (id),
+    UNIQUE KEY uk_article_revision (article_id, revision)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+INSERT INTO article_revision (article_id, revision, title, description, content, author_id, created_at)
+SELECT id, 1, title, description, content, author_id, COALESCE(updated_at, created_at) FROM article;
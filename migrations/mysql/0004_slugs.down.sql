@@ -0,0 +1,10 @@
+DROP TABLE slug_history;
+
+DROP INDEX uk_tag_slug ON tag;
+ALTER TABLE tag DROP COLUMN slug;
+
+DROP INDEX uk_category_slug ON category;
+ALTER TABLE category DROP COLUMN slug;
+
+DROP INDEX uk_article_slug ON article;
+ALTER TABLE article DROP COLUMN slug;
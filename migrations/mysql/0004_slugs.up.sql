@@ -0,0 +1,21 @@
+-- 文章、分类、标签的 slug 及 slug 历史（修改 slug 后旧地址重定向到新地址）
+-- 已有数据的 slug 为空，由 `lsp-blog generate-slugs` 生成
+
+ALTER TABLE article ADD COLUMN slug VARCHAR(128) DEFAULT NULL;
+CREATE UNIQUE INDEX uk_article_slug ON article (slug);
+
+ALTER TABLE category ADD COLUMN slug VARCHAR(128) DEFAULT NULL;
+CREATE UNIQUE INDEX uk_category_slug ON category (slug);
+
+ALTER TABLE tag ADD COLUMN slug VARCHAR(128) DEFAULT NULL;
+CREATE UNIQUE INDEX uk_tag_slug ON tag (slug);
+
+CREATE TABLE slug_history (
+    id INT UNSIGNED NOT NULL AUTO_INCREMENT,
+    kind VARCHAR(16) NOT NULL,
+    slug VARCHAR(128) NOT NULL,
+    target_id INT UNSIGNED NOT NULL,
+    created_at BIGINT DEFAULT NULL,
+    PRIMARY KEY (id),
+    UNIQUE KEY uk_slug_history (kind, slug)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
@@ -0,0 +1,4 @@
+-- 文章正文渲染后的 HTML，保存文章时生成
+-- 已有文章为空，由 `lsp-blog render-content` 生成，生成前详情接口按需渲染
+
+ALTER TABLE article ADD COLUMN content_html LONGTEXT;
@@ -0,0 +1,4 @@
+ALTER TABLE article
+    DROP COLUMN reading_minutes,
+    DROP COLUMN word_count,
+    DROP COLUMN toc;
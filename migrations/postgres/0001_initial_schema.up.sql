@@ -0,0 +1,101 @@
+-- 初始表结构
+-- user、category、tag、article、article_to_tag 在引入迁移之前由手工建表，已存在时保留原表，
+-- 再补齐之后新增的列
+
+CREATE TABLE IF NOT EXISTS "user" (
+    id SERIAL PRIMARY KEY,
+    username VARCHAR(64) NOT NULL,
+    password VARCHAR(255) NOT NULL,
+    nickname VARCHAR(64)
+);
+CREATE UNIQUE INDEX IF NOT EXISTS uk_user_username ON "user" (username);
+ALTER TABLE "user" ADD COLUMN role VARCHAR(16);
+ALTER TABLE "user" ADD COLUMN avatar VARCHAR(255);
+ALTER TABLE "user" ADD COLUMN bio TEXT;
+ALTER TABLE "user" ADD COLUMN email VARCHAR(255);
+ALTER TABLE "user" ADD COLUMN website VARCHAR(255);
+ALTER TABLE "user" ADD COLUMN totp_secret VARCHAR(64);
+ALTER TABLE "user" ADD COLUMN totp_pending_secret VARCHAR(64);
+ALTER TABLE "user" ADD COLUMN totp_last_step BIGINT;
+ALTER TABLE "user" ADD COLUMN created_at BIGINT;
+ALTER TABLE "user" ADD COLUMN updated_at BIGINT;
+
+-- 引入角色之前的账号拥有全部权限
+UPDATE "user" SET role = 'admin' WHERE role IS NULL;
+
+CREATE TABLE IF NOT EXISTS category (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(64) NOT NULL,
+    created_at BIGINT,
+    updated_at BIGINT
+);
+
+CREATE TABLE IF NOT EXISTS tag (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(64) NOT NULL,
+    created_at BIGINT,
+    updated_at BIGINT
+);
+
+CREATE TABLE IF NOT EXISTS article (
+    id SERIAL PRIMARY KEY,
+    title VARCHAR(255) NOT NULL,
+    description TEXT,
+    content TEXT,
+    cate_id INT,
+    istop BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at BIGINT,
+    updated_at BIGINT
+);
+ALTER TABLE article ADD COLUMN author_id INT;
+CREATE INDEX IF NOT EXISTS idx_article_cate_id ON article (cate_id);
+CREATE INDEX IF NOT EXISTS idx_article_author_id ON article (author_id);
+CREATE INDEX IF NOT EXISTS idx_article_created_at ON article (created_at);
+
+CREATE TABLE IF NOT EXISTS article_to_tag (
+    article_id INT NOT NULL,
+    tag_id INT NOT NULL,
+    PRIMARY KEY (article_id, tag_id)
+);
+CREATE INDEX IF NOT EXISTS idx_article_to_tag_tag_id ON article_to_tag (tag_id);
+
+CREATE TABLE refresh_token (
+    id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL,
+    family_id VARCHAR(64) NOT NULL,
+    token_hash VARCHAR(64) NOT NULL,
+    expires_at BIGINT NOT NULL,
+    revoked_at BIGINT,
+    created_at BIGINT
+);
+CREATE UNIQUE INDEX uk_refresh_token_hash ON refresh_token (token_hash);
+CREATE INDEX idx_refresh_token_user_id ON refresh_token (user_id);
+CREATE INDEX idx_refresh_token_family_id ON refresh_token (family_id);
+
+CREATE TABLE token_revocation (
+    id SERIAL PRIMARY KEY,
+    jti VARCHAR(64),
+    user_id INT,
+    revoked_at BIGINT NOT NULL,
+    expires_at BIGINT NOT NULL
+);
+CREATE INDEX idx_token_revocation_jti ON token_revocation (jti);
+CREATE INDEX idx_token_revocation_user_id ON token_revocation (user_id);
+
+CREATE TABLE login_event (
+    id SERIAL PRIMARY KEY,
+    username VARCHAR(64),
+    ip VARCHAR(64),
+    event VARCHAR(32) NOT NULL,
+    created_at BIGINT NOT NULL
+);
+CREATE INDEX idx_login_event_created_at ON login_event (created_at);
+
+CREATE TABLE recovery_code (
+    id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL,
+    code_hash VARCHAR(64) NOT NULL,
+    used_at BIGINT,
+    created_at BIGINT
+);
+CREATE INDEX idx_recovery_code_user_id ON recovery_code (user_id);
@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS idx_article_status_published_at;
+
+ALTER TABLE article
+    DROP COLUMN published_at,
+    DROP COLUMN status;
@@ -0,0 +1,16 @@
+-- 文章历史版本：每次保存文章都记录一个版本，已有文章记录为第 1 个版本
+
+CREATE TABLE article_revision (
+    id SERIAL PRIMARY KEY,
+    article_id INT NOT NULL,
+    revision INT NOT NULL,
+    title VARCHAR(255) NOT NULL,
+    description TEXT,
+    content TEXT,
+    author_id INT,
+    created_at BIGINT
+);
+CREATE UNIQUE INDEX uk_article_revision ON article_revision (article_id, revision);
+
+INSERT INTO article_revision (article_id, revision, title, description, content, author_id, created_at)
+SELECT id, 1, title, description, content, author_id, COALESCE(updated_at, created_at) FROM article;
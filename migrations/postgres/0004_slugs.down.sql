@@ -0,0 +1,10 @@
+DROP TABLE slug_history;
+
+DROP INDEX IF EXISTS uk_tag_slug;
+ALTER TABLE tag DROP COLUMN slug;
+
+DROP INDEX IF EXISTS uk_category_slug;
+ALTER TABLE category DROP COLUMN slug;
+
+DROP INDEX IF EXISTS uk_article_slug;
+ALTER TABLE article DROP COLUMN slug;
@@ -0,0 +1,7 @@
+-- 文章目录（JSON）、字数及预计阅读时间（分钟），保存文章时由正文生成
+-- 已有文章为空，由 `lsp-blog render-content` 生成，生成前详情接口按需计算
+
+ALTER TABLE article
+    ADD COLUMN toc TEXT,
+    ADD COLUMN word_count INT,
+    ADD COLUMN reading_minutes INT;
@@ -0,0 +1,9 @@
+DROP TABLE IF EXISTS recovery_code;
+DROP TABLE IF EXISTS login_event;
+DROP TABLE IF EXISTS token_revocation;
+DROP TABLE IF EXISTS refresh_token;
+DROP TABLE IF EXISTS article_to_tag;
+DROP TABLE IF EXISTS article;
+DROP TABLE IF EXISTS tag;
+DROP TABLE IF EXISTS category;
+DROP TABLE IF EXISTS user;
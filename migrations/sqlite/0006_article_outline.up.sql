@@ -0,0 +1,6 @@
+-- 文章目录（JSON）、字数及预计阅读时间（分钟），保存文章时由正文生成
+-- 已有文章为空，由 `lsp-blog render-content` 生成，生成前详情接口按需计算
+
+ALTER TABLE article ADD COLUMN toc TEXT;
+ALTER TABLE article ADD COLUMN word_count INTEGER;
+ALTER TABLE article ADD COLUMN reading_minutes INTEGER;
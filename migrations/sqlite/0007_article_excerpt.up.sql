@@ -0,0 +1,4 @@
+-- 文章摘要：填写了描述时为描述，否则由正文生成，列表接口以摘要代替正文
+-- 已有文章为空，由 `lsp-blog render-content` 生成，生成前列表接口按需计算
+
+ALTER TABLE article ADD COLUMN excerpt TEXT;
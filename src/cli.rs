@@ -0,0 +1,339 @@
+// 命令行：不带子命令时启动 web 服务，子命令用于运维管理
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, Subcommand};
+use rbatis::crud::CRUD;
+use rocket::figment::Figment;
+use rocket::serde::json::serde_json;
+
+use crate::controller::user_controller::{valid_username, MIN_PASSWORD_LEN};
+use crate::model::article::Article;
+use crate::model::backup::Backup;
+use crate::model::category::Category;
+use crate::model::slug_history::{SlugHistory, SlugKind};
+use crate::model::tag::Tag;
+use crate::model::user::{NewUser, User, UserProfile};
+use crate::repository::Repositories;
+use crate::util::database;
+use crate::util::keyring::{write_private, JwtConfig, KeyFile};
+use crate::util::migration;
+use crate::util::permission::Role;
+use crate::util::slug;
+use crate::RB;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "lsp-blog",
+    version,
+    about = "lsp-blog server and administration commands"
+)]
+pub struct Cli {
+    /// Runs the web server when omitted
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Apply, list or roll back database migrations
+    Migrate {
+        #[command(subcommand)]
+        action: Option<MigrateAction>,
+    },
+    /// Create an admin user, prompting for the password
+    CreateAdmin { username: String },
+    /// Set a new password for a user, prompting for it, and revoke the user's sessions
+    ResetPassword { username: String },
+    /// Generate a new jwt signing key in the key file and make it active
+    RotateJwtKey,
+    /// Does nothing: search queries the article table directly, there is no index to rebuild
+    ReindexSearch,
+    /// Generate missing slugs for articles, categories and tags
+    GenerateSlugs,
+    /// Render the HTML, table of contents, word count and excerpt of every article again
+    RenderContent,
+    /// Export users, categories, tags and articles as JSON
+    Export {
+        /// Output file, stdout when omitted
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Import a JSON export into an empty database
+    Import { file: PathBuf },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MigrateAction {
+    /// Apply pending migrations (default)
+    Up,
+    /// List migrations and whether they are applied
+    Status,
+    /// Roll back the latest applied migrations
+    Rollback {
+        #[arg(default_value_t = 1)]
+        steps: usize,
+    },
+}
+
+/// 命令执行错误，内容直接输出给用户
+type CliResult = Result<(), String>;
+
+/// 执行子命令，返回进程退出码
+/// ---
+/// @parameter  command     Command
+/// @parameter  figment     &Figment
+/// @return     i32
+/// ---
+pub async fn run(command: Command, figment: &Figment) -> i32 {
+    // 命令行输出不需要请求及 SQL 日志
+    log::set_max_level(log::LevelFilter::Warn);
+
+    let result = match command {
+        // 不需要连接数据库的命令
+        Command::RotateJwtKey => rotate_jwt_key(figment),
+        Command::ReindexSearch => reindex_search(),
+        command => match database::init(&RB, figment).await {
+            Ok(config) => run_with_database(command, config.auto_migrate).await,
+            Err(e) => Err(e.to_string()),
+        },
+    };
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            1
+        }
+    }
+}
+
+async fn run_with_database(command: Command, auto_migrate: bool) -> CliResult {
+    // 与启动服务时一样先执行数据库迁移
+    if auto_migrate && !matches!(command, Command::Migrate { .. }) {
+        migration::migrate(&RB).await.map_err(|e| e.to_string())?;
+    }
+    match command {
+        Command::Migrate { action } => migrate(action.unwrap_or(MigrateAction::Up)).await,
+        Command::CreateAdmin { username } => create_admin(username).await,
+        Command::ResetPassword { username } => reset_password(&username).await,
+        Command::Export { output } => export(output.as_deref()).await,
+        Command::Import { file } => import(&file).await,
+        Command::GenerateSlugs => generate_slugs().await,
+        Command::RenderContent => render_content().await,
+        Command::RotateJwtKey | Command::ReindexSearch => unreachable!(),
+    }
+}
+
+async fn migrate(action: MigrateAction) -> CliResult {
+    match action {
+        MigrateAction::Up => {
+            let done = migration::migrate(&RB).await.map_err(|e| e.to_string())?;
+            println!("applied {} migration(s)", done.len());
+            for m in done {
+                println!("  {}", m.name);
+            }
+        }
+        MigrateAction::Status => {
+            for m in migration::status(&RB).await.map_err(|e| e.to_string())? {
+                let state = match m.applied_at {
+                    Some(at) => format!("applied at {}", at),
+                    None => "pending".to_string(),
+                };
+                println!("{}  {}", m.name, state);
+            }
+        }
+        MigrateAction::Rollback { steps } => {
+            let done = migration::rollback(&RB, steps)
+                .await
+                .map_err(|e| e.to_string())?;
+            println!("rolled back {} migration(s)", done.len());
+            for m in done {
+                println!("  {}", m.name);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 提示输入两次新密码
+fn prompt_new_password() -> Result<String, String> {
+    let password = rpassword::prompt_password("Password: ").map_err(|e| e.to_string())?;
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(format!(
+            "password must be at least {} characters",
+            MIN_PASSWORD_LEN
+        ));
+    }
+    let confirm = rpassword::prompt_password("Confirm password: ").map_err(|e| e.to_string())?;
+    if password != confirm {
+        return Err("passwords do not match".to_string());
+    }
+    Ok(password)
+}
+
+async fn create_admin(username: String) -> CliResult {
+    if !valid_username(&username) {
+        return Err("username must be 3-32 letters, digits, '_', '.' or '-'".to_string());
+    }
+    if User::username_exists(&username)
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        return Err(format!("user {} already exists", username));
+    }
+    let password = prompt_new_password()?;
+    let new_user = NewUser {
+        username: username.clone(),
+        password,
+        role: Role::Admin.as_str().to_string(),
+        profile: UserProfile::default(),
+    };
+    let id = User::create(new_user).await.map_err(|e| e.to_string())?;
+    match id {
+        Some(id) => println!("created admin {} (id {})", username, id),
+        None => println!("created admin {}", username),
+    }
+    Ok(())
+}
+
+async fn reset_password(username: &str) -> CliResult {
+    let user = User::find_by_username(username)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("user {} not found", username))?;
+    let id = user.id.unwrap_or_default();
+    let password = prompt_new_password()?;
+    User::update_password(id, &password)
+        .await
+        .map_err(|e| e.to_string())?;
+    User::revoke_sessions(id).await.map_err(|e| e.to_string())?;
+    println!("password of {} reset, all sessions revoked", username);
+    Ok(())
+}
+
+fn rotate_jwt_key(figment: &Figment) -> CliResult {
+    let config = JwtConfig::from_figment(figment).map_err(|e| e.to_string())?;
+    let path = config
+        .key_file
+        .ok_or("no jwt key_file is configured, keys set in Rocket.toml must be rotated by hand")?;
+    let mut file = if path.exists() {
+        KeyFile::read(&path).map_err(|e| e.to_string())?
+    } else {
+        KeyFile::default()
+    };
+    let kid = file.rotate().kid.clone();
+    file.write(&path).map_err(|e| e.to_string())?;
+    println!("new signing key {} written to {}", kid, path.display());
+    if let Some(active_kid) = config.active_kid {
+        println!(
+            "note: active_kid = \"{}\" in Rocket.toml overrides the key file, update or remove it",
+            active_kid
+        );
+    }
+    println!(
+        "restart the server to sign with the new key, tokens signed with older keys stay valid"
+    );
+    Ok(())
+}
+
+fn reindex_search() -> CliResult {
+    // 搜索直接查询 article 表（LIKE），没有需要重建的索引
+    println!("search queries the article table directly, there is no index to rebuild");
+    Ok(())
+}
+
+async fn generate_slugs() -> CliResult {
+    let repos = Repositories::db();
+    // 数据 id 及用于生成 slug 的标题或名称
+    let mut pending: Vec<(SlugKind, u32, String)> = Vec::new();
+    let articles: Vec<Article> = RB.fetch_list().await.map_err(|e| e.to_string())?;
+    for art in articles.into_iter().filter(|a| a.slug.is_none()) {
+        pending.push((
+            SlugKind::Article,
+            art.id.unwrap_or_default(),
+            art.title.unwrap_or_default(),
+        ));
+    }
+    let categories: Vec<Category> = RB.fetch_list().await.map_err(|e| e.to_string())?;
+    for cate in categories.into_iter().filter(|c| c.slug.is_none()) {
+        pending.push((
+            SlugKind::Category,
+            cate.id.unwrap_or_default(),
+            cate.name.unwrap_or_default(),
+        ));
+    }
+    let tags: Vec<Tag> = RB.fetch_list().await.map_err(|e| e.to_string())?;
+    for tag in tags.into_iter().filter(|t| t.slug.is_none()) {
+        pending.push((
+            SlugKind::Tag,
+            tag.id.unwrap_or_default(),
+            tag.name.unwrap_or_default(),
+        ));
+    }
+
+    let mut filled = 0;
+    for (kind, id, text) in pending {
+        let slug = slug::generate(&repos, kind, &text, Some(id))
+            .await
+            .map_err(|e| e.to_string())?;
+        filled += SlugHistory::fill(kind, id, &slug)
+            .await
+            .map_err(|e| e.to_string())?;
+        println!("  {} {} -> {}", kind.as_str(), id, slug);
+    }
+    println!("generated {} slug(s)", filled);
+    Ok(())
+}
+
+async fn render_content() -> CliResult {
+    let articles: Vec<Article> = RB.fetch_list().await.map_err(|e| e.to_string())?;
+    let mut rendered = 0;
+    for art in articles {
+        rendered += Article::update_rendered(
+            art.id.unwrap_or_default(),
+            art.description.as_deref(),
+            art.content.as_deref(),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    println!("rendered {} article(s)", rendered);
+    Ok(())
+}
+
+async fn export(output: Option<&Path>) -> CliResult {
+    let backup = Backup::export().await.map_err(|e| e.to_string())?;
+    let json = serde_json::to_string_pretty(&backup).map_err(|e| e.to_string())?;
+    match output {
+        Some(path) => {
+            // 导出文件包含密码哈希，仅所有者可读写
+            write_private(path, json.as_bytes())
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+            eprintln!(
+                "exported {} user(s), {} categories, {} tag(s), {} article(s) to {}",
+                backup.users.len(),
+                backup.categories.len(),
+                backup.tags.len(),
+                backup.articles.len(),
+                path.display()
+            );
+        }
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
+async fn import(file: &Path) -> CliResult {
+    let raw = fs::read_to_string(file).map_err(|e| format!("{}: {}", file.display(), e))?;
+    let backup: Backup =
+        serde_json::from_str(&raw).map_err(|e| format!("{}: {}", file.display(), e))?;
+    backup.import().await.map_err(|e| e.to_string())?;
+    println!(
+        "imported {} user(s), {} categories, {} tag(s), {} article(s)",
+        backup.users.len(),
+        backup.categories.len(),
+        backup.tags.len(),
+        backup.articles.len()
+    );
+    Ok(())
+}
@@ -1,148 +1,473 @@
 use chrono::Local;
 use rocket::form::FromForm;
+use rocket::response::Redirect;
 use rocket::serde::json::{serde_json::json, Json, Value};
-use rocket::{delete, get, post, put};
+use rocket::{delete, get, post, put, uri, State};
 use serde::Deserialize;
+use validator::{Validate, ValidationError};
 
-use crate::util::token::Token;
-use crate::model::article::{Article, ArticleForUpdateVo, ArticleVo};
-use crate::response::resp_obj::{RespData, RespWithPagination};
+use crate::model::article::{Article, ArticleForUpdateVo, ArticleStatus, ArticleVo};
+use crate::model::slug_history::SlugKind;
+use crate::repository::Repositories;
+use crate::response::error::{AppError, FieldError};
+use crate::response::resp_obj::{RespData, RespWithPagination, SlugResp, StyleSheet};
+use crate::util::highlight;
+use crate::util::permission::{perm, Authorized, Permission};
+use crate::util::slug::{self, slug_format};
+use crate::util::token::AuthUser;
+use crate::util::validation::{self, not_blank, unique_ids};
+
+/// 每页最多条数
+const MAX_PAGE_SIZE: u64 = 100;
 
 /// 接收分页查询字符串的结构体
-#[derive(Debug, PartialEq, FromForm)]
+/// serde 的字段名只用作校验错误中的字段名
+#[derive(Debug, PartialEq, FromForm, Deserialize, Validate)]
 pub struct Page {
     page: u64,
-    pageSize: u64,
+    #[field(name = "pageSize")]
+    #[serde(rename = "pageSize")]
+    #[validate(range(
+        max = "MAX_PAGE_SIZE",
+        code = "too_large",
+        message = "Must be at most 100."
+    ))]
+    page_size: u64,
 }
 
+/// 文章不存在
+pub(crate) const ARTICLE_NOT_FOUND: AppError = AppError::NotFound {
+    error: "article_not_found",
+    msg: "Article not found.",
+};
+
 /// 分页查询文章列表
 #[get("/articles?<page..>")]
-pub async fn list(page: Page) -> Value {
+pub async fn list(page: Page, repos: &State<Repositories>) -> Result<Value, AppError> {
+    if page.page == 0 || page.page_size == 0 {
+        return Err(AppError::bad_request(
+            "invalid_page",
+            "page and pageSize must be at least 1.",
+        ));
+    }
+    validation::check(validation::field_errors(&page))?;
+    // offset 超出范围时数据库无法处理
+    let offset = match (page.page - 1).checked_mul(page.page_size) {
+        Some(offset) if offset <= i64::MAX as u64 => offset,
+        _ => {
+            return Err(AppError::invalid_field(
+                "page",
+                "too_large",
+                "Page is too large.",
+            ))
+        }
+    };
     // page_size 每页条数
-    let page_size = page.pageSize;
+    let page_size = page.page_size;
     // current_page 当前页
     let current_page = page.page;
-    // total 总记录数
-    let total = Article::find_total().await.unwrap();
-
-    // 分页查询（带分类信息）
-    let page = (page.page - 1) * page_size;
-    let articles = Article::find_all_by_pagination_with_category(&page.to_string(), &page_size.to_string()).await;
-    if let Ok(arts) = articles {
-        let data = RespWithPagination::<Vec<ArticleVo>> {
-            code: 200,
-            msg: "Success",
-            data: arts,
-            current_page: current_page,
-            page_size: page_size,
-            total: total,
-        };
-        return json!(data);
+    // total 已发布文章总数
+    let total = repos.articles.count_published().await?;
+
+    // 分页查询已发布的文章（带分类信息）
+    let arts = repos
+        .articles
+        .find_published_page(page_size, offset)
+        .await?
+        .into_iter()
+        .map(ArticleVo::into_summary)
+        .collect();
+    let data = RespWithPagination::<Vec<ArticleVo>> {
+        code: 200,
+        msg: "Success",
+        data: arts,
+        current_page,
+        page_size,
+        total,
+    };
+    return Ok(json!(data));
+}
+
+/// 文章正文的输出格式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ContentFormat {
+    /// Markdown 原文（content）
+    Markdown,
+    /// 渲染后的 HTML（content_html）
+    Html,
+}
+
+impl ContentFormat {
+    /// 解析查询字符串中的 format，未指定时为 Markdown
+    fn parse(format: Option<&str>) -> Result<ContentFormat, AppError> {
+        match format {
+            None | Some("markdown") => Ok(ContentFormat::Markdown),
+            Some("html") => Ok(ContentFormat::Html),
+            Some(_) => Err(AppError::bad_request(
+                "invalid_format",
+                "format must be markdown or html.",
+            )),
+        }
+    }
+
+    /// 只保留对应格式的正文（另一个字段为 null）
+    fn apply(self, mut art: ArticleVo) -> ArticleVo {
+        match self {
+            ContentFormat::Markdown => art.content_html = None,
+            ContentFormat::Html => art.content = None,
+        }
+        art
     }
-    return json!(RespData {
-        code: 500,
-        msg: "error",
-        data: (),
-    });
 }
 
-/// 文章详情
-#[get("/article/<id>")]
-pub async fn detail(id: u32) -> Value {
-    let article = Article::find_by_id(id).await;
-    if let Ok(art) = article {
-        return json!(RespData {
+/// 已发布的文章详情（带分类名称），其他状态的文章视为不存在
+async fn published_with_category(repos: &Repositories, id: u32) -> Result<ArticleVo, AppError> {
+    match repos.articles.find_by_id_with_category(id).await? {
+        Some(art)
+            if art.status.as_deref().and_then(ArticleStatus::parse)
+                == Some(ArticleStatus::Published) =>
+        {
+            Ok(art)
+        }
+        _ => Err(ARTICLE_NOT_FOUND),
+    }
+}
+
+/// 文章详情（只显示已发布的文章），format 为 markdown（默认）或 html
+#[get("/article/<id>?<format>")]
+pub async fn detail(
+    id: u32,
+    format: Option<&str>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    let format = ContentFormat::parse(format)?;
+    let art = published_with_category(repos, id).await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Success",
+        data: format.apply(art),
+    }));
+}
+
+/// 按 slug 查询文章详情（只显示已发布的文章），旧 slug 永久重定向到当前 slug
+#[get("/article/slug/<slug>?<format>")]
+pub async fn detail_by_slug(
+    slug: &str,
+    format: Option<&str>,
+    repos: &State<Repositories>,
+) -> Result<SlugResp, AppError> {
+    let content_format = ContentFormat::parse(format)?;
+    if let Some(art) = repos.articles.find_by_slug(slug).await? {
+        let art = published_with_category(repos, art.id.unwrap_or_default()).await?;
+        return Ok(SlugResp::Found(json!(RespData {
             code: 200,
             msg: "Success",
-            data: art,
-        });
+            data: content_format.apply(art),
+        })));
     }
-    return json!(RespData {
-        code: 500,
-        msg: "error",
-        data: (),
-    });
+    let current = slug::redirect_target(repos, SlugKind::Article, slug)
+        .await?
+        .ok_or(ARTICLE_NOT_FOUND)?;
+    Ok(SlugResp::Moved(Redirect::moved(uri!(
+        "/api",
+        detail_by_slug(current, format)
+    ))))
 }
 
-/// 正在编辑的文章详情
+/// 文章正文中代码高亮的样式表（主题由 highlight 配置决定）
+#[get("/highlight.css")]
+pub fn highlight_css() -> StyleSheet {
+    StyleSheet::new(highlight::css())
+}
+
+/// 正在编辑的文章详情（任意状态，只能查看有权限修改的文章）
 #[get("/article/edit/<id>")]
-pub async fn editing_article_detail(id: &str) -> Value {
-    let res = Article::find_editing_by_id(id).await;
-    match res {
-        Ok(op_art) => match op_art {
-            Some(art) => {
-                return json!(RespData {
-                    code: 200,
-                    msg: "Success",
-                    data: art,
-                });
-            }
-            None => {
-                return json!(());
-            }
-        },
-        Err(_) => {
-            return json!(());
-        }
+pub async fn editing_article_detail(
+    id: u32,
+    auth: Authorized<perm::EditOwnArticle>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    let art = repos
+        .articles
+        .find_by_id(id)
+        .await?
+        .ok_or(ARTICLE_NOT_FOUND)?;
+    if !auth.user().can_edit_article(art.author_id) {
+        return Err(AppError::forbidden());
     }
+
+    let art = repos
+        .articles
+        .find_editing_by_id(id)
+        .await?
+        .ok_or(ARTICLE_NOT_FOUND)?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Success",
+        data: art,
+    }));
 }
 
 /// 最热文章
 #[get("/article/hot")]
-pub async fn hot() -> Value {
-    let hots = Article::hot_list().await;
-    if let Ok(hs) = hots {
-        return json!(RespData {
-            code: 200,
-            msg: "Success",
-            data: hs
-        });
-    }
-    return json!(());
+pub async fn hot(repos: &State<Repositories>) -> Result<Value, AppError> {
+    let hs: Vec<Article> = repos
+        .articles
+        .hot_list()
+        .await?
+        .into_iter()
+        .map(Article::into_summary)
+        .collect();
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Success",
+        data: hs
+    }));
 }
 
 /// 删除文章
 #[delete("/article/<id>")]
-pub async fn delete(id: u32, _t: Token) -> Value {
-    let res = Article::remove(id).await;
-    match res {
-        Ok(_) => {
-            return json!(RespData {
-                code: 200,
-                msg: "删除文章成功",
-                data: ()
-            });
-        }
-        Err(_) => {
-            return json!(RespData {
-                code: 500,
-                msg: "删除文章失败",
-                data: ()
-            });
-        }
+pub async fn delete(
+    id: u32,
+    auth: Authorized<perm::DeleteOwnArticle>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    // 只能删除有权限的文章
+    let art = repos
+        .articles
+        .find_by_id(id)
+        .await?
+        .ok_or(ARTICLE_NOT_FOUND)?;
+    if !auth.user().can_delete_article(art.author_id) {
+        return Err(AppError::forbidden());
     }
+
+    repos.articles.remove(id).await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "删除文章成功",
+        data: ()
+    }));
 }
 
 /// 接收前端post/put提交的数据
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate, Default)]
+#[serde(default)]
 pub struct PostOrPutArticleData {
+    #[validate(custom = "not_blank", length(max = 255, code = "too_long"))]
     pub title: String,
+    /// 网址 slug，只有管理员可以指定或修改
+    /// 未提交时：新增文章由标题生成；更新文章保持原 slug
+    #[validate(custom = "slug_format")]
+    pub slug: Option<String>,
+    #[validate(length(max = 500, code = "too_long"))]
     pub description: String,
-    pub cate_id: u32,
+    #[validate(required(code = "required", message = "This field is required."))]
+    pub cate_id: Option<u32>,
+    #[validate(custom = "not_blank", length(max = 200000, code = "too_long"))]
     pub content: String,
+    #[validate(
+        custom = "unique_ids",
+        length(max = 20, code = "too_many", message = "At most 20 tags.")
+    )]
     pub tags: Vec<u32>,
+    /// draft、published、scheduled 或 archived
+    /// 未提交时：新增文章有发布权限则直接发布，否则保存为草稿；更新文章保持原状态
+    #[validate(custom = "known_status")]
+    pub status: Option<String>,
+    /// 定时发布的时间（秒级时间戳），仅 scheduled 状态使用
+    pub published_at: Option<i64>,
+}
+
+/// 文章状态必须是已知的状态
+fn known_status(status: &str) -> Result<(), ValidationError> {
+    if ArticleStatus::parse(status).is_none() {
+        return Err(validation::invalid(
+            "invalid",
+            "Status must be draft, published, scheduled or archived.",
+        ));
+    }
+    Ok(())
+}
+
+/// 计算保存的状态及发布时间
+/// ---
+/// 发布及定时发布需要发布权限；已发布的文章重新保存时保留原发布时间；
+/// 没有发布权限的用户修改已发布或定时发布文章时见 `review_live_edit`
+/// ---
+fn publication(
+    data: &PostOrPutArticleData,
+    user: &AuthUser,
+    current: Option<&Article>,
+) -> Result<(ArticleStatus, Option<i64>), AppError> {
+    let current_status = current
+        .and_then(|a| a.status.as_deref())
+        .and_then(ArticleStatus::parse);
+    let current_published_at = current.and_then(|a| a.published_at);
+    let can_publish = user.role.can(Permission::PublishArticle);
+    let requested = data.status.as_deref().and_then(ArticleStatus::parse);
+    let (status, published_at) = match requested {
+        None if current.is_some() => (
+            current_status.unwrap_or(ArticleStatus::Draft),
+            current_published_at,
+        ),
+        _ => {
+            let status = match requested {
+                Some(status) => status,
+                None if can_publish => ArticleStatus::Published,
+                None => ArticleStatus::Draft,
+            };
+            let publishing = matches!(status, ArticleStatus::Published | ArticleStatus::Scheduled);
+            if publishing && Some(status) != current_status && !can_publish {
+                return Err(AppError::forbidden());
+            }
+            let published_at = match status {
+                ArticleStatus::Draft => None,
+                ArticleStatus::Scheduled => data.published_at,
+                ArticleStatus::Published if current_status == Some(ArticleStatus::Published) => {
+                    current_published_at
+                }
+                ArticleStatus::Published => Some(Local::now().timestamp()),
+                ArticleStatus::Archived => current_published_at,
+            };
+            (status, published_at)
+        }
+    };
+
+    Ok(match current {
+        Some(current) => review_live_edit(
+            user,
+            current,
+            (status, published_at),
+            Some(data.content.as_str()),
+        ),
+        None => (status, published_at),
+    })
+}
+
+/// 前台可见（或即将可见）的内容只能由有发布权限的用户修改
+/// ---
+/// 没有发布权限的用户修改已发布或定时发布文章的正文或发布时间时，文章改回草稿
+/// @parameter  user        &AuthUser
+/// @parameter  current     &Article                        修改前的文章
+/// @parameter  saving      (ArticleStatus, Option<i64>)    将要保存的状态及发布时间
+/// @parameter  content     Option<&str>                    将要保存的正文
+/// @return     (ArticleStatus, Option<i64>)
+/// ---
+pub(crate) fn review_live_edit(
+    user: &AuthUser,
+    current: &Article,
+    saving: (ArticleStatus, Option<i64>),
+    content: Option<&str>,
+) -> (ArticleStatus, Option<i64>) {
+    let (status, published_at) = saving;
+    let current_status = current.status.as_deref().and_then(ArticleStatus::parse);
+    let live = matches!(
+        current_status,
+        Some(ArticleStatus::Published | ArticleStatus::Scheduled)
+    );
+    if live && Some(status) == current_status && !user.role.can(Permission::PublishArticle) {
+        let content_changed = current.content.as_deref() != content;
+        if content_changed || published_at != current.published_at {
+            return (ArticleStatus::Draft, None);
+        }
+    }
+    (status, published_at)
+}
+
+/// 校验文章数据：字段规则、slug 是否被使用及引用的分类、标签是否存在
+async fn check_post_data(
+    data: &PostOrPutArticleData,
+    id: Option<u32>,
+    repos: &Repositories,
+) -> Result<(), AppError> {
+    let mut errors = validation::field_errors(data);
+    slug::check_taken(
+        repos,
+        SlugKind::Article,
+        data.slug.as_deref(),
+        id,
+        &mut errors,
+    )
+    .await?;
+    if data.status.as_deref() == Some(ArticleStatus::Scheduled.as_str()) {
+        match data.published_at {
+            None => errors.push(FieldError::new(
+                "published_at",
+                "required",
+                "Scheduled articles need a publish time.",
+            )),
+            Some(t) if t <= Local::now().timestamp() => errors.push(FieldError::new(
+                "published_at",
+                "in_past",
+                "Publish time must be in the future.",
+            )),
+            Some(_) => {}
+        }
+    }
+    if let Some(cate_id) = data.cate_id {
+        if repos.categories.find_by_id(cate_id).await?.is_none() {
+            errors.push(FieldError::new(
+                "cate_id",
+                "not_found",
+                "Category does not exist.",
+            ));
+        }
+    }
+    if !validation::has_error(&errors, "tags") {
+        let found = repos.tags.find_by_ids(&data.tags).await?;
+        let missing: Vec<String> = data
+            .tags
+            .iter()
+            .filter(|id| !found.iter().any(|t| t.id == Some(**id)))
+            .map(|id| id.to_string())
+            .collect();
+        if !missing.is_empty() {
+            errors.push(FieldError::new(
+                "tags",
+                "not_found",
+                format!("Tags do not exist: {}.", missing.join(", ")),
+            ));
+        }
+    }
+    validation::check(errors)
 }
 
 /// 新增文章
 #[post("/article", data = "<post_data>")]
-pub async fn create(post_data: Json<PostOrPutArticleData>, _t: Token) -> Value {
+pub async fn create(
+    post_data: Json<PostOrPutArticleData>,
+    auth: Authorized<perm::CreateArticle>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    check_post_data(&post_data, None, repos).await?;
+    let (status, published_at) = publication(&post_data, auth.user(), None)?;
+    let slug = slug::resolve(
+        repos,
+        SlugKind::Article,
+        auth.user(),
+        post_data.slug.as_deref(),
+        None,
+        &post_data.title,
+    )
+    .await?;
     let art = Article {
         id: None,
         title: Some(post_data.title.clone()),
+        slug: Some(slug),
         description: Some(post_data.description.clone()),
         content: Some(post_data.content.clone()),
-        cate_id: Some(post_data.cate_id),
+        // 保存时由正文生成
+        content_html: None,
+        toc: None,
+        word_count: None,
+        reading_minutes: None,
+        excerpt: None,
+        cate_id: post_data.cate_id,
+        author_id: Some(auth.user().id),
         istop: Some(false),
+        status: Some(status.as_str().to_string()),
+        published_at,
         created_at: Some(Local::now().timestamp()),
         updated_at: Some(Local::now().timestamp()),
     };
@@ -151,55 +476,62 @@ pub async fn create(post_data: Json<PostOrPutArticleData>, _t: Token) -> Value {
     let tag_ids: Vec<u32> = post_data.tags.clone();
 
     // 执行添加
-    let res = Article::add_article(art, tag_ids).await;
-    match res {
-        Ok(_) => {
-            return json!(RespData {
-                code: 200,
-                msg: "新增文章成功",
-                data: ()
-            });
-        }
-        Err(_) => {
-            return json!(RespData {
-                code: 500,
-                msg: "新增文章失败",
-                data: ()
-            });
-        }
-    }
+    repos.articles.add(art, tag_ids).await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "新增文章成功",
+        data: ()
+    }));
 }
 
 /// 更新文章
 #[put("/article/<id>", data = "<put_data>")]
-pub async fn update(id: u32, put_data: Json<PostOrPutArticleData>, _t: Token) -> Value {
+pub async fn update(
+    id: u32,
+    put_data: Json<PostOrPutArticleData>,
+    auth: Authorized<perm::EditOwnArticle>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    // 只能修改有权限的文章
+    let art = repos
+        .articles
+        .find_by_id(id)
+        .await?
+        .ok_or(ARTICLE_NOT_FOUND)?;
+    if !auth.user().can_edit_article(art.author_id) {
+        return Err(AppError::forbidden());
+    }
+    check_post_data(&put_data, Some(id), repos).await?;
+    let (status, published_at) = publication(&put_data, auth.user(), Some(&art))?;
+    let slug = slug::resolve(
+        repos,
+        SlugKind::Article,
+        auth.user(),
+        put_data.slug.as_deref(),
+        Some((id, art.slug.as_deref())),
+        &put_data.title,
+    )
+    .await?;
+
     let art_edit_obj = ArticleForUpdateVo {
         title: put_data.title.clone(),
+        slug,
         description: put_data.description.clone(),
         content: put_data.content.clone(),
-        cate_id: put_data.cate_id,
+        cate_id: put_data.cate_id.unwrap_or_default(),
         tags: put_data.tags.clone(),
+        status: status.as_str().to_string(),
+        published_at,
+        editor_id: auth.user().id,
     };
 
     // 执行更新
-    let res = Article::update(id, art_edit_obj).await;
-
-    match res {
-        Ok(_) => {
-            return json!(RespData {
-                code: 200,
-                msg: "更新文章成功",
-                data: (),
-            });
-        }
-        Err(_) => {
-            return json!(RespData {
-                code: 500,
-                msg: "更新文章失败",
-                data: (),
-            });
-        }
-    }
+    repos.articles.update(id, art_edit_obj).await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "更新文章成功",
+        data: (),
+    }));
 }
 
 /// 接收文章搜索的查询字符串结构题
@@ -209,51 +541,45 @@ pub struct SearchData {
     category: u32,
 }
 
-/// 文章搜索（后台）
+/// 文章搜索（后台，包括全部状态）
+/// 只返回当前用户可以修改的文章：编辑及管理员为全部文章，作者及投稿者为自己的文章
 #[get("/article/search?<sdata..>")]
-pub async fn admin_search(sdata: SearchData) -> Value {
+pub async fn admin_search(
+    sdata: SearchData,
+    auth: Authorized<perm::EditOwnArticle>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
     let title = sdata.title;
     let category = sdata.category;
 
-    let res = Article::admin_search(title, category).await;
-
-    match res {
-        Ok(arts) => {
-            return json!(RespData {
-                code: 200,
-                msg: "Success",
-                data: arts,
-            });
-        }
-        Err(_) => {
-            return json!(RespData {
-                code: 500,
-                msg: "Failed",
-                data: (),
-            });
-        }
-    }
+    let arts: Vec<Article> = repos
+        .articles
+        .admin_search(title, category)
+        .await?
+        .into_iter()
+        .filter(|art| auth.user().can_edit_article(art.author_id))
+        .map(Article::into_summary)
+        .collect();
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Success",
+        data: arts,
+    }));
 }
 
 /// 文章搜索（前台）
 #[get("/article/search/<keyword>")]
-pub async fn search(keyword: &str) -> Value {
-    let res = Article::search(keyword).await;
-
-    match res {
-        Ok(arts) => {
-            return json!(RespData {
-                code: 200,
-                msg: "Success",
-                data: arts,
-            });
-        }
-        Err(_) => {
-            return json!(RespData {
-                code: 500,
-                msg: "Failed",
-                data: (),
-            });
-        }
-    }
-}
\ No newline at end of file
+pub async fn search(keyword: &str, repos: &State<Repositories>) -> Result<Value, AppError> {
+    let arts: Vec<Article> = repos
+        .articles
+        .search(keyword)
+        .await?
+        .into_iter()
+        .map(Article::into_summary)
+        .collect();
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Success",
+        data: arts,
+    }));
+}
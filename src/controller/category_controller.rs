@@ -1,132 +1,237 @@
+use rocket::response::Redirect;
 use rocket::serde::json::{serde_json::json, Json, Value};
-use rocket::{delete, get, post, put};
+use rocket::{delete, get, post, put, uri, State};
 use serde::Deserialize;
+use validator::Validate;
 
+use crate::model::article::Article;
 use crate::model::category::Category;
-use crate::response::resp_obj::RespData;
-use crate::util::token::Token;
+use crate::model::slug_history::SlugKind;
+use crate::repository::Repositories;
+use crate::response::error::{AppError, FieldError};
+use crate::response::resp_obj::{RespData, SlugResp};
+use crate::util::permission::{perm, Authorized};
+use crate::util::slug::{self, slug_format};
+use crate::util::validation::{self, not_blank};
+
+/// 分类不存在
+const CATEGORY_NOT_FOUND: AppError = AppError::NotFound {
+    error: "category_not_found",
+    msg: "Category not found.",
+};
 
 /// 全部分类
 #[get("/categories")]
-pub async fn list() -> Value {
+pub async fn list(repos: &State<Repositories>) -> Result<Value, AppError> {
     // 查询全部分类（带博客数量）
-    let catevos = Category::find_all_with_blogcount().await;
-    if let Ok(cates) = catevos {
-        return json!(RespData {
-            code: 200,
-            msg: "Success",
-            data: cates
-        });
-    }
-    return json!(());
+    let cates = repos.categories.find_all_with_blogcount().await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Success",
+        data: cates
+    }));
 }
 
 /// 分类详情
 #[get("/category/<id>")]
-pub async fn detail(id: &str) -> Value {
-    let cate = Category::find_by_id(id).await;
-    if let Ok(oc) = cate {
-        return json!(RespData::<Option<Category>> {
+pub async fn detail(id: u32, repos: &State<Repositories>) -> Result<Value, AppError> {
+    let cate = repos
+        .categories
+        .find_by_id(id)
+        .await?
+        .ok_or(CATEGORY_NOT_FOUND)?;
+    return Ok(json!(RespData::<Category> {
+        code: 200,
+        msg: "Success",
+        data: cate
+    }));
+}
+
+/// 获取分类下文章列表
+#[get("/category/<cid>/artlist")]
+pub async fn cate_artlist(cid: u32, repos: &State<Repositories>) -> Result<Value, AppError> {
+    if repos.categories.find_by_id(cid).await?.is_none() {
+        return Err(CATEGORY_NOT_FOUND);
+    }
+    let arts: Vec<Article> = repos
+        .categories
+        .find_articles(cid)
+        .await?
+        .into_iter()
+        .map(Article::into_summary)
+        .collect();
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Success",
+        data: arts
+    }));
+}
+
+/// 按 slug 查询分类详情，旧 slug 永久重定向到当前 slug
+/// 与 /category/<cid>/artlist 形状相同，通过 rank 降低优先级避免冲突
+#[get("/category/slug/<slug>", rank = 2)]
+pub async fn detail_by_slug(slug: &str, repos: &State<Repositories>) -> Result<SlugResp, AppError> {
+    if let Some(cate) = repos.categories.find_by_slug(slug).await? {
+        return Ok(SlugResp::Found(json!(RespData {
             code: 200,
             msg: "Success",
-            data: oc
-        });
+            data: cate
+        })));
     }
-    return json!(());
+    let current = slug::redirect_target(repos, SlugKind::Category, slug)
+        .await?
+        .ok_or(CATEGORY_NOT_FOUND)?;
+    Ok(SlugResp::Moved(Redirect::moved(uri!(
+        "/api",
+        detail_by_slug(current)
+    ))))
 }
 
-/// 获取分类下文章列表
-#[get("/category/<cid>/artlist")]
-pub async fn cate_artlist(cid: u32) -> Value {
-    let articles = Category::find_articles_by_cateid(&cid.to_string()).await;
-    if let Ok(arts) = articles {
-        return json!(RespData {
+/// 按 slug 获取分类下文章列表，旧 slug 永久重定向到当前 slug
+#[get("/category/slug/<slug>/artlist")]
+pub async fn cate_artlist_by_slug(
+    slug: &str,
+    repos: &State<Repositories>,
+) -> Result<SlugResp, AppError> {
+    if let Some(cate) = repos.categories.find_by_slug(slug).await? {
+        let arts: Vec<Article> = repos
+            .categories
+            .find_articles(cate.id.unwrap_or_default())
+            .await?
+            .into_iter()
+            .map(Article::into_summary)
+            .collect();
+        return Ok(SlugResp::Found(json!(RespData {
             code: 200,
             msg: "Success",
             data: arts
-        });
+        })));
     }
-    return json!(());
+    let current = slug::redirect_target(repos, SlugKind::Category, slug)
+        .await?
+        .ok_or(CATEGORY_NOT_FOUND)?;
+    Ok(SlugResp::Moved(Redirect::moved(uri!(
+        "/api",
+        cate_artlist_by_slug(current)
+    ))))
 }
 
 /// 删除分类
 #[delete("/categories/<id>")]
-pub async fn delete(id: &str, _t: Token) -> Value {
-    let res = Category::remove(id).await;
-    match res {
-        Ok(_) => {
-            return json!(RespData {
-                code: 200,
-                msg: "删除分类成功",
-                data: ()
-            });
-        }
-        Err(_) => {
-            return json!(RespData {
-                code: 500,
-                msg: "删除分类失败",
-                data: ()
-            });
-        }
+pub async fn delete(
+    id: u32,
+    _auth: Authorized<perm::ManageTaxonomy>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    if repos.categories.find_by_id(id).await?.is_none() {
+        return Err(CATEGORY_NOT_FOUND);
     }
+    repos.categories.remove(id).await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "删除分类成功",
+        data: ()
+    }));
 }
 
 /// 接收前端 post/put 提交的数据
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate, Default)]
+#[serde(default)]
 pub struct PostData<'a> {
+    #[validate(custom = "not_blank", length(max = 64, code = "too_long"))]
     pub name: &'a str,
+    /// 网址 slug，只有管理员可以指定或修改；未提交时新增由名称生成，更新保持原 slug
+    #[validate(custom = "slug_format")]
+    pub slug: Option<String>,
+}
+
+/// 校验分类数据：字段规则及名称唯一（更新时排除自身）
+async fn check_post_data(
+    data: &PostData<'_>,
+    id: Option<u32>,
+    repos: &Repositories,
+) -> Result<(), AppError> {
+    let mut errors = validation::field_errors(data);
+    slug::check_taken(
+        repos,
+        SlugKind::Category,
+        data.slug.as_deref(),
+        id,
+        &mut errors,
+    )
+    .await?;
+    if !validation::has_error(&errors, "name") {
+        if let Some(existing) = repos.categories.find_by_name(data.name.trim()).await? {
+            if existing.id != id {
+                errors.push(FieldError::new(
+                    "name",
+                    "taken",
+                    "Category name already exists.",
+                ));
+            }
+        }
+    }
+    validation::check(errors)
 }
 
 /// 新增分类
 #[post("/categories", data = "<post_data>")]
-pub async fn create(post_data: Json<PostData<'_>>, _t: Token) -> Value {
-    let name = post_data.name;
-    let create_res = Category::create(name).await;
-    if let Ok(res) = create_res {
-        // 受影响行数
-        let affected_row = res.rows_affected;
-        // println!("{}", affected_row);
-        // 返回的 id
-        // let id = res.last_insert_id.unwrap();
-        if affected_row > 0 {
-            return json!(RespData {
-                code: 200,
-                msg: "新增分类成功",
-                data: ()
-            });
-        }
-        return json!(RespData {
-            code: 500,
-            msg: "新增分类失败",
-            data: ()
-        });
+pub async fn create(
+    post_data: Json<PostData<'_>>,
+    auth: Authorized<perm::ManageTaxonomy>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    check_post_data(&post_data, None, repos).await?;
+    let name = post_data.name.trim();
+    let slug = slug::resolve(
+        repos,
+        SlugKind::Category,
+        auth.user(),
+        post_data.slug.as_deref(),
+        None,
+        name,
+    )
+    .await?;
+    let res = repos.categories.create(name, &slug).await?;
+    // 受影响行数
+    if res.rows_affected == 0 {
+        return Err(AppError::Internal("category was not inserted".to_string()));
     }
-    return json!(RespData {
-        code: 500,
-        msg: "新增分类失败",
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "新增分类成功",
         data: ()
-    });
+    }));
 }
 
 /// 更新分类
 #[put("/categories/<id>", data = "<put_data>")]
-pub async fn update(id: &str, put_data: Json<PostData<'_>>, _t: Token) -> Value {
-    let name = put_data.name;
-    let res = Category::update(id, name).await;
-    match res {
-        Ok(_) => {
-            return json!(RespData {
-                code: 200,
-                msg: "更新分类成功",
-                data: ()
-            });
-        }
-        Err(_) => { 
-            return json!(RespData {
-                code: 500,
-                msg: "更新分类失败",
-                data: ()
-            });
-        }
-    }
-}
\ No newline at end of file
+pub async fn update(
+    id: u32,
+    put_data: Json<PostData<'_>>,
+    auth: Authorized<perm::ManageTaxonomy>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    let cate = repos
+        .categories
+        .find_by_id(id)
+        .await?
+        .ok_or(CATEGORY_NOT_FOUND)?;
+    check_post_data(&put_data, Some(id), repos).await?;
+    let name = put_data.name.trim();
+    let slug = slug::resolve(
+        repos,
+        SlugKind::Category,
+        auth.user(),
+        put_data.slug.as_deref(),
+        Some((id, cate.slug.as_deref())),
+        name,
+    )
+    .await?;
+    repos.categories.update(id, name, &slug).await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "更新分类成功",
+        data: ()
+    }));
+}
@@ -1,4 +1,6 @@
 pub mod article_controller;
 pub mod category_controller;
+pub mod revision_controller;
 pub mod tag_controller;
+pub mod two_factor_controller;
 pub mod user_controller;
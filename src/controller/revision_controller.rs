@@ -0,0 +1,142 @@
+use rocket::serde::json::{serde_json::json, Value};
+use rocket::{get, post, State};
+
+use super::article_controller::{review_live_edit, ARTICLE_NOT_FOUND};
+use crate::model::article::{Article, ArticleStatus};
+use crate::repository::Repositories;
+use crate::response::error::AppError;
+use crate::response::resp_obj::RespData;
+use crate::util::diff::line_diff;
+use crate::util::permission::{perm, Authorized};
+use crate::util::token::AuthUser;
+
+/// 版本不存在
+const REVISION_NOT_FOUND: AppError = AppError::NotFound {
+    error: "revision_not_found",
+    msg: "Revision not found.",
+};
+
+/// 查询文章，只能查看有权限修改的文章的版本
+async fn editable_article(
+    id: u32,
+    user: &AuthUser,
+    repos: &Repositories,
+) -> Result<Article, AppError> {
+    let art = repos
+        .articles
+        .find_by_id(id)
+        .await?
+        .ok_or(ARTICLE_NOT_FOUND)?;
+    if !user.can_edit_article(art.author_id) {
+        return Err(AppError::forbidden());
+    }
+    Ok(art)
+}
+
+// 以下路由与 /article/edit/<id>、/article/search/<keyword> 形状相同，通过 rank 降低优先级避免冲突
+
+/// 文章的版本列表（不含正文，最新的在前）
+#[get("/article/<id>/revisions", rank = 2)]
+pub async fn list(
+    id: u32,
+    auth: Authorized<perm::EditOwnArticle>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    editable_article(id, auth.user(), repos).await?;
+    let revisions = repos.articles.revisions(id).await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Success",
+        data: revisions,
+    }));
+}
+
+/// 版本详情
+#[get("/article/<id>/revisions/<revision>", rank = 3)]
+pub async fn detail(
+    id: u32,
+    revision: u32,
+    auth: Authorized<perm::EditOwnArticle>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    editable_article(id, auth.user(), repos).await?;
+    let revision = repos
+        .articles
+        .find_revision(id, revision)
+        .await?
+        .ok_or(REVISION_NOT_FOUND)?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Success",
+        data: revision,
+    }));
+}
+
+/// 比较两个版本：标题、描述及正文的逐行差异
+#[get("/article/<id>/revisions/diff?<from>&<to>", rank = 2)]
+pub async fn diff(
+    id: u32,
+    from: u32,
+    to: u32,
+    auth: Authorized<perm::EditOwnArticle>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    editable_article(id, auth.user(), repos).await?;
+    let old = repos
+        .articles
+        .find_revision(id, from)
+        .await?
+        .ok_or(REVISION_NOT_FOUND)?;
+    let new = repos
+        .articles
+        .find_revision(id, to)
+        .await?
+        .ok_or(REVISION_NOT_FOUND)?;
+    let text = |s: &Option<String>| s.clone().unwrap_or_default();
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Success",
+        data: json!({
+            "from": old.summary(),
+            "to": new.summary(),
+            "title": line_diff(&text(&old.title), &text(&new.title)),
+            "description": line_diff(&text(&old.description), &text(&new.description)),
+            "content": line_diff(&text(&old.content), &text(&new.content)),
+        }),
+    }));
+}
+
+/// 恢复版本：用该版本的标题、描述及正文覆盖文章，并记录为新版本
+/// 与修改文章相同，没有发布权限的用户恢复已发布文章的版本时文章改回草稿
+#[post("/article/<id>/revisions/<revision>/restore")]
+pub async fn restore(
+    id: u32,
+    revision: u32,
+    auth: Authorized<perm::EditOwnArticle>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    let art = editable_article(id, auth.user(), repos).await?;
+    let revision = repos
+        .articles
+        .find_revision(id, revision)
+        .await?
+        .ok_or(REVISION_NOT_FOUND)?;
+    let current = (
+        art.status
+            .as_deref()
+            .and_then(ArticleStatus::parse)
+            .unwrap_or(ArticleStatus::Draft),
+        art.published_at,
+    );
+    let (status, published_at) =
+        review_live_edit(auth.user(), &art, current, revision.content.as_deref());
+    repos
+        .articles
+        .restore_revision(id, revision, auth.user().id, status, published_at)
+        .await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "恢复版本成功",
+        data: (),
+    }));
+}
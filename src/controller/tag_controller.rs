@@ -1,115 +1,186 @@
-use crate::model::tag::Tag;
-use crate::util::token::Token;
+use crate::model::article::Article;
+use crate::model::slug_history::SlugKind;
+use crate::repository::Repositories;
+use crate::response::error::{AppError, FieldError};
+use crate::util::permission::{perm, Authorized};
+use crate::util::slug::{self, slug_format};
+use crate::util::validation::{self, not_blank};
+use rocket::response::Redirect;
 use rocket::serde::json::Json;
 use rocket::serde::json::{serde_json::json, Value};
-use rocket::{delete, get, post, put};
+use rocket::{delete, get, post, put, uri, State};
 use serde::Deserialize;
+use validator::Validate;
 
-use crate::response::resp_obj::RespData;
+use crate::response::resp_obj::{RespData, SlugResp};
+
+/// 标签不存在
+const TAG_NOT_FOUND: AppError = AppError::NotFound {
+    error: "tag_not_found",
+    msg: "Tag not found.",
+};
 
 /// 全部标签
 #[get("/tags")]
-pub async fn list() -> Value {
-    let tags = Tag::find_all_with_blogcount().await;
-    if let Ok(ts) = tags {
-        return json!(RespData {
-            code: 200,
-            msg: "Success",
-            data: ts,
-        });
-    }
-    return json!(());
+pub async fn list(repos: &State<Repositories>) -> Result<Value, AppError> {
+    let ts = repos.tags.find_all_with_blogcount().await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Success",
+        data: ts,
+    }));
 }
 
 /// 某个标签下文章列表
 #[get("/tag/<tid>/articles")]
-pub async fn tag_articles(tid: u32) -> Value {
-    let articles = Tag::find_articles_by_tagid(&tid.to_string()).await;
-    if let Ok(arts) = articles {
-        return json!(RespData {
+pub async fn tag_articles(tid: u32, repos: &State<Repositories>) -> Result<Value, AppError> {
+    if repos.tags.find_by_id(tid).await?.is_none() {
+        return Err(TAG_NOT_FOUND);
+    }
+    let arts: Vec<Article> = repos
+        .tags
+        .find_articles(tid)
+        .await?
+        .into_iter()
+        .map(Article::into_summary)
+        .collect();
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Success",
+        data: arts,
+    }));
+}
+
+/// 按 slug 获取标签下文章列表，旧 slug 永久重定向到当前 slug
+#[get("/tag/slug/<slug>/articles")]
+pub async fn tag_articles_by_slug(
+    slug: &str,
+    repos: &State<Repositories>,
+) -> Result<SlugResp, AppError> {
+    if let Some(tag) = repos.tags.find_by_slug(slug).await? {
+        let arts: Vec<Article> = repos
+            .tags
+            .find_articles(tag.id.unwrap_or_default())
+            .await?
+            .into_iter()
+            .map(Article::into_summary)
+            .collect();
+        return Ok(SlugResp::Found(json!(RespData {
             code: 200,
             msg: "Success",
             data: arts,
-        });
+        })));
     }
-    return json!(());
+    let current = slug::redirect_target(repos, SlugKind::Tag, slug)
+        .await?
+        .ok_or(TAG_NOT_FOUND)?;
+    Ok(SlugResp::Moved(Redirect::moved(uri!(
+        "/api",
+        tag_articles_by_slug(current)
+    ))))
 }
 
 /// 接收前端post/put提交的数据
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate, Default)]
+#[serde(default)]
 pub struct PostData<'a> {
+    #[validate(custom = "not_blank", length(max = 64, code = "too_long"))]
     pub name: &'a str,
+    /// 网址 slug，只有管理员可以指定或修改；未提交时新增由名称生成，更新保持原 slug
+    #[validate(custom = "slug_format")]
+    pub slug: Option<String>,
+}
+
+/// 校验标签数据：字段规则及名称唯一（更新时排除自身）
+async fn check_post_data(
+    data: &PostData<'_>,
+    id: Option<u32>,
+    repos: &Repositories,
+) -> Result<(), AppError> {
+    let mut errors = validation::field_errors(data);
+    slug::check_taken(repos, SlugKind::Tag, data.slug.as_deref(), id, &mut errors).await?;
+    if !validation::has_error(&errors, "name") {
+        if let Some(existing) = repos.tags.find_by_name(data.name.trim()).await? {
+            if existing.id != id {
+                errors.push(FieldError::new("name", "taken", "Tag name already exists."));
+            }
+        }
+    }
+    validation::check(errors)
 }
 
 /// 新增标签
 #[post("/tags", data = "<post_data>")]
-pub async fn create(post_data: Json<PostData<'_>>, _t: Token) -> Value {
-    let name = post_data.name;
-    let tag_res = Tag::create(name).await;
-    if let Ok(res) = tag_res {
-        // 受影响行数
-        let affected_rows = res.rows_affected;
-        if affected_rows > 0 {
-            return json!(RespData {
-                code: 200,
-                msg: "新增标签成功",
-                data: (),
-            });
-        }
-        return json!(RespData {
-            code: 500,
-            msg: "新增标签失败",
-            data: (),
-        });
+pub async fn create(
+    post_data: Json<PostData<'_>>,
+    auth: Authorized<perm::ManageTaxonomy>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    check_post_data(&post_data, None, repos).await?;
+    let name = post_data.name.trim();
+    let slug = slug::resolve(
+        repos,
+        SlugKind::Tag,
+        auth.user(),
+        post_data.slug.as_deref(),
+        None,
+        name,
+    )
+    .await?;
+    let res = repos.tags.create(name, &slug).await?;
+    // 受影响行数
+    if res.rows_affected == 0 {
+        return Err(AppError::Internal("tag was not inserted".to_string()));
     }
-    return json!(RespData {
-        code: 500,
-        msg: "新增标签失败",
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "新增标签成功",
         data: (),
-    });
+    }));
 }
 
 /// 更新标签
 #[put("/tags/<id>", data = "<put_data>")]
-pub async fn update(id: u32, put_data: Json<PostData<'_>>, _t: Token) -> Value {
-    let name = put_data.name;
-    let res = Tag::update(id, name).await;
-    match res {
-        Ok(_) => {
-            return json!(RespData {
-                code: 200,
-                msg: "更新标签成功",
-                data: (),
-            });
-        }
-        Err(_) => {
-            return json!(RespData {
-                code: 500,
-                msg: "更新标签失败",
-                data: (),
-            });
-        }
-    }
+pub async fn update(
+    id: u32,
+    put_data: Json<PostData<'_>>,
+    auth: Authorized<perm::ManageTaxonomy>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    let tag = repos.tags.find_by_id(id).await?.ok_or(TAG_NOT_FOUND)?;
+    check_post_data(&put_data, Some(id), repos).await?;
+    let name = put_data.name.trim();
+    let slug = slug::resolve(
+        repos,
+        SlugKind::Tag,
+        auth.user(),
+        put_data.slug.as_deref(),
+        Some((id, tag.slug.as_deref())),
+        name,
+    )
+    .await?;
+    repos.tags.update(id, name, &slug).await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "更新标签成功",
+        data: (),
+    }));
 }
 
 /// 删除标签
 #[delete("/tags/<id>")]
-pub async fn remove(id: u32, _t: Token) -> Value {
-    let res = Tag::remove(id).await;
-    match res {
-        Ok(_) => {
-            return json!(RespData {
-                code: 200,
-                msg: "删除标签成功",
-                data: (),
-            });
-        }
-        Err(_) => {
-            return json!(RespData {
-                code: 500,
-                msg: "删除标签失败",
-                data: (),
-            });
-        }
+pub async fn remove(
+    id: u32,
+    _auth: Authorized<perm::ManageTaxonomy>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    if repos.tags.find_by_id(id).await?.is_none() {
+        return Err(TAG_NOT_FOUND);
     }
+    repos.tags.remove(id).await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "删除标签成功",
+        data: (),
+    }));
 }
@@ -0,0 +1,309 @@
+use std::net::IpAddr;
+
+use rocket::serde::json::{serde_json::json, Json, Value};
+use rocket::serde::{Deserialize, Serialize};
+use rocket::{delete, get, post, State};
+
+use crate::model::user::User;
+use crate::repository::Repositories;
+use crate::response::error::AppError;
+use crate::response::resp_obj::RespData;
+use crate::util::permission::{perm, Authorized};
+use crate::util::throttle::LoginThrottle;
+use crate::util::token::Token;
+use crate::util::totp;
+
+/// 每次生成的恢复码数量
+const RECOVERY_CODE_COUNT: usize = 10;
+
+/// 两步验证状态
+#[derive(Serialize)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    /// 剩余可用的恢复码数量
+    pub recovery_codes_remaining: u64,
+}
+
+/// 开启两步验证时返回的密钥
+#[derive(Serialize)]
+pub struct SetupResData {
+    /// base32 密钥，供无法扫码时手动输入
+    pub secret: String,
+    /// 供验证器应用扫码的 otpauth URI
+    pub otpauth_uri: String,
+}
+
+/// 恢复码（只在生成时返回一次）
+#[derive(Serialize)]
+pub struct RecoveryCodesData {
+    pub recovery_codes: Vec<String>,
+}
+
+/// 提交的动态码
+#[derive(Deserialize)]
+pub struct CodeData<'a> {
+    /// 动态码或恢复码
+    pub code: &'a str,
+}
+
+/// 关闭两步验证提交的数据
+#[derive(Deserialize)]
+pub struct DisableData<'a> {
+    pub password: &'a str,
+    /// 动态码或恢复码
+    pub code: &'a str,
+}
+
+/// 校验第二因素：动态码（同一时间步只能使用一次）或未使用过的恢复码
+/// ---
+/// @parameter  repos       &Repositories
+/// @parameter  user        &User
+/// @parameter  code        &str
+/// @return     Result<bool, rbatis::Error>
+/// ---
+pub async fn verify_second_factor(
+    repos: &Repositories,
+    user: &User,
+    code: &str,
+) -> Result<bool, rbatis::Error> {
+    let id = user.id.unwrap_or_default();
+    let secret = user.totp_secret.as_deref().unwrap_or_default();
+    if let Some(step) = totp::verify(secret, code, user.totp_last_step) {
+        return repos.users.accept_totp_step(id, step).await;
+    }
+    repos.users.consume_recovery_code(id, code).await
+}
+
+/// 生成新的恢复码并替换旧的恢复码
+async fn regenerate_recovery_codes(
+    repos: &Repositories,
+    user_id: u32,
+) -> Result<Vec<String>, rbatis::Error> {
+    let codes = totp::generate_recovery_codes(RECOVERY_CODE_COUNT);
+    repos.users.replace_recovery_codes(user_id, &codes).await?;
+    Ok(codes)
+}
+
+/// 两步验证码错误
+const INVALID_CODE: AppError = AppError::BadRequest {
+    error: "invalid_code",
+    msg: "Invalid verification code.",
+};
+
+/// 已开启两步验证
+const ALREADY_ENABLED: AppError = AppError::Conflict {
+    error: "two_factor_already_enabled",
+    msg: "Two-factor authentication is already enabled.",
+    details: None,
+};
+
+/// 未开启两步验证
+const NOT_ENABLED: AppError = AppError::BadRequest {
+    error: "two_factor_not_enabled",
+    msg: "Two-factor authentication is not enabled.",
+};
+
+/// 查询当前登录用户（令牌校验之后被删除时视为令牌无效）
+async fn current_user(repos: &Repositories, t: &Token) -> Result<User, AppError> {
+    repos
+        .users
+        .find_by_id(t.user.id)
+        .await?
+        .ok_or(AppError::unauthorized("invalid_token", "Invalid token."))
+}
+
+/// 已登录用户的校验与登录共用失败计数：限流中直接拒绝
+pub(crate) async fn check_throttle(
+    repos: &Repositories,
+    throttle: &LoginThrottle,
+    username: &str,
+    ip: Option<IpAddr>,
+) -> Result<(), AppError> {
+    if let Err(retry_after) = throttle.check(username, ip) {
+        let ip_str = ip.map(|i| i.to_string());
+        let _ = repos
+            .users
+            .record_login_event(username, ip_str, "throttled")
+            .await;
+        return Err(AppError::TooManyRequests { retry_after });
+    }
+    Ok(())
+}
+
+/// 记录一次密码或动态码校验失败，返回对应的错误
+pub(crate) async fn record_failure(
+    repos: &Repositories,
+    throttle: &LoginThrottle,
+    username: &str,
+    ip: Option<IpAddr>,
+    event: &str,
+    err: AppError,
+) -> AppError {
+    let ip_str = ip.map(|i| i.to_string());
+    let locked = throttle.record_failure(username, ip);
+    let _ = repos
+        .users
+        .record_login_event(username, ip_str.clone(), event)
+        .await;
+    if locked {
+        let _ = repos
+            .users
+            .record_login_event(username, ip_str, "locked")
+            .await;
+    }
+    err
+}
+
+/// 两步验证状态
+#[get("/admin/2fa")]
+pub async fn status(t: Token, repos: &State<Repositories>) -> Result<Value, AppError> {
+    let user = current_user(repos, &t).await?;
+    let remaining = repos.users.remaining_recovery_codes(t.user.id).await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Success",
+        data: TwoFactorStatus {
+            enabled: user.two_factor_enabled(),
+            recovery_codes_remaining: remaining,
+        },
+    }));
+}
+
+/// 开始开启两步验证：生成新密钥，确认动态码后才生效
+#[post("/admin/2fa/setup")]
+pub async fn setup(t: Token, repos: &State<Repositories>) -> Result<Value, AppError> {
+    let user = current_user(repos, &t).await?;
+    if user.two_factor_enabled() {
+        return Err(ALREADY_ENABLED);
+    }
+
+    let secret = totp::generate_secret();
+    let otpauth_uri = totp::otpauth_uri(&secret, &t.user.username).unwrap_or_default();
+    repos.users.set_pending_totp(t.user.id, &secret).await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Scan the otpauth uri and confirm with a code.",
+        data: SetupResData {
+            secret,
+            otpauth_uri,
+        },
+    }));
+}
+
+/// 确认动态码并开启两步验证，返回恢复码
+#[post("/admin/2fa/enable", data = "<post_data>")]
+pub async fn enable(
+    post_data: Json<CodeData<'_>>,
+    t: Token,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    let user = current_user(repos, &t).await?;
+    if user.two_factor_enabled() {
+        return Err(ALREADY_ENABLED);
+    }
+    let secret = match user.totp_pending_secret {
+        Some(s) if !s.is_empty() => s,
+        _ => {
+            return Err(AppError::bad_request(
+                "two_factor_setup_required",
+                "Call /admin/2fa/setup first.",
+            ));
+        }
+    };
+    let step = totp::verify(&secret, post_data.code, None).ok_or(INVALID_CODE)?;
+
+    repos.users.enable_totp(t.user.id, &secret, step).await?;
+    let recovery_codes = regenerate_recovery_codes(repos, t.user.id).await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Two-factor authentication enabled, store the recovery codes safely.",
+        data: RecoveryCodesData { recovery_codes },
+    }));
+}
+
+/// 关闭两步验证（需校验密码和动态码）
+#[post("/admin/2fa/disable", data = "<post_data>")]
+pub async fn disable(
+    post_data: Json<DisableData<'_>>,
+    t: Token,
+    ip: Option<IpAddr>,
+    throttle: &State<LoginThrottle>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    let user = current_user(repos, &t).await?;
+    if !user.two_factor_enabled() {
+        return Err(NOT_ENABLED);
+    }
+
+    let username = &t.user.username;
+    check_throttle(repos, throttle, username, ip).await?;
+    if !repos
+        .users
+        .verify_password(t.user.id, post_data.password)
+        .await?
+    {
+        let err = AppError::bad_request("invalid_password", "Password error.");
+        return Err(record_failure(repos, throttle, username, ip, "failed", err).await);
+    }
+    if !verify_second_factor(repos, &user, post_data.code).await? {
+        return Err(
+            record_failure(repos, throttle, username, ip, "2fa_failed", INVALID_CODE).await,
+        );
+    }
+    throttle.record_success(username);
+
+    repos.users.disable_totp(t.user.id).await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Two-factor authentication disabled.",
+        data: (),
+    }));
+}
+
+/// 重新生成恢复码，旧的恢复码全部作废
+#[post("/admin/2fa/recovery-codes", data = "<post_data>")]
+pub async fn recovery_codes(
+    post_data: Json<CodeData<'_>>,
+    t: Token,
+    ip: Option<IpAddr>,
+    throttle: &State<LoginThrottle>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    let user = current_user(repos, &t).await?;
+    if !user.two_factor_enabled() {
+        return Err(NOT_ENABLED);
+    }
+
+    let username = &t.user.username;
+    check_throttle(repos, throttle, username, ip).await?;
+    if !verify_second_factor(repos, &user, post_data.code).await? {
+        return Err(
+            record_failure(repos, throttle, username, ip, "2fa_failed", INVALID_CODE).await,
+        );
+    }
+    throttle.record_success(username);
+    let recovery_codes = regenerate_recovery_codes(repos, t.user.id).await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Recovery codes regenerated.",
+        data: RecoveryCodesData { recovery_codes },
+    }));
+}
+
+/// 关闭用户的两步验证（管理员），用于用户丢失验证器且没有恢复码时
+#[delete("/user/<id>/2fa")]
+pub async fn reset(
+    id: u32,
+    _auth: Authorized<perm::ManageUsers>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    if repos.users.find_by_id(id).await?.is_none() {
+        return Err(AppError::not_found("user_not_found", "User not found."));
+    }
+    repos.users.disable_totp(id).await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Two-factor authentication disabled.",
+        data: (),
+    }));
+}
@@ -1,10 +1,24 @@
+use std::net::IpAddr;
+
 use rocket::serde::json::{serde_json::json, Json, Value};
 use rocket::serde::{Deserialize, Serialize};
-use rocket::{delete, get, post};
+use rocket::{delete, get, post, put, State};
+use validator::{Validate, ValidationError};
 
-use crate::model::user::User;
+use crate::controller::two_factor_controller::{
+    check_throttle, record_failure, verify_second_factor,
+};
+use crate::model::refresh_token::Rotation;
+use crate::model::user::{NewUser, User, UserProfile};
+use crate::repository::Repositories;
+use crate::response::error::{AppError, FieldError};
 use crate::response::resp_obj::RespData;
-use crate::util::token::{create_token, Token};
+use crate::util::permission::{perm, Authorized, Permission, Role};
+use crate::util::throttle::{ip_key, user_key, LoginThrottle};
+use crate::util::token::{
+    create_challenge_token, create_token, verify_challenge_token, Token, TOKEN_CONFIG,
+};
+use crate::util::validation::{self, email_or_empty, http_url_or_empty, not_blank};
 
 /// 接收前端post提交的用户名和密码
 #[derive(Deserialize)]
@@ -18,83 +32,649 @@ pub struct PostData<'a> {
 pub struct LoginResData {
     pub username: String,
     pub token: String,
+    pub refresh_token: String,
+    pub expires_in: u64,
+}
+
+/// 已开启两步验证时的登录响应数据
+#[derive(Serialize)]
+pub struct TwoFactorChallengeData {
+    pub username: String,
+    pub two_factor_required: bool,
+    /// 挑战令牌，有效期 5 分钟
+    pub challenge_token: String,
+}
+
+/// 两步验证登录提交的数据
+#[derive(Deserialize)]
+pub struct TwoFactorLoginData<'a> {
+    pub challenge_token: &'a str,
+    /// 动态码或恢复码
+    pub code: &'a str,
+}
+
+/// 新增用户提交的数据
+#[derive(Deserialize, Validate, Default)]
+#[serde(default)]
+pub struct CreateUserData {
+    #[validate(custom = "username_format")]
+    pub username: String,
+    #[validate(length(min = "MIN_PASSWORD_LEN", code = "too_short"))]
+    pub password: String,
+    #[validate(custom = "known_role")]
+    pub role: String,
+    #[validate(custom = "not_blank", length(max = 32, code = "too_long"))]
+    pub nickname: Option<String>,
+    #[validate(length(max = 255, code = "too_long"))]
+    pub avatar: Option<String>,
+    #[validate(length(max = 500, code = "too_long"))]
+    pub bio: Option<String>,
+    #[validate(custom = "email_or_empty", length(max = 128, code = "too_long"))]
+    pub email: Option<String>,
+    #[validate(custom = "http_url_or_empty", length(max = 255, code = "too_long"))]
+    pub website: Option<String>,
+}
+
+/// 修改用户资料提交的数据，未提交的字段保持不变
+#[derive(Deserialize, Validate, Default)]
+#[serde(default)]
+pub struct UpdateUserData {
+    #[validate(custom = "not_blank", length(max = 32, code = "too_long"))]
+    pub nickname: Option<String>,
+    #[validate(length(max = 255, code = "too_long"))]
+    pub avatar: Option<String>,
+    #[validate(length(max = 500, code = "too_long"))]
+    pub bio: Option<String>,
+    #[validate(custom = "email_or_empty", length(max = 128, code = "too_long"))]
+    pub email: Option<String>,
+    #[validate(custom = "http_url_or_empty", length(max = 255, code = "too_long"))]
+    pub website: Option<String>,
+    /// 仅管理员可以修改
+    #[validate(custom = "known_role")]
+    pub role: Option<String>,
+}
+
+/// 修改密码提交的数据
+#[derive(Deserialize, Validate, Default)]
+#[serde(default)]
+pub struct ChangePasswordData<'a> {
+    pub old_password: &'a str,
+    #[validate(length(min = "MIN_PASSWORD_LEN", code = "too_short"))]
+    pub new_password: &'a str,
+}
+
+/// 重置密码提交的数据
+#[derive(Deserialize, Validate, Default)]
+#[serde(default)]
+pub struct ResetPasswordData<'a> {
+    #[validate(length(min = "MIN_PASSWORD_LEN", code = "too_short"))]
+    pub new_password: &'a str,
+}
+
+/// 密码最小长度
+pub(crate) const MIN_PASSWORD_LEN: usize = 8;
+
+/// 校验用户名：3-32 位字母、数字、下划线、点或短横线
+pub(crate) fn valid_username(username: &str) -> bool {
+    (3..=32).contains(&username.chars().count())
+        && username
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+/// 用户不存在
+const USER_NOT_FOUND: AppError = AppError::NotFound {
+    error: "user_not_found",
+    msg: "User not found.",
+};
+
+/// 保留至少一个管理员：target 为管理员且是最后一个管理员时拒绝
+async fn ensure_other_admin(repos: &Repositories, target: &User) -> Result<(), AppError> {
+    if target.role.as_deref() == Some(Role::Admin.as_str())
+        && repos.users.count_admins().await? <= 1
+    {
+        return Err(AppError::conflict(
+            "last_admin",
+            "Cannot remove the last admin.",
+        ));
+    }
+    Ok(())
+}
+
+/// 用户名格式
+fn username_format(username: &str) -> Result<(), ValidationError> {
+    if !valid_username(username) {
+        return Err(validation::invalid(
+            "invalid_format",
+            "Username must be 3-32 letters, digits, '_', '.' or '-'.",
+        ));
+    }
+    Ok(())
+}
+
+/// 角色必须是已知的角色
+fn known_role(role: &str) -> Result<(), ValidationError> {
+    if Role::parse(role).is_none() {
+        return Err(validation::invalid("invalid", "Invalid role."));
+    }
+    Ok(())
+}
+
+/// 解析已通过校验的角色
+fn parse_role(role: &str) -> Role {
+    Role::parse(role).unwrap_or(Role::Author)
+}
+
+/// 接收前端提交的刷新令牌
+#[derive(Deserialize)]
+pub struct RefreshData<'a> {
+    pub refresh_token: &'a str,
+}
+
+/// 退出登录时可选提交的刷新令牌
+#[derive(Deserialize)]
+pub struct LogoutData<'a> {
+    pub refresh_token: Option<&'a str>,
 }
 
 /// 用户登录
 #[post("/admin/login", data = "<post_data>")]
-pub async fn login(post_data: Json<PostData<'_>>) -> Value {
-    // println!("username: {}, password: {}", post_data.username, post_data.password);
+pub async fn login(
+    post_data: Json<PostData<'_>>,
+    ip: Option<IpAddr>,
+    throttle: &State<LoginThrottle>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    let username = post_data.username;
+    let ip_str = ip.map(|i| i.to_string());
+
+    // 退避或锁定中，直接拒绝
+    if let Err(retry_after) = throttle.check(username, ip) {
+        let _ = repos
+            .users
+            .record_login_event(username, ip_str, "throttled")
+            .await;
+        return Err(AppError::TooManyRequests { retry_after });
+    }
+
     // 从数据库中检查用户名和密码是否匹配
-    let user = User::login(post_data.username, post_data.password).await;
-    if let Ok(op_user) = user {
-        if let Some(user) = op_user {
-            let user_name = user.username.unwrap();
-            // 生成token
-            let token = create_token(&user_name);
-            // 响应数据
-            let data = LoginResData {
-                username: user_name,
-                token: token,
+    if let Some(user) = repos.users.login(username, post_data.password).await? {
+        // 已开启两步验证时只返回挑战令牌，失败计数在两步验证通过后才清除
+        if user.two_factor_enabled() {
+            let data = TwoFactorChallengeData {
+                username: user.username.clone().unwrap_or_default(),
+                two_factor_required: true,
+                challenge_token: create_challenge_token(&user),
             };
-            return json!(RespData {
+            return Ok(json!(RespData {
                 code: 200,
-                msg: "Login Successful.",
-                data: data,
-            });
+                msg: "Two-factor authentication required.",
+                data,
+            }));
         }
-        return json!(RespData {
-            code: 400,
-            msg: "Username or password error.",
-            data: (),
-        });
+        throttle.record_success(username);
+        return issue_session(repos, user).await;
     }
-    return json!(RespData {
-        code: 400,
-        msg: "Username or password error.",
+
+    // 记录失败
+    let locked = throttle.record_failure(username, ip);
+    let _ = repos
+        .users
+        .record_login_event(username, ip_str.clone(), "failed")
+        .await;
+    if locked {
+        let _ = repos
+            .users
+            .record_login_event(username, ip_str, "locked")
+            .await;
+    }
+    return Err(AppError::unauthorized(
+        "invalid_credentials",
+        "Username or password error.",
+    ));
+}
+
+/// 挑战令牌无效
+const INVALID_CHALLENGE: AppError = AppError::Unauthorized {
+    error: "invalid_challenge_token",
+    msg: "Invalid or expired challenge token.",
+};
+
+/// 两步验证登录：提交挑战令牌和动态码（或恢复码）换取访问令牌
+#[post("/admin/login/2fa", data = "<post_data>")]
+pub async fn login_two_factor(
+    post_data: Json<TwoFactorLoginData<'_>>,
+    ip: Option<IpAddr>,
+    throttle: &State<LoginThrottle>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    let (uid, username) =
+        verify_challenge_token(post_data.challenge_token).ok_or(INVALID_CHALLENGE)?;
+    let ip_str = ip.map(|i| i.to_string());
+
+    // 动态码与密码共用失败计数
+    if let Err(retry_after) = throttle.check(&username, ip) {
+        let _ = repos
+            .users
+            .record_login_event(&username, ip_str, "throttled")
+            .await;
+        return Err(AppError::TooManyRequests { retry_after });
+    }
+
+    let user = match repos.users.find_by_id(uid).await? {
+        Some(u) if u.username.as_ref() == Some(&username) && u.two_factor_enabled() => u,
+        _ => return Err(INVALID_CHALLENGE),
+    };
+
+    if verify_second_factor(repos, &user, post_data.code).await? {
+        throttle.record_success(&username);
+        return issue_session(repos, user).await;
+    }
+    let locked = throttle.record_failure(&username, ip);
+    let _ = repos
+        .users
+        .record_login_event(&username, ip_str.clone(), "2fa_failed")
+        .await;
+    if locked {
+        let _ = repos
+            .users
+            .record_login_event(&username, ip_str, "locked")
+            .await;
+    }
+    return Err(AppError::unauthorized(
+        "invalid_code",
+        "Invalid verification code.",
+    ));
+}
+
+/// 登录成功：签发访问令牌并开启新的刷新令牌族
+async fn issue_session(repos: &Repositories, user: User) -> Result<Value, AppError> {
+    let token = create_token(&user);
+    let user_name = user.username.unwrap_or_default();
+    let refresh_token = repos
+        .users
+        .issue_refresh_token(
+            user.id.unwrap_or_default(),
+            None,
+            TOKEN_CONFIG.refresh_ttl_secs as i64,
+        )
+        .await?;
+    // 响应数据
+    let data = LoginResData {
+        username: user_name,
+        token,
+        refresh_token,
+        expires_in: TOKEN_CONFIG.access_ttl_secs,
+    };
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Login Successful.",
+        data,
+    }));
+}
+
+/// 刷新令牌无效
+const INVALID_REFRESH_TOKEN: AppError = AppError::Unauthorized {
+    error: "invalid_refresh_token",
+    msg: "Invalid refresh token.",
+};
+
+/// 刷新令牌：作废旧的刷新令牌并签发新的访问令牌和刷新令牌
+#[post("/admin/token/refresh", data = "<post_data>")]
+pub async fn refresh(
+    post_data: Json<RefreshData<'_>>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    let ttl = TOKEN_CONFIG.refresh_ttl_secs as i64;
+    let rotation = repos
+        .users
+        .rotate_refresh_token(post_data.refresh_token, ttl)
+        .await?;
+    let (user_id, refresh_token) = match rotation {
+        Rotation::Rotated {
+            user_id,
+            refresh_token,
+        } => (user_id, refresh_token),
+        Rotation::Invalid => return Err(INVALID_REFRESH_TOKEN),
+        Rotation::Reused => {
+            return Err(AppError::unauthorized(
+                "refresh_token_reused",
+                "Refresh token reuse detected, session revoked.",
+            ));
+        }
+    };
+
+    let user = repos
+        .users
+        .find_by_id(user_id)
+        .await?
+        .ok_or(INVALID_REFRESH_TOKEN)?;
+    let data = LoginResData {
+        token: create_token(&user),
+        username: user.username.unwrap_or_default(),
+        refresh_token,
+        expires_in: TOKEN_CONFIG.access_ttl_secs,
+    };
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Refresh Successful.",
+        data,
+    }));
+}
+
+/// 退出登录：吊销当前访问令牌及其刷新令牌族
+#[post("/admin/logout", data = "<post_data>")]
+pub async fn logout(
+    post_data: Option<Json<LogoutData<'_>>>,
+    t: Token,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    repos
+        .users
+        .revoke_token(t.jti(), t.user.id, t.expires_at())
+        .await?;
+    if let Some(refresh_token) = post_data.as_ref().and_then(|d| d.refresh_token) {
+        repos
+            .users
+            .revoke_refresh_token(refresh_token, t.user.id)
+            .await?;
+    }
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Logout successful.",
         data: (),
+    }));
+}
+
+/// 退出全部会话：吊销该用户的全部访问令牌和刷新令牌
+#[post("/admin/logout/all")]
+pub async fn logout_all(t: Token, repos: &State<Repositories>) -> Result<Value, AppError> {
+    repos.users.revoke_sessions(t.user.id).await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "All sessions logged out.",
+        data: (),
+    }));
+}
+
+/// 当前登录用户信息
+#[get("/admin/me")]
+pub async fn me(t: Token) -> Value {
+    return json!(RespData {
+        code: 200,
+        msg: "Success",
+        data: t.user,
     });
 }
 
-/// 用户列表
-#[get("/users")]
-pub async fn list() -> Value {
-    let users = User::get_user_list().await;
-    match users {
-        Ok(users) => {
-            return json!(RespData {
-                code: 200,
-                msg: "Get user list successful.",
-                data: users,
-            });
-        }
-        Err(_) => {
-            return json!(RespData {
-                code: 500,
-                msg: "Error",
-                data: (),
-            });
-        }
+/// 当前被限流或锁定的用户名和 IP（管理员）
+#[get("/admin/lockouts")]
+pub async fn lockouts(
+    throttle: &State<LoginThrottle>,
+    _auth: Authorized<perm::ManageUsers>,
+) -> Value {
+    return json!(RespData {
+        code: 200,
+        msg: "Success",
+        data: throttle.list(),
+    });
+}
+
+/// 解除用户名或 IP 的限制（管理员）
+#[delete("/admin/lockouts?<username>&<ip>")]
+pub async fn clear_lockout(
+    username: Option<&str>,
+    ip: Option<IpAddr>,
+    throttle: &State<LoginThrottle>,
+    _auth: Authorized<perm::ManageUsers>,
+) -> Result<Value, AppError> {
+    let mut cleared = false;
+    if let Some(username) = username {
+        cleared |= throttle.clear(&user_key(username));
+    }
+    if let Some(ip) = ip {
+        cleared |= throttle.clear(&ip_key(&ip));
+    }
+    if !cleared {
+        return Err(AppError::not_found(
+            "lockout_not_found",
+            "Lockout not found.",
+        ));
+    }
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Lockout cleared.",
+        data: (),
+    }));
+}
+
+/// 最近的登录失败事件（管理员）
+#[get("/admin/login-events?<limit>")]
+pub async fn login_events(
+    limit: Option<u64>,
+    _auth: Authorized<perm::ManageUsers>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    let limit = limit.unwrap_or(50).min(500);
+    let events = repos.users.recent_login_events(limit).await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Success",
+        data: events,
+    }));
+}
+
+/// 新增用户（管理员）
+#[post("/users", data = "<post_data>")]
+pub async fn create(
+    post_data: Json<CreateUserData>,
+    _auth: Authorized<perm::ManageUsers>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    let data = post_data.into_inner();
+    let mut errors = validation::field_errors(&data);
+    // 用户名唯一
+    if !validation::has_error(&errors, "username")
+        && repos.users.username_exists(&data.username).await?
+    {
+        errors.push(FieldError::new(
+            "username",
+            "taken",
+            "Username already exists.",
+        ));
+    }
+    validation::check(errors)?;
+
+    let role = parse_role(&data.role);
+    let profile = UserProfile {
+        nickname: data.nickname,
+        avatar: data.avatar,
+        bio: data.bio,
+        email: data.email,
+        website: data.website,
+    };
+    let new_user = NewUser {
+        username: data.username,
+        password: data.password,
+        role: role.as_str().to_string(),
+        profile,
+    };
+    let id = repos.users.create(new_user).await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Create user successful.",
+        data: id,
+    }));
+}
+
+/// 修改用户资料（本人或管理员）
+#[put("/user/<id>", data = "<put_data>")]
+pub async fn update(
+    id: u32,
+    put_data: Json<UpdateUserData>,
+    t: Token,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    let is_admin = t.user.role.can(Permission::ManageUsers);
+    if t.user.id != id && !is_admin {
+        return Err(AppError::forbidden());
+    }
+    let data = put_data.into_inner();
+
+    // 只有管理员可以修改角色
+    if data.role.is_some() && !is_admin {
+        return Err(AppError::forbidden());
+    }
+    validation::check(validation::field_errors(&data))?;
+
+    let role = data.role.map(|r| parse_role(&r).as_str().to_string());
+    let profile = UserProfile {
+        nickname: data.nickname,
+        avatar: data.avatar,
+        bio: data.bio,
+        email: data.email,
+        website: data.website,
+    };
+
+    let target = repos.users.find_by_id(id).await?.ok_or(USER_NOT_FOUND)?;
+    // 管理员改为其他角色时保留至少一个管理员
+    if role.as_deref().is_some_and(|r| r != Role::Admin.as_str()) {
+        ensure_other_admin(repos, &target).await?;
+    }
+    repos.users.update_profile(id, profile, role).await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Update user successful.",
+        data: (),
+    }));
+}
+
+/// 修改自己的密码，成功后全部会话失效
+/// 原密码错误与登录共用失败计数，避免持有访问令牌即可无限猜测密码
+#[put("/admin/password", data = "<put_data>")]
+pub async fn change_password(
+    put_data: Json<ChangePasswordData<'_>>,
+    t: Token,
+    ip: Option<IpAddr>,
+    throttle: &State<LoginThrottle>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    validation::check(validation::field_errors(&*put_data))?;
+    let username = &t.user.username;
+    check_throttle(repos, throttle, username, ip).await?;
+    if !repos
+        .users
+        .change_password(t.user.id, put_data.old_password, put_data.new_password)
+        .await?
+    {
+        let err = AppError::bad_request("invalid_password", "Old password error.");
+        return Err(record_failure(repos, throttle, username, ip, "failed", err).await);
+    }
+    throttle.record_success(username);
+
+    repos.users.revoke_sessions(t.user.id).await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Password changed, please login again.",
+        data: (),
+    }));
+}
+
+/// 重置用户密码（管理员），成功后该用户全部会话失效
+#[put("/user/<id>/password", data = "<put_data>")]
+pub async fn reset_password(
+    id: u32,
+    put_data: Json<ResetPasswordData<'_>>,
+    _auth: Authorized<perm::ManageUsers>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    validation::check(validation::field_errors(&*put_data))?;
+    if repos
+        .users
+        .update_password(id, put_data.new_password)
+        .await?
+        == 0
+    {
+        return Err(USER_NOT_FOUND);
     }
+
+    repos.users.revoke_sessions(id).await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Reset password successful.",
+        data: (),
+    }));
+}
+
+/// 用户列表（管理员）
+#[get("/users")]
+pub async fn list(
+    _auth: Authorized<perm::ManageUsers>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    let users = repos.users.list().await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Get user list successful.",
+        data: users,
+    }));
 }
 
 /// 删除用户
-#[delete("/user/<id>")]
-pub async fn delete(id: &str, _t: Token) -> Value {
-    let res = User::delete_user(id).await;
-    match res {
-        Ok(_) => {
-            return json!(RespData {
-                code: 200,
-                msg: "Delete user successful.",
-                data: (),
+/// ---
+/// 不能删除自己和最后一个管理员；用户名下有文章时必须通过 reassign_to 指定接收文章的用户
+/// ---
+#[delete("/user/<id>?<reassign_to>")]
+pub async fn delete(
+    id: u32,
+    reassign_to: Option<u32>,
+    auth: Authorized<perm::ManageUsers>,
+    repos: &State<Repositories>,
+) -> Result<Value, AppError> {
+    if id == auth.user().id {
+        return Err(AppError::bad_request(
+            "cannot_delete_self",
+            "Cannot delete yourself.",
+        ));
+    }
+
+    let target = repos.users.find_by_id(id).await?.ok_or(USER_NOT_FOUND)?;
+
+    // 保留至少一个管理员
+    ensure_other_admin(repos, &target).await?;
+
+    // 文章处理策略：转移给其他用户，否则阻止删除
+    let articles = repos.articles.count_by_author(id).await?;
+    let reassign_to = match reassign_to {
+        _ if articles == 0 => None,
+        None => {
+            return Err(AppError::Conflict {
+                error: "user_has_articles",
+                msg: "User still has articles, reassign them with ?reassign_to=<user id>.",
+                details: Some(json!({ "articles": articles })),
             });
         }
-        Err(_) => {
-            return json!(RespData {
-                code: 500,
-                msg: "Error",
-                data: (),
-            });
+        Some(new_author) if new_author == id => {
+            return Err(AppError::bad_request(
+                "invalid_reassign_target",
+                "Cannot reassign articles to the deleted user.",
+            ));
         }
-    }
+        Some(new_author) => match repos.users.find_by_id(new_author).await? {
+            Some(_) => Some(new_author),
+            None => {
+                return Err(AppError::bad_request(
+                    "invalid_reassign_target",
+                    "Reassign target not found.",
+                ));
+            }
+        },
+    };
+
+    repos.users.delete(id, reassign_to).await?;
+    return Ok(json!(RespData {
+        code: 200,
+        msg: "Delete user successful.",
+        data: (),
+    }));
 }
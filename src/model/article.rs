@@ -1,24 +1,47 @@
 // 文章模型及关联函数
 
 use crate::RB;
-use rbson::bson;
+use rbson::{bson, Bson};
 use chrono::Local;
 use rbatis::crud::CRUD;
-use rbatis::db::DBExecResult;
 use rbatis::executor::Executor;
-use rbatis::executor::ExecutorMut;
 use rbatis::Error;
 
+use super::article_revision::ArticleRevision;
+use super::slug_history::{SlugHistory, SlugKind};
+use crate::util::dialect::{dialect, lenient_bool};
+use crate::util::markdown::{self, Toc};
+use crate::util::transaction::UnitOfWork;
+
 // 文章表
 #[crud_table(table_name:article)]
 #[derive(Clone, Debug)]
 pub struct Article {
     pub id: Option<u32>,
     pub title: Option<String>,
+    /// 网址中使用的唯一标识
+    pub slug: Option<String>,
     pub description: Option<String>,
+    /// Markdown 原文
     pub content: Option<String>,
+    /// 正文渲染后的 HTML，保存时由 content 生成
+    pub content_html: Option<String>,
+    /// 标题目录，保存时由 content 生成
+    pub toc: Option<Toc>,
+    /// 字数（中文、日文按字计）
+    pub word_count: Option<u32>,
+    /// 预计阅读时间（分钟）
+    pub reading_minutes: Option<u32>,
+    /// 摘要：填写了描述时为描述，否则由正文生成
+    pub excerpt: Option<String>,
     pub cate_id: Option<u32>,
+    pub author_id: Option<u32>,
+    #[serde(default, deserialize_with = "lenient_bool")]
     pub istop: Option<bool>,
+    /// 状态，见 `ArticleStatus`
+    pub status: Option<String>,
+    /// 发布时间（定时文章为计划发布时间）
+    pub published_at: Option<i64>,
     pub created_at: Option<i64>,
     pub updated_at: Option<i64>,
 }
@@ -29,11 +52,21 @@ pub struct Article {
 pub struct ArticleVo {
     pub id: Option<u32>,
     pub title: Option<String>,
+    pub slug: Option<String>,
     pub description: Option<String>,
     pub content: Option<String>,
+    pub content_html: Option<String>,
+    pub toc: Option<Toc>,
+    pub word_count: Option<u32>,
+    pub reading_minutes: Option<u32>,
+    pub excerpt: Option<String>,
     pub cate_id: Option<u32>,
     pub cate_name: Option<String>,
+    pub author_id: Option<u32>,
+    #[serde(default, deserialize_with = "lenient_bool")]
     pub istop: Option<bool>,
+    pub status: Option<String>,
+    pub published_at: Option<i64>,
     pub created_at: Option<i64>,
     pub updated_at: Option<i64>,
 }
@@ -44,11 +77,17 @@ pub struct ArticleVo {
 pub struct ArticleEditVo {
     pub id: Option<u32>,
     pub title: Option<String>,
+    pub slug: Option<String>,
     pub description: Option<String>,
     pub content: Option<String>,
     pub cate_id: Option<u32>,
     pub cate_name: Option<String>,
+    #[serde(default, deserialize_with = "lenient_bool")]
     pub is_top: Option<bool>,
+    /// 逗号分隔的标签 id
+    pub tags: Option<String>,
+    pub status: Option<String>,
+    pub published_at: Option<i64>,
     pub created_at: Option<i64>,
     pub updated_at: Option<i64>,
 }
@@ -56,224 +95,489 @@ pub struct ArticleEditVo {
 // 封装前端提交过来的数据
 pub struct ArticleForUpdateVo {
     pub title: String,
+    pub slug: String,
     pub description: String,
     pub content: String,
     pub cate_id: u32,
     pub tags: Vec<u32>,
+    pub status: String,
+    pub published_at: Option<i64>,
+    /// 保存的用户，记录为新版本的作者
+    pub editor_id: u32,
+}
+
+/// 文章状态
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArticleStatus {
+    /// 草稿：只在后台可见
+    Draft,
+    /// 已发布：前台可见
+    Published,
+    /// 定时发布：到达 published_at 后由后台任务改为已发布
+    Scheduled,
+    /// 已归档：从前台下线
+    Archived,
+}
+
+impl ArticleStatus {
+    /// 解析数据库中保存的状态，无法识别时返回 None
+    pub fn parse(status: &str) -> Option<ArticleStatus> {
+        match status {
+            "draft" => Some(ArticleStatus::Draft),
+            "published" => Some(ArticleStatus::Published),
+            "scheduled" => Some(ArticleStatus::Scheduled),
+            "archived" => Some(ArticleStatus::Archived),
+            _ => None,
+        }
+    }
+
+    /// 数据库中保存的状态名
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArticleStatus::Draft => "draft",
+            ArticleStatus::Published => "published",
+            ArticleStatus::Scheduled => "scheduled",
+            ArticleStatus::Archived => "archived",
+        }
+    }
+}
+
+// 文章与标签关联表
+#[crud_table(table_name:article_to_tag)]
+#[derive(Clone, Debug)]
+pub struct ArticleTag {
+    pub article_id: Option<u32>,
+    pub tag_id: Option<u32>,
+}
+
+/// 由正文生成的列（content_html, toc, word_count, reading_minutes, excerpt）的值
+/// 在阻塞线程池中渲染，须在开启事务之前调用
+async fn rendered_values(description: Option<&str>, content: Option<&str>) -> Result<Vec<Bson>, Error> {
+    let content = match content {
+        Some(content) => content,
+        None => return Ok(vec![Bson::Null; 5]),
+    };
+    let r = markdown::render_blocking(content).await?;
+    Ok(vec![
+        bson!(r.html),
+        bson!(r.toc.to_json()),
+        bson!(r.word_count),
+        bson!(r.reading_minutes),
+        bson!(excerpt(description, r.excerpt)),
+    ])
+}
+
+/// 摘要：填写了描述时使用描述，否则使用由正文生成的摘要
+fn excerpt(description: Option<&str>, generated: String) -> String {
+    match description.map(str::trim) {
+        Some(description) if !description.is_empty() => description.to_string(),
+        _ => generated,
+    }
+}
+
+impl ArticleVo {
+    /// 列表中的文章：以摘要代替正文
+    pub fn into_summary(mut self) -> ArticleVo {
+        self.content = None;
+        self.content_html = None;
+        self
+    }
 }
 
 impl Article {
-    /// 获取全部记录数量
-    pub async fn find_total() -> Result<u64, Error> {
-        RB.fetch_count::<Article>().await
+    /// 由正文生成 HTML、目录、字数、阅读时间及摘要（内存仓库使用，数据库仓库在保存时渲染）
+    #[cfg(test)]
+    pub fn render_content(&mut self) {
+        let r = markdown::render(self.content.as_deref().unwrap_or_default());
+        self.content_html = Some(r.html);
+        self.toc = Some(r.toc);
+        self.word_count = Some(r.word_count);
+        self.reading_minutes = Some(r.reading_minutes);
+        self.excerpt = Some(excerpt(self.description.as_deref(), r.excerpt));
+    }
+
+    /// 列表中的文章：以摘要代替正文
+    pub fn into_summary(mut self) -> Article {
+        self.content = None;
+        self.content_html = None;
+        self
     }
 
-    /// 分页查询文章列表
+    /// 已发布文章数量
+    pub async fn count_published() -> Result<u64, Error> {
+        let w = RB.new_wrapper().eq("status", "published");
+        RB.fetch_count_by_wrapper::<Article>(w).await
+    }
+
+    /// 分页查询已发布的文章列表
     #[sql(
         RB,
-        "select a.*,c.name as cate_name from article a, category c WHERE a.cate_id = c.id limit ?,?"
+        "select a.*,c.name as cate_name from article a, category c WHERE a.cate_id = c.id AND a.status = 'published' limit ? offset ?"
     )]
     pub async fn find_all_by_pagination_with_category(
-        page: &str,
-        per_page: &str,
+        per_page: &u64,
+        offset: &u64,
     ) -> Result<Vec<ArticleVo>, Error> {}
 
-    /// 查询全部文章（带分类信息）
-    #[sql(
-        RB,
-        "select a.*,c.name as cate_name from article a, category c WHERE a.cate_id = c.id"
-    )]
-    pub async fn find_all_with_category() -> Result<Vec<ArticleVo>, Error> {}
+    /// 作者的文章数量
+    pub async fn count_by_author(author_id: u32) -> Result<u64, Error> {
+        let w = RB.new_wrapper().eq("author_id", author_id);
+        RB.fetch_count_by_wrapper::<Article>(w).await
+    }
 
-    /// 根据id查询文章详情
+    /// 根据id查询文章详情（任意状态）
     pub async fn find_by_id(id: u32) -> Result<Option<Article>, Error> {
         let w = RB.new_wrapper().eq("id", id);
         RB.fetch_by_wrapper(w).await
     }
 
+    /// 根据id查询已发布的文章
+    pub async fn find_published_by_id(id: u32) -> Result<Option<Article>, Error> {
+        let w = RB.new_wrapper().eq("id", id).eq("status", "published");
+        RB.fetch_by_wrapper(w).await
+    }
+
+    /// 根据slug查询文章详情（任意状态）
+    pub async fn find_by_slug(slug: &str) -> Result<Option<Article>, Error> {
+        let w = RB.new_wrapper().eq("slug", slug);
+        RB.fetch_by_wrapper(w).await
+    }
+
     /// 根据id查询正在编辑的文章
-    #[sql(
-        RB,
-        "SELECT a.id,a.title,a.description,a.content,a.cate_id,GROUP_CONCAT(att.tag_id) as tags FROM article a LEFT JOIN article_to_tag att ON a.id = att.article_id WHERE a.id = ? GROUP BY a.id;"
-    )]
-    pub async fn find_editing_by_id(id: &str) -> Result<Option<ArticleEditVo>, Error> {}
+    pub async fn find_editing_by_id(id: u32) -> Result<Option<ArticleEditVo>, Error> {
+        let sql = format!(
+            "SELECT a.id,a.title,a.slug,a.description,a.content,a.cate_id,a.status,a.published_at,{} as tags FROM article a LEFT JOIN article_to_tag att ON a.id = att.article_id WHERE a.id = ? GROUP BY a.id,a.title,a.slug,a.description,a.content,a.cate_id,a.status,a.published_at",
+            dialect().group_concat("att.tag_id")
+        );
+        RB.fetch(&sql, vec![bson!(id)]).await
+    }
 
-    /// 文章详情（带分类信息）
+    /// 文章详情（任意状态，带分类信息）
     #[sql(
         RB,
-        "SELECT a.*,c.name as cate_name FROM article a, category c WHERE a.cate_id = c.id AND a.id = ?"
+        "SELECT a.id,a.title,a.slug,a.description,a.content,a.content_html,a.toc,a.word_count,a.reading_minutes,a.excerpt,a.cate_id,c.name as cate_name,a.author_id,a.istop,a.status,a.published_at,a.created_at,a.updated_at FROM article a LEFT JOIN category c ON a.cate_id = c.id WHERE a.id = ?"
     )]
-    pub async fn find_by_id_with_category(id: &str) -> Result<Option<ArticleVo>, Error> {}
+    pub async fn find_by_id_with_category(id: &u32) -> Result<Option<ArticleVo>, Error> {}
 
-    /// 最热文章
+    /// 最热文章（已发布）
     pub async fn hot_list() -> Result<Vec<Article>, Error> {
-        let w = RB.new_wrapper().order_by(false, &["created_at"])
+        let w = RB.new_wrapper()
+            .eq("status", "published")
+            .order_by(false, &["created_at"])
             .push_sql("limit 9");
         RB.fetch_list_by_wrapper(w).await
     }
 
-    /// 前台文章搜索
+    /// 前台文章搜索（已发布）
     pub async fn search(keyword: &str) -> Result<Vec<Article>, Error> {
-        let w = RB.new_wrapper().like("title", keyword);
+        let w = RB.new_wrapper()
+            .like("title", keyword)
+            .eq("status", "published");
         RB.fetch_list_by_wrapper(w).await
     }
 
-    /// 新增文章
+    /// 新增文章及其标签关联
     pub async fn add_article(article: Article, tag_ids: Vec<u32>) -> rbatis::core::Result<()> {
-        // 创建事务对象
-        let mut tx = RB.acquire_begin().await?.defer_async(|mut tx1| async move {
-            if !tx1.is_done() {
-                tx1.rollback().await;
-                // println!("事务回滚成功");
-            } else {
-                // println!("不需要回滚");
-            }
-        });
-
-        // 向文章表插入数据
-        let art_res = tx.exec("INSERT INTO article (title, description, content, cate_id, istop, created_at, updated_at) VALUES (?,?,?,?,?,?,?);",
-            vec![
-                bson!(article.title), 
-                bson!(article.description), 
-                bson!(article.content),
-                bson!(article.cate_id),
-                bson!(article.istop),
-                bson!(article.created_at),
-                bson!(article.updated_at),
-            ]).await;
-        
-        // 获取新添加文章的id
-        match art_res {
-            Ok(res) => {
-                let new_art_id = res.last_insert_id;
-                // 向article_to_tag表中插入数据
-                for i in tag_ids.iter() {
-                    tx.exec(
-                        "INSERT INTO article_to_tag (article_id, tag_id) VALUES (?,?);",
-                        vec![bson!(new_art_id), bson!(*i)],
-                    ).await;
-                }
-            }
-            Err(e) => {
-                tx.rollback().await;
-                return Err(e);
-            }
+        let rendered = rendered_values(article.description.as_deref(), article.content.as_deref()).await?;
+        let mut uow = UnitOfWork::begin(&RB).await?;
+
+        // 事务1: 向文章表插入数据
+        let new_art_id = uow
+            .insert(
+                "insert_article",
+                "INSERT INTO article (title, slug, description, content, content_html, toc, word_count, reading_minutes, excerpt, cate_id, author_id, istop, status, published_at, created_at, updated_at) VALUES (?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?)",
+                [
+                    vec![
+                        bson!(article.title.clone()),
+                        bson!(article.slug),
+                        bson!(article.description.clone()),
+                        bson!(article.content.clone()),
+                    ],
+                    rendered,
+                    vec![
+                        bson!(article.cate_id),
+                        bson!(article.author_id),
+                        bson!(article.istop),
+                        bson!(article.status),
+                        bson!(article.published_at),
+                        bson!(article.created_at),
+                        bson!(article.updated_at),
+                    ],
+                ]
+                .concat(),
+            )
+            .await?;
+
+        // 事务2: 记录第 1 个版本
+        let snapshot = ArticleRevision {
+            id: None,
+            article_id: new_art_id.map(|id| id as u32),
+            revision: None,
+            title: article.title,
+            description: article.description,
+            content: article.content,
+            author_id: article.author_id,
+            created_at: article.created_at,
+        };
+        ArticleRevision::record(&mut uow, &snapshot).await?;
+
+        // 事务3: 向article_to_tag表中插入数据
+        for tag_id in tag_ids {
+            uow.exec(
+                "insert_article_tags",
+                "INSERT INTO article_to_tag (article_id, tag_id) VALUES (?,?);",
+                vec![bson!(new_art_id), bson!(tag_id)],
+            )
+            .await?;
         }
+
         // 提交事务
-        tx.commit().await;
-        return Ok(());
+        uow.commit().await?;
+        Ok(())
     }
 
-    /// 删除文章
+    /// 删除文章及其标签关联
     pub async fn remove(id: u32) -> rbatis::core::Result<()> {
-        let art = Self::find_by_id(id).await;
-        // println!("删除查询到的文章: {:?}", art);
-        match art {
-            Ok(a1) => match a1 {
-                Some(a2) => {
-                    // 创建事务对象
-                    let mut tx = RB.acquire_begin().await?.defer_async(|mut tx1| async move {
-                        if !tx1.is_done() {
-                            tx1.rollback().await;
-                        } else {}
-                    });
-
-                    // 事务1: 删除article_to_tag表中关联文章的数据
-                    tx.exec(
-                        "DELETE FROM article_to_tag WHERE article_id = ?;",
-                        vec![bson!(a2.id)],
-                    ).await;
-                    
-                    // 事务2: 删除article表中对应id文章
-                    tx.exec(
-                        "DELETE FROM article WHERE id = ?;",
-                        vec![bson!(a2.id)]
-                    ).await;
-
-                    // 提交事务
-                    tx.commit().await;
-                    return Ok(());
-                }
-                None => return Err(Error::E("文章不存在".to_string())),
-            },
-            Err(e) => {
-                return Err(e);
-            }
+        if Self::find_by_id(id).await?.is_none() {
+            return Err(Error::E("文章不存在".to_string()));
         }
+        let mut uow = UnitOfWork::begin(&RB).await?;
+
+        // 事务1: 删除article_to_tag表中关联文章的数据
+        uow.exec(
+            "delete_article_tags",
+            "DELETE FROM article_to_tag WHERE article_id = ?;",
+            vec![bson!(id)],
+        )
+        .await?;
+
+        // 事务2: 删除文章的历史版本
+        uow.exec(
+            "delete_article_revisions",
+            "DELETE FROM article_revision WHERE article_id = ?;",
+            vec![bson!(id)],
+        )
+        .await?;
+
+        // 事务3: 删除文章的旧 slug
+        SlugHistory::forget(&mut uow, SlugKind::Article, id).await?;
+
+        // 事务4: 删除article表中对应id文章
+        uow.exec(
+            "delete_article",
+            "DELETE FROM article WHERE id = ?;",
+            vec![bson!(id)],
+        )
+        .await?;
+
+        // 提交事务
+        uow.commit().await?;
+        Ok(())
     }
 
-    /// 更新文章
+    /// 更新文章及其标签关联
     pub async fn update(id: u32, put_art: ArticleForUpdateVo) -> rbatis::core::Result<()> {
-        let old_art = Self::find_by_id(id).await;
-        match old_art {
-            Ok(oart) => match oart {
-                Some(a) => {
-                    let tags = put_art.tags;
-                    
-                    // 创建事务对象
-                    let mut tx = RB.acquire_begin().await?.defer_async(|mut tx1| async move {
-                        if !tx1.is_done() {
-                            tx1.rollback().await;
-                        } else {}
-                    });
-
-                    // 事务1: 删除article_to_tag表中关联数据
-                    tx.exec(
-                        "DELETE FROM article_to_tag WHERE article_id = ?;",
-                        vec![bson!(a.id)],
-                    ).await;
-
-                    // 事务2: 添加article_to_tag关联关系
-                    for i in tags {
-                        tx.exec(
-                            "INSERT INTO article_to_tag VALUES (?,?);",
-                            vec![bson!(a.id), bson!(i)],
-                        ).await;
-                    }
-
-                    // 事务3: 更新article表
-                    tx.exec(
-                        "UPDATE article SET title = ?, description = ?, content = ?, cate_id = ?, updated_at = ?, WHERE id = ?;",
-                        vec![
-                            bson!(put_art.title),
-                            bson!(put_art.description),
-                            bson!(put_art.content),
-                            bson!(put_art.cate_id),
-                            bson!(Local::now().timestamp()),
-                            bson!(a.id)
-                        ]
-                    ).await;
-
-                    // 提交事务
-                    tx.commit().await;
-                    return Ok(());
-                }
-                None => return Err(Error::E("该分类不存在".to_string())),
-            },
-            Err(e) => {
-                return Err(e);
-            }
+        let old_art = match Self::find_by_id(id).await? {
+            Some(a) => a,
+            None => return Err(Error::E("文章不存在".to_string())),
+        };
+        let rendered = rendered_values(Some(&put_art.description), Some(&put_art.content)).await?;
+        let now = Local::now().timestamp();
+        let mut uow = UnitOfWork::begin(&RB).await?;
+
+        // 事务1: 更新article表
+        uow.exec(
+            "update_article",
+            "UPDATE article SET title = ?, slug = ?, description = ?, content = ?, content_html = ?, toc = ?, word_count = ?, reading_minutes = ?, excerpt = ?, cate_id = ?, status = ?, published_at = ?, updated_at = ? WHERE id = ?;",
+            [
+                vec![
+                    bson!(put_art.title.clone()),
+                    bson!(put_art.slug.clone()),
+                    bson!(put_art.description.clone()),
+                    bson!(put_art.content.clone()),
+                ],
+                rendered,
+                vec![
+                    bson!(put_art.cate_id),
+                    bson!(put_art.status),
+                    bson!(put_art.published_at),
+                    bson!(now),
+                    bson!(id),
+                ],
+            ]
+            .concat(),
+        )
+        .await?;
+
+        // 事务2: 修改 slug 时记录旧 slug
+        SlugHistory::record(&mut uow, SlugKind::Article, id, old_art.slug.as_deref(), &put_art.slug).await?;
+
+        // 事务3: 记录新版本
+        let snapshot = ArticleRevision {
+            id: None,
+            article_id: Some(id),
+            revision: None,
+            title: Some(put_art.title),
+            description: Some(put_art.description),
+            content: Some(put_art.content),
+            author_id: Some(put_art.editor_id),
+            created_at: Some(now),
+        };
+        ArticleRevision::record(&mut uow, &snapshot).await?;
+
+        // 事务4: 删除article_to_tag表中关联数据
+        uow.exec(
+            "delete_article_tags",
+            "DELETE FROM article_to_tag WHERE article_id = ?;",
+            vec![bson!(id)],
+        )
+        .await?;
+
+        // 事务5: 添加article_to_tag关联关系
+        for tag_id in put_art.tags {
+            uow.exec(
+                "insert_article_tags",
+                "INSERT INTO article_to_tag (article_id, tag_id) VALUES (?,?);",
+                vec![bson!(id), bson!(tag_id)],
+            )
+            .await?;
         }
+
+        // 提交事务
+        uow.commit().await?;
+        Ok(())
+    }
+
+    /// 恢复历史版本：用版本的标题、描述及正文覆盖文章，并记录为新版本
+    /// ---
+    /// @parameter  id              u32
+    /// @parameter  revision        ArticleRevision
+    /// @parameter  editor_id       u32
+    /// @parameter  status          ArticleStatus       恢复后的状态
+    /// @parameter  published_at    Option<i64>         恢复后的发布时间
+    /// @return     rbatis::core::Result<()>
+    /// ---
+    pub async fn restore_revision(
+        id: u32,
+        revision: ArticleRevision,
+        editor_id: u32,
+        status: ArticleStatus,
+        published_at: Option<i64>,
+    ) -> rbatis::core::Result<()> {
+        let rendered = rendered_values(revision.description.as_deref(), revision.content.as_deref()).await?;
+        let now = Local::now().timestamp();
+        let mut uow = UnitOfWork::begin(&RB).await?;
+
+        // 事务1: 更新article表
+        uow.exec(
+            "restore_article",
+            "UPDATE article SET title = ?, description = ?, content = ?, content_html = ?, toc = ?, word_count = ?, reading_minutes = ?, excerpt = ?, status = ?, published_at = ?, updated_at = ? WHERE id = ?;",
+            [
+                vec![
+                    bson!(revision.title.clone()),
+                    bson!(revision.description.clone()),
+                    bson!(revision.content.clone()),
+                ],
+                rendered,
+                vec![bson!(status.as_str()), bson!(published_at), bson!(now), bson!(id)],
+            ]
+            .concat(),
+        )
+        .await?;
+
+        // 事务2: 记录新版本
+        let snapshot = ArticleRevision {
+            id: None,
+            article_id: Some(id),
+            revision: None,
+            author_id: Some(editor_id),
+            created_at: Some(now),
+            ..revision
+        };
+        ArticleRevision::record(&mut uow, &snapshot).await?;
+
+        // 提交事务
+        uow.commit().await?;
+        Ok(())
+    }
+
+    /// 重新生成文章的 HTML、目录、字数、阅读时间及摘要
+    /// ---
+    /// @parameter  id              u32
+    /// @parameter  description     Option<&str>
+    /// @parameter  content         Option<&str>
+    /// @return     Result<u64, Error>     受影响行数
+    /// ---
+    pub async fn update_rendered(id: u32, description: Option<&str>, content: Option<&str>) -> Result<u64, Error> {
+        let mut args = rendered_values(description, content).await?;
+        args.push(bson!(id));
+        let res = RB
+            .exec(
+                "UPDATE article SET content_html = ?, toc = ?, word_count = ?, reading_minutes = ?, excerpt = ? WHERE id = ?",
+                args,
+            )
+            .await?;
+        Ok(res.rows_affected)
+    }
+
+    /// 为迁移前的文章（content_html、toc 或 excerpt 为空）生成 HTML、目录、字数、阅读时间及摘要
+    /// 启动时执行一次，之后读取文章时不再渲染
+    /// ---
+    /// @return     Result<u64, Error>     渲染的文章数量
+    /// ---
+    pub async fn render_missing() -> Result<u64, Error> {
+        let w = RB.new_wrapper()
+            .is_null("content_html")
+            .or()
+            .is_null("toc")
+            .or()
+            .is_null("excerpt");
+        let articles: Vec<Article> = RB.fetch_list_by_wrapper(w).await?;
+        let mut rendered = 0;
+        for art in articles {
+            rendered += Self::update_rendered(
+                art.id.unwrap_or_default(),
+                art.description.as_deref(),
+                Some(art.content.as_deref().unwrap_or_default()),
+            )
+            .await?;
+        }
+        Ok(rendered)
+    }
+
+    /// 发布到达发布时间的定时文章
+    /// ---
+    /// @parameter  now     i64
+    /// @return     Result<u64, Error>     发布的文章数量
+    /// ---
+    pub async fn publish_due(now: i64) -> Result<u64, Error> {
+        let res = RB
+            .exec(
+                "UPDATE article SET status = 'published' WHERE status = 'scheduled' AND published_at <= ?",
+                vec![bson!(now)],
+            )
+            .await?;
+        Ok(res.rows_affected)
     }
 
     /// 后台文章搜索
     pub async fn admin_search(title: String, category: u32) -> Result<Vec<Article>, Error> {
         let w;
-        if title == "".to_string() && category != 0 {
+        if title.is_empty() && category != 0 {
             w = RB.new_wrapper().eq("cate_id", category);
             return RB.fetch_list_by_wrapper(w).await;
         }
 
-        if title == "".to_string() && category == 0 {
+        if title.is_empty() && category == 0 {
             return RB.fetch_list().await;
         }
 
-        if title != "".to_string() && category == 0 {
+        if !title.is_empty() && category == 0 {
             w = RB.new_wrapper().like("title", title);
             return RB.fetch_list_by_wrapper(w).await;
         }
 
-        if title != "".to_string() && category != 0 {
+        if !title.is_empty() && category != 0 {
             w = RB.new_wrapper()
                 .like("title", title)
                 .eq("cate_id", category);
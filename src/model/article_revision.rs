@@ -0,0 +1,105 @@
+// 文章历史版本模型及关联函数
+// 每次保存文章（新增、更新、恢复版本）都在同一个事务中记录一个版本，版本号在每篇文章内从 1 递增
+use crate::RB;
+use rbatis::crud::CRUD;
+use rbatis::executor::Executor;
+use rbatis::Error;
+use rbson::bson;
+
+use crate::util::transaction::{StepError, UnitOfWork};
+
+// 文章版本表
+#[crud_table(table_name:article_revision)]
+#[derive(Clone, Debug)]
+pub struct ArticleRevision {
+    pub id: Option<u32>,
+    pub article_id: Option<u32>,
+    /// 文章内的版本号
+    pub revision: Option<u32>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub content: Option<String>,
+    /// 保存该版本的用户
+    pub author_id: Option<u32>,
+    pub created_at: Option<i64>,
+}
+
+// 版本列表的输出对象vo（不含正文）
+#[crud_table(table_name:article_revision)]
+#[derive(Clone, Debug)]
+pub struct ArticleRevisionVo {
+    pub id: Option<u32>,
+    pub article_id: Option<u32>,
+    pub revision: Option<u32>,
+    pub title: Option<String>,
+    pub author_id: Option<u32>,
+    pub created_at: Option<i64>,
+}
+
+impl ArticleRevision {
+    /// 版本概要（不含正文）
+    pub fn summary(&self) -> ArticleRevisionVo {
+        ArticleRevisionVo {
+            id: self.id,
+            article_id: self.article_id,
+            revision: self.revision,
+            title: self.title.clone(),
+            author_id: self.author_id,
+            created_at: self.created_at,
+        }
+    }
+
+    /// 文章的全部版本，最新的在前
+    /// ---
+    /// @parameter  article_id      u32
+    /// @return     Result<Vec<ArticleRevisionVo>, Error>
+    /// ---
+    pub async fn list(article_id: u32) -> Result<Vec<ArticleRevisionVo>, Error> {
+        RB.fetch(
+            "SELECT id, article_id, revision, title, author_id, created_at FROM article_revision WHERE article_id = ? ORDER BY revision DESC",
+            vec![bson!(article_id)],
+        )
+        .await
+    }
+
+    /// 查询文章的某个版本
+    /// ---
+    /// @parameter  article_id      u32
+    /// @parameter  revision        u32
+    /// @return     Result<Option<ArticleRevision>, Error>
+    /// ---
+    pub async fn find(article_id: u32, revision: u32) -> Result<Option<ArticleRevision>, Error> {
+        let w = RB
+            .new_wrapper()
+            .eq("article_id", article_id)
+            .eq("revision", revision);
+        RB.fetch_by_wrapper(w).await
+    }
+
+    /// 在事务中记录文章的新版本，版本号为该文章已有的最大版本号加 1
+    /// ---
+    /// @parameter  uow         &mut UnitOfWork
+    /// @parameter  snapshot    &ArticleRevision    文章 id、标题、描述、正文、作者及时间
+    /// @return     Result<(), StepError>
+    /// ---
+    pub async fn record(
+        uow: &mut UnitOfWork<'_>,
+        snapshot: &ArticleRevision,
+    ) -> Result<(), StepError> {
+        uow.exec(
+            "insert_article_revision",
+            "INSERT INTO article_revision (article_id, revision, title, description, content, author_id, created_at) SELECT ?, COALESCE(MAX(revision), 0) + 1, ?, ?, ?, ?, ? FROM article_revision WHERE article_id = ?;",
+            vec![
+                bson!(snapshot.article_id),
+                bson!(snapshot.title.clone()),
+                bson!(snapshot.description.clone()),
+                bson!(snapshot.content.clone()),
+                bson!(snapshot.author_id),
+                bson!(snapshot.created_at),
+                bson!(snapshot.article_id),
+            ],
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+// 数据导出及导入：用户、分类、标签、文章及其关联、文章历史版本、slug 历史，导入时保留原有 id
+use crate::RB;
+use chrono::Local;
+use rbatis::crud::{Skip, CRUD};
+use rbatis::Error;
+use rbson::{bson, Bson};
+use rocket::serde::{Deserialize, Serialize};
+
+use super::article::{Article, ArticleTag};
+use super::article_revision::ArticleRevision;
+use super::category::Category;
+use super::recovery_code::RecoveryCode;
+use super::slug_history::SlugHistory;
+use super::tag::Tag;
+use super::user::User;
+use crate::util::dialect::dialect;
+use crate::util::transaction::UnitOfWork;
+
+/// 导出文件格式版本
+const FORMAT_VERSION: u32 = 1;
+
+/// 导出的数据（包含密码哈希及两步验证密钥，需妥善保管）
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct Backup {
+    pub version: u32,
+    pub exported_at: i64,
+    pub users: Vec<User>,
+    pub recovery_codes: Vec<RecoveryCode>,
+    pub categories: Vec<Category>,
+    pub tags: Vec<Tag>,
+    pub articles: Vec<Article>,
+    pub article_tags: Vec<ArticleTag>,
+    /// 文章历史版本（较早的导出文件中没有）
+    #[serde(default)]
+    pub article_revisions: Vec<ArticleRevision>,
+    /// 旧 slug（较早的导出文件中没有）
+    #[serde(default)]
+    pub slug_history: Vec<SlugHistory>,
+}
+
+impl Backup {
+    /// 导出全部数据
+    /// ---
+    /// @return     Result<Backup, Error>
+    /// ---
+    pub async fn export() -> Result<Backup, Error> {
+        Ok(Backup {
+            version: FORMAT_VERSION,
+            exported_at: Local::now().timestamp(),
+            users: RB.fetch_list().await?,
+            recovery_codes: RB.fetch_list().await?,
+            categories: RB.fetch_list().await?,
+            tags: RB.fetch_list().await?,
+            articles: RB.fetch_list().await?,
+            article_tags: RB.fetch_list().await?,
+            article_revisions: RB.fetch_list().await?,
+            slug_history: RB.fetch_list().await?,
+        })
+    }
+
+    /// 导入到空数据库，全部数据在一个事务中写入
+    /// ---
+    /// @return     Result<(), Error>
+    /// ---
+    pub async fn import(&self) -> Result<(), Error> {
+        if self.version != FORMAT_VERSION {
+            return Err(Error::E(format!(
+                "unsupported export format version {}",
+                self.version
+            )));
+        }
+        let existing = RB.fetch_count::<User>().await?
+            + RB.fetch_count::<Category>().await?
+            + RB.fetch_count::<Tag>().await?
+            + RB.fetch_count::<Article>().await?;
+        if existing > 0 {
+            return Err(Error::E("import requires an empty database".to_string()));
+        }
+
+        let mut uow = UnitOfWork::begin(&RB).await?;
+
+        let skips = [Skip::Value(Bson::Null)];
+        for user in &self.users {
+            uow.save("insert_users", user, &skips).await?;
+        }
+        for code in &self.recovery_codes {
+            uow.save("insert_recovery_codes", code, &skips).await?;
+        }
+        for category in &self.categories {
+            uow.save("insert_categories", category, &skips).await?;
+        }
+        for tag in &self.tags {
+            uow.save("insert_tags", tag, &skips).await?;
+        }
+        for article in &self.articles {
+            // 目录在数据库中保存为 JSON 字符串，单独写入
+            let toc = article.toc.as_ref().map(|toc| toc.to_json());
+            let article = Article {
+                toc: None,
+                ..article.clone()
+            };
+            uow.save("insert_articles", &article, &skips).await?;
+            if toc.is_some() {
+                uow.exec(
+                    "update_article_toc",
+                    "UPDATE article SET toc = ? WHERE id = ?",
+                    vec![bson!(toc), bson!(article.id)],
+                )
+                .await?;
+            }
+        }
+        for article_tag in &self.article_tags {
+            uow.save("insert_article_tags", article_tag, &skips).await?;
+        }
+        for revision in &self.article_revisions {
+            uow.save("insert_article_revisions", revision, &skips)
+                .await?;
+        }
+        for history in &self.slug_history {
+            uow.save("insert_slug_history", history, &skips).await?;
+        }
+
+        // 插入了指定 id 的记录，需要同步自增序列
+        for table in [
+            "user",
+            "recovery_code",
+            "category",
+            "tag",
+            "article",
+            "article_revision",
+            "slug_history",
+        ] {
+            if let Some(sql) = dialect().reset_sequence(table) {
+                uow.exec("reset_sequences", &sql, vec![]).await?;
+            }
+        }
+
+        uow.commit().await?;
+        Ok(())
+    }
+}
@@ -2,12 +2,15 @@
 
 use crate::RB;
 use chrono::Local;
-use rbatis::crud::CRUD;
+use rbatis::crud::{Skip, CRUD};
 use rbatis::db::DBExecResult;
 use rbatis::sql;
 use rbatis::Error;
+use rbson::{bson, Bson};
 
 use super::article::Article;
+use super::slug_history::{SlugHistory, SlugKind};
+use crate::util::transaction::UnitOfWork;
 
 // 分类表
 #[crud_table(table_name:category)]
@@ -15,6 +18,8 @@ use super::article::Article;
 pub struct Category {
     pub id: Option<u32>,
     pub name: Option<String>,
+    /// 网址中使用的唯一标识
+    pub slug: Option<String>,
     pub created_at: Option<i64>,
     pub updated_at: Option<i64>,
 }
@@ -25,99 +30,137 @@ pub struct Category {
 pub struct CategoryVo {
     pub id: Option<u32>,
     pub name: Option<String>,
+    pub slug: Option<String>,
     pub blog_count: Option<u32>,
     pub created_at: Option<i64>,
     pub updated_at: Option<i64>,
 }
 
 impl Category {
-    /// 查询全部分类（带文章数量）
+    /// 查询全部分类（带已发布文章数量）
     /// ---
     /// @return     Result<Vec<CategoryVo>, Error>
     /// ---
     #[sql(
         RB,
-        "SELECT id, name, created_at, updated_at, (select count(*) FROM article WHERE category.id = article.cate_id) as blog_count from category"
+        "SELECT id, name, slug, created_at, updated_at, (select count(*) FROM article WHERE category.id = article.cate_id AND article.status = 'published') as blog_count from category"
     )]
     pub async fn find_all_with_blogcount() -> Result<Vec<CategoryVo>, Error> {}
 
     /// 根据id查询分类
     /// ---
-    /// @parameter      id          &str
+    /// @parameter      id          u32
     /// @return         Result<Option<Category>, Error>
     /// ---
-    pub async fn find_by_id(id: &str) -> Result<Option<Category>, Error> {
+    pub async fn find_by_id(id: u32) -> Result<Option<Category>, Error> {
         let w = RB.new_wrapper().eq("id", id);
         RB.fetch_by_wrapper(w).await
     }
 
-    /// 查询分类下的全部文章
+    /// 根据名称查询分类
     /// ---
-    /// @parameter      cate_id     &str
+    /// @parameter      name        &str
+    /// @return         Result<Option<Category>, Error>
+    /// ---
+    pub async fn find_by_name(name: &str) -> Result<Option<Category>, Error> {
+        let w = RB.new_wrapper().eq("name", name).limit(1);
+        let cates: Vec<Category> = RB.fetch_list_by_wrapper(w).await?;
+        Ok(cates.into_iter().next())
+    }
+
+    /// 根据slug查询分类
+    /// ---
+    /// @parameter      slug        &str
+    /// @return         Result<Option<Category>, Error>
+    /// ---
+    pub async fn find_by_slug(slug: &str) -> Result<Option<Category>, Error> {
+        let w = RB.new_wrapper().eq("slug", slug);
+        RB.fetch_by_wrapper(w).await
+    }
+
+    /// 查询分类下已发布的文章
+    /// ---
+    /// @parameter      cate_id     &u32
     /// @return         Result<Vec<Article>, Error>
     #[sql(
         RB,
-        "SELECT a.* FROM article a, category c WHERE a.cate_id = c.id AND c.id = ?"
+        "SELECT a.* FROM article a, category c WHERE a.cate_id = c.id AND c.id = ? AND a.status = 'published'"
     )]
-    pub async fn find_articles_by_cateid(cate_id: &str) -> Result<Vec<Article>, Error> {}
+    pub async fn find_articles_by_cateid(cate_id: &u32) -> Result<Vec<Article>, Error> {}
 
     /// 新增分类
     /// ---
     /// @parameter      name        &str
+    /// @parameter      slug        &str
     /// @return         Result<DBExecResult, Error>
     /// ---
-    pub async fn create(name: &str) -> Result<DBExecResult, Error> {
+    pub async fn create(name: &str, slug: &str) -> Result<DBExecResult, Error> {
         let cate = Category {
             id: None,
             name: Some(name.to_string()),
+            slug: Some(slug.to_string()),
             created_at: Some(Local::now().timestamp()),
             updated_at: Some(Local::now().timestamp()),
         };
-        RB.save(&cate, &[]).await
+        RB.save(&cate, &[Skip::Value(Bson::Null)]).await
     }
 
-    /// 更新分类
+    /// 更新分类，修改 slug 时记录旧 slug
     /// ---
-    /// @parameter      id              &str
+    /// @parameter      id              u32
     /// @parameter      new_name        &str
+    /// @parameter      new_slug        &str
     /// @return         Result<u64, Error>
     /// ---
-    pub async fn update(id: &str, new_name: &str) -> Result<u64, Error> {
-        let old_cate = Self::find_by_id(id).await;
-        match old_cate {
-            Ok(ocate) => match ocate {
-                Some(c) => {
-                    let ncate = Category {
-                        name: Some(new_name.to_string()),
-                        updated_at: Some(Local::now().timestamp()),
-                        ..c
-                    };
-                    let w = RB.new_wrapper().eq("id", id);
-                    return RB.update_by_wrapper(&ncate, w, &[]).await
-                }
-                None => return Err(Error::E("该分类不存在".to_string()))
-            },
-            Err(e) => {
-                return Err(e);
-            }
-        }
+    pub async fn update(id: u32, new_name: &str, new_slug: &str) -> Result<u64, Error> {
+        let old_cate = match Self::find_by_id(id).await? {
+            Some(c) => c,
+            None => return Err(Error::E("该分类不存在".to_string())),
+        };
+        let mut uow = UnitOfWork::begin(&RB).await?;
+        let res = uow
+            .exec(
+                "update_category",
+                "UPDATE category SET name = ?, slug = ?, updated_at = ? WHERE id = ?;",
+                vec![
+                    bson!(new_name),
+                    bson!(new_slug),
+                    bson!(Local::now().timestamp()),
+                    bson!(id),
+                ],
+            )
+            .await?;
+        SlugHistory::record(
+            &mut uow,
+            SlugKind::Category,
+            id,
+            old_cate.slug.as_deref(),
+            new_slug,
+        )
+        .await?;
+        uow.commit().await?;
+        Ok(res.rows_affected)
     }
 
-    /// 删除分类
+    /// 删除分类及其旧 slug
     /// ---
-    /// @parameter      id      &str
+    /// @parameter      id      u32
     /// @return Result<u64, Error>
     /// ---
-    pub async fn remove(id: &str) -> Result<u64, Error> {
-        let cate = Self::find_by_id(id).await;
-        match cate {
-            Ok(c1) => match c1 {
-                Some(c2) => return RB.remove_by_column::<Category, _>("id", c2.id).await,
-                None => return Err(Error::E("该分类不存在".to_string())),
-            },
-            Err(e) => {
-                return Err(e);
-            }
+    pub async fn remove(id: u32) -> Result<u64, Error> {
+        if Self::find_by_id(id).await?.is_none() {
+            return Err(Error::E("该分类不存在".to_string()));
         }
+        let mut uow = UnitOfWork::begin(&RB).await?;
+        SlugHistory::forget(&mut uow, SlugKind::Category, id).await?;
+        let res = uow
+            .exec(
+                "delete_category",
+                "DELETE FROM category WHERE id = ?;",
+                vec![bson!(id)],
+            )
+            .await?;
+        uow.commit().await?;
+        Ok(res.rows_affected)
     }
 }
@@ -0,0 +1,49 @@
+// 登录失败事件模型及关联函数
+use crate::RB;
+use chrono::Local;
+use rbatis::crud::{Skip, CRUD};
+use rbatis::Error;
+use rbson::Bson;
+
+// 登录失败事件表
+#[crud_table(table_name:login_event)]
+#[derive(Clone, Debug)]
+pub struct LoginEvent {
+    pub id: Option<u32>,
+    pub username: Option<String>,
+    pub ip: Option<String>,
+    /// failed: 用户名或密码错误；2fa_failed: 动态码错误；throttled: 限流中被拒绝；locked: 触发锁定
+    pub event: Option<String>,
+    pub created_at: Option<i64>,
+}
+
+impl LoginEvent {
+    /// 记录事件
+    /// ---
+    /// @parameter  username    &str
+    /// @parameter  ip          Option<String>
+    /// @parameter  event       &str
+    /// @return     Result<(), Error>
+    /// ---
+    pub async fn record(username: &str, ip: Option<String>, event: &str) -> Result<(), Error> {
+        let record = LoginEvent {
+            id: None,
+            username: Some(username.chars().take(64).collect()),
+            ip,
+            event: Some(event.to_string()),
+            created_at: Some(Local::now().timestamp()),
+        };
+        RB.save(&record, &[Skip::Value(Bson::Null)]).await?;
+        Ok(())
+    }
+
+    /// 最近的事件
+    /// ---
+    /// @parameter  limit       u64
+    /// @return     Result<Vec<LoginEvent>, Error>
+    /// ---
+    pub async fn recent(limit: u64) -> Result<Vec<LoginEvent>, Error> {
+        let w = RB.new_wrapper().order_by(false, &["id"]).limit(limit);
+        RB.fetch_list_by_wrapper(w).await
+    }
+}
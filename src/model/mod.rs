@@ -1,4 +1,11 @@
 pub mod article;
+pub mod article_revision;
+pub mod backup;
 pub mod category;
+pub mod login_event;
+pub mod recovery_code;
+pub mod refresh_token;
+pub mod slug_history;
 pub mod tag;
+pub mod token_revocation;
 pub mod user;
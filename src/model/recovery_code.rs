@@ -0,0 +1,91 @@
+// 两步验证恢复码模型及关联函数
+use crate::util::crypto::sha256_hex as digest;
+use crate::util::totp::normalize_recovery_code;
+use crate::util::transaction::UnitOfWork;
+use crate::RB;
+use chrono::Local;
+use rbatis::crud::CRUD;
+use rbatis::executor::Executor;
+use rbatis::Error;
+use rbson::bson;
+
+// 恢复码表（只保存恢复码的 sha256 摘要）
+#[crud_table(table_name:recovery_code)]
+#[derive(Clone, Debug)]
+pub struct RecoveryCode {
+    pub id: Option<u32>,
+    pub user_id: Option<u32>,
+    pub code_hash: Option<String>,
+    pub used_at: Option<i64>,
+    pub created_at: Option<i64>,
+}
+
+impl RecoveryCode {
+    /// 替换用户的全部恢复码
+    /// ---
+    /// @parameter  user_id     u32
+    /// @parameter  codes       &[String]
+    /// @return     Result<(), Error>
+    /// ---
+    pub async fn replace(user_id: u32, codes: &[String]) -> Result<(), Error> {
+        let now = Local::now().timestamp();
+        let mut uow = UnitOfWork::begin(&RB).await?;
+
+        uow.exec(
+            "delete_recovery_codes",
+            "DELETE FROM recovery_code WHERE user_id = ?;",
+            vec![bson!(user_id)],
+        )
+        .await?;
+        for code in codes {
+            uow.exec(
+                "insert_recovery_code",
+                "INSERT INTO recovery_code (user_id, code_hash, created_at) VALUES (?, ?, ?);",
+                vec![
+                    bson!(user_id),
+                    bson!(digest(&normalize_recovery_code(code))),
+                    bson!(now),
+                ],
+            )
+            .await?;
+        }
+
+        // 提交事务
+        uow.commit().await?;
+        Ok(())
+    }
+
+    /// 使用恢复码，每个恢复码只能使用一次
+    /// ---
+    /// @parameter  user_id     u32
+    /// @parameter  code        &str
+    /// @return     Result<bool, Error>     恢复码有效且未使用时返回 true
+    /// ---
+    pub async fn consume(user_id: u32, code: &str) -> Result<bool, Error> {
+        let res = RB
+            .exec(
+                "UPDATE recovery_code SET used_at = ? WHERE user_id = ? AND code_hash = ? AND used_at IS NULL",
+                vec![
+                    bson!(Local::now().timestamp()),
+                    bson!(user_id),
+                    bson!(digest(&normalize_recovery_code(code))),
+                ],
+            )
+            .await?;
+        Ok(res.rows_affected == 1)
+    }
+
+    /// 剩余可用的恢复码数量
+    /// ---
+    /// @parameter  user_id     u32
+    /// @return     Result<u64, Error>
+    /// ---
+    pub async fn remaining(user_id: u32) -> Result<u64, Error> {
+        let w = RB
+            .new_wrapper()
+            .eq("user_id", user_id)
+            .and()
+            .is_null("used_at");
+        RB.fetch_count_by_wrapper::<RecoveryCode>(w).await
+    }
+}
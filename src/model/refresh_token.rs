@@ -0,0 +1,161 @@
+// 刷新令牌模型及关联函数
+use crate::util::crypto::{random_token, sha256_hex as digest};
+use crate::RB;
+use chrono::Local;
+use rbatis::crud::{Skip, CRUD};
+use rbatis::executor::Executor;
+use rbatis::Error;
+use rbson::{bson, Bson};
+
+// 刷新令牌表（只保存令牌的 sha256 摘要）
+#[crud_table(table_name:refresh_token)]
+#[derive(Clone, Debug)]
+pub struct RefreshToken {
+    pub id: Option<u32>,
+    pub user_id: Option<u32>,
+    pub family_id: Option<String>,
+    pub token_hash: Option<String>,
+    pub expires_at: Option<i64>,
+    pub revoked_at: Option<i64>,
+    pub created_at: Option<i64>,
+}
+
+/// 刷新令牌轮换结果
+pub enum Rotation {
+    /// 轮换成功，返回用户 id 和新的刷新令牌
+    Rotated { user_id: u32, refresh_token: String },
+    /// 令牌不存在或已过期
+    Invalid,
+    /// 已使用过的令牌再次出现，整个令牌族已被吊销
+    Reused,
+}
+
+impl RefreshToken {
+    /// 签发刷新令牌
+    /// ---
+    /// @parameter  user_id     u32
+    /// @parameter  family_id   Option<&str>    为空时开启新的令牌族（新会话）
+    /// @parameter  ttl_secs    i64
+    /// @return     Result<String, Error>
+    /// ---
+    pub async fn issue(
+        user_id: u32,
+        family_id: Option<&str>,
+        ttl_secs: i64,
+    ) -> Result<String, Error> {
+        let now = Local::now().timestamp();
+        let token = random_token(32);
+        let record = RefreshToken {
+            id: None,
+            user_id: Some(user_id),
+            family_id: Some(
+                family_id
+                    .map(|f| f.to_string())
+                    .unwrap_or_else(|| random_token(16)),
+            ),
+            token_hash: Some(digest(&token)),
+            expires_at: Some(now + ttl_secs),
+            revoked_at: None,
+            created_at: Some(now),
+        };
+        RB.save(&record, &[Skip::Value(Bson::Null)]).await?;
+
+        // 顺带清理过期令牌
+        RB.exec(
+            "DELETE FROM refresh_token WHERE expires_at < ?",
+            vec![bson!(now)],
+        )
+        .await?;
+        Ok(token)
+    }
+
+    /// 根据令牌查询记录
+    async fn find_by_token(token: &str) -> Result<Option<RefreshToken>, Error> {
+        let w = RB.new_wrapper().eq("token_hash", digest(token));
+        RB.fetch_by_wrapper(w).await
+    }
+
+    /// 轮换刷新令牌：旧令牌作废并在同一令牌族中签发新令牌
+    /// ---
+    /// @parameter  token       &str
+    /// @parameter  ttl_secs    i64
+    /// @return     Result<Rotation, Error>
+    /// ---
+    pub async fn rotate(token: &str, ttl_secs: i64) -> Result<Rotation, Error> {
+        let now = Local::now().timestamp();
+        let record = match Self::find_by_token(token).await? {
+            Some(r) => r,
+            None => return Ok(Rotation::Invalid),
+        };
+        let (id, user_id, family_id) = match (record.id, record.user_id, record.family_id) {
+            (Some(id), Some(uid), Some(fid)) => (id, uid, fid),
+            _ => return Ok(Rotation::Invalid),
+        };
+
+        if record.revoked_at.is_some() {
+            // 令牌被重复使用，说明可能已泄露，吊销整个令牌族
+            Self::revoke_family(&family_id).await?;
+            return Ok(Rotation::Reused);
+        }
+        if record.expires_at.unwrap_or(0) < now {
+            return Ok(Rotation::Invalid);
+        }
+
+        // 只有一个并发请求能成功作废旧令牌
+        let res = RB
+            .exec(
+                "UPDATE refresh_token SET revoked_at = ? WHERE id = ? AND revoked_at IS NULL",
+                vec![bson!(now), bson!(id)],
+            )
+            .await?;
+        if res.rows_affected != 1 {
+            Self::revoke_family(&family_id).await?;
+            return Ok(Rotation::Reused);
+        }
+
+        let refresh_token = Self::issue(user_id, Some(&family_id), ttl_secs).await?;
+        Ok(Rotation::Rotated {
+            user_id,
+            refresh_token,
+        })
+    }
+
+    /// 吊销令牌所在的令牌族（退出当前会话）
+    /// ---
+    /// @parameter  token       &str
+    /// @parameter  user_id     u32         只能吊销自己的令牌
+    /// @return     Result<(), Error>
+    /// ---
+    pub async fn revoke(token: &str, user_id: u32) -> Result<(), Error> {
+        if let Some(record) = Self::find_by_token(token).await? {
+            if record.user_id == Some(user_id) {
+                if let Some(family_id) = record.family_id {
+                    Self::revoke_family(&family_id).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 吊销整个令牌族
+    pub async fn revoke_family(family_id: &str) -> Result<u64, Error> {
+        let res = RB
+            .exec(
+                "UPDATE refresh_token SET revoked_at = ? WHERE family_id = ? AND revoked_at IS NULL",
+                vec![bson!(Local::now().timestamp()), bson!(family_id)],
+            )
+            .await?;
+        Ok(res.rows_affected)
+    }
+
+    /// 吊销用户的全部刷新令牌
+    pub async fn revoke_all_for_user(user_id: u32) -> Result<u64, Error> {
+        let res = RB
+            .exec(
+                "UPDATE refresh_token SET revoked_at = ? WHERE user_id = ? AND revoked_at IS NULL",
+                vec![bson!(Local::now().timestamp()), bson!(user_id)],
+            )
+            .await?;
+        Ok(res.rows_affected)
+    }
+}
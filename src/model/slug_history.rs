@@ -0,0 +1,138 @@
+// slug 历史模型及关联函数
+// 文章、分类、标签修改 slug 后，旧 slug 记录在这里，按旧 slug 访问时重定向到当前 slug
+use crate::RB;
+use chrono::Local;
+use rbatis::crud::CRUD;
+use rbatis::executor::Executor;
+use rbatis::Error;
+use rbson::bson;
+
+use crate::util::transaction::{StepError, UnitOfWork};
+
+// slug 历史表
+#[crud_table(table_name:slug_history)]
+#[derive(Clone, Debug)]
+pub struct SlugHistory {
+    pub id: Option<u32>,
+    /// 见 `SlugKind`
+    pub kind: Option<String>,
+    pub slug: Option<String>,
+    /// 文章、分类或标签的 id
+    pub target_id: Option<u32>,
+    pub created_at: Option<i64>,
+}
+
+/// 使用 slug 的数据类型，每种类型的 slug 各自唯一
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlugKind {
+    Article,
+    Category,
+    Tag,
+}
+
+impl SlugKind {
+    /// slug_history 中保存的类型名，与数据所在的表同名
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SlugKind::Article => "article",
+            SlugKind::Category => "category",
+            SlugKind::Tag => "tag",
+        }
+    }
+}
+
+impl SlugHistory {
+    /// 旧 slug 指向的数据 id
+    /// ---
+    /// @parameter  kind    SlugKind
+    /// @parameter  slug    &str
+    /// @return     Result<Option<u32>, Error>
+    /// ---
+    pub async fn find_target(kind: SlugKind, slug: &str) -> Result<Option<u32>, Error> {
+        let w = RB
+            .new_wrapper()
+            .eq("kind", kind.as_str())
+            .eq("slug", slug)
+            .limit(1);
+        let rows: Vec<SlugHistory> = RB.fetch_list_by_wrapper(w).await?;
+        Ok(rows.into_iter().next().and_then(|h| h.target_id))
+    }
+
+    /// 在事务中记录 slug 的变更：旧 slug 加入历史，新 slug 从历史中移除（改回以前的 slug 时）
+    /// ---
+    /// @parameter  uow         &mut UnitOfWork
+    /// @parameter  kind        SlugKind
+    /// @parameter  target_id   u32
+    /// @parameter  old_slug    Option<&str>
+    /// @parameter  new_slug    &str
+    /// @return     Result<(), StepError>
+    /// ---
+    pub async fn record(
+        uow: &mut UnitOfWork<'_>,
+        kind: SlugKind,
+        target_id: u32,
+        old_slug: Option<&str>,
+        new_slug: &str,
+    ) -> Result<(), StepError> {
+        if old_slug == Some(new_slug) {
+            return Ok(());
+        }
+        uow.exec(
+            "delete_slug_history",
+            "DELETE FROM slug_history WHERE kind = ? AND slug = ?;",
+            vec![bson!(kind.as_str()), bson!(new_slug)],
+        )
+        .await?;
+        if let Some(old_slug) = old_slug {
+            uow.exec(
+                "insert_slug_history",
+                "INSERT INTO slug_history (kind, slug, target_id, created_at) VALUES (?, ?, ?, ?);",
+                vec![
+                    bson!(kind.as_str()),
+                    bson!(old_slug),
+                    bson!(target_id),
+                    bson!(Local::now().timestamp()),
+                ],
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// 在事务中删除已删除数据的全部旧 slug
+    /// ---
+    /// @parameter  uow         &mut UnitOfWork
+    /// @parameter  kind        SlugKind
+    /// @parameter  target_id   u32
+    /// @return     Result<(), StepError>
+    /// ---
+    pub async fn forget(
+        uow: &mut UnitOfWork<'_>,
+        kind: SlugKind,
+        target_id: u32,
+    ) -> Result<(), StepError> {
+        uow.exec(
+            "delete_slug_history",
+            "DELETE FROM slug_history WHERE kind = ? AND target_id = ?;",
+            vec![bson!(kind.as_str()), bson!(target_id)],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// 为没有 slug 的数据设置 slug（生成已有数据的 slug）
+    /// ---
+    /// @parameter  kind    SlugKind
+    /// @parameter  id      u32
+    /// @parameter  slug    &str
+    /// @return     Result<u64, Error>     受影响行数
+    /// ---
+    pub async fn fill(kind: SlugKind, id: u32, slug: &str) -> Result<u64, Error> {
+        let sql = format!(
+            "UPDATE {} SET slug = ? WHERE id = ? AND slug IS NULL",
+            kind.as_str()
+        );
+        let res = RB.exec(&sql, vec![bson!(slug), bson!(id)]).await?;
+        Ok(res.rows_affected)
+    }
+}
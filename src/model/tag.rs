@@ -1,11 +1,14 @@
 // 标签模型及关联函数
 use crate::RB;
 use chrono::Local;
-use rbatis::crud::CRUD;
+use rbatis::crud::{Skip, CRUD};
 use rbatis::db::DBExecResult;
 use rbatis::{crud_table, Error};
+use rbson::{bson, Bson};
 
 use super::article::Article;
+use super::slug_history::{SlugHistory, SlugKind};
+use crate::util::transaction::UnitOfWork;
 
 // 标签表
 #[crud_table(table_name:tag)]
@@ -13,6 +16,8 @@ use super::article::Article;
 pub struct Tag {
     pub id: Option<u32>,
     pub name: Option<String>,
+    /// 网址中使用的唯一标识
+    pub slug: Option<String>,
     pub created_at: Option<i64>,
     pub updated_at: Option<i64>,
 }
@@ -23,18 +28,19 @@ pub struct Tag {
 pub struct TagVo {
     pub id: Option<u32>,
     pub name: Option<String>,
+    pub slug: Option<String>,
     pub blog_count: Option<u32>,
     pub created_at: Option<i64>,
     pub updated_at: Option<i64>,
 }
 
 impl Tag {
-    /// 查询全部标签（带文章数量）
+    /// 查询全部标签（带已发布文章数量）
     /// ---
     /// @return     Result<Vec<Tag>, Error>
     /// ---
     #[sql(
-        RB, "SELECT `id`, `name`, `created_at`, `updated_at`, (SELECT COUNT(*) FROM `article` INNER JOIN `article_to_tag` on `article_id` WHERE `tag`.`id` = `article_to_tag`.`id`) AS `blog_count` FROM `tag`"
+        RB, "SELECT t.id, t.name, t.slug, t.created_at, t.updated_at, (SELECT COUNT(*) FROM article_to_tag att, article a WHERE att.tag_id = t.id AND att.article_id = a.id AND a.status = 'published') AS blog_count FROM tag t"
     )]
     pub async fn find_all_with_blogcount() -> Result<Vec<TagVo>, Error> {}
 
@@ -42,77 +48,127 @@ impl Tag {
     /// ---
     /// @parameter      id      u32
     pub async fn find_by_id(id: u32) -> Result<Option<Tag>, Error> {
-        let w = RB.new_wrapper().eq("id", id.to_string());
+        let w = RB.new_wrapper().eq("id", id);
         RB.fetch_by_wrapper(w).await
     }
 
-    /// 查询标签下的全部文章
+    /// 根据名称查询标签
     /// ---
-    /// @parameter      id      &str
+    /// @parameter      name        &str
+    /// @return         Result<Option<Tag>, Error>
+    /// ---
+    pub async fn find_by_name(name: &str) -> Result<Option<Tag>, Error> {
+        let w = RB.new_wrapper().eq("name", name).limit(1);
+        let tags: Vec<Tag> = RB.fetch_list_by_wrapper(w).await?;
+        Ok(tags.into_iter().next())
+    }
+
+    /// 根据slug查询标签
+    /// ---
+    /// @parameter      slug        &str
+    /// @return         Result<Option<Tag>, Error>
+    /// ---
+    pub async fn find_by_slug(slug: &str) -> Result<Option<Tag>, Error> {
+        let w = RB.new_wrapper().eq("slug", slug);
+        RB.fetch_by_wrapper(w).await
+    }
+
+    /// 根据id列表查询标签
+    /// ---
+    /// @parameter      ids         &[u32]
+    /// @return         Result<Vec<Tag>, Error>
+    /// ---
+    pub async fn find_by_ids(ids: &[u32]) -> Result<Vec<Tag>, Error> {
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+        let w = RB.new_wrapper().r#in("id", ids);
+        RB.fetch_list_by_wrapper(w).await
+    }
+
+    /// 查询标签下已发布的文章
+    /// ---
+    /// @parameter      tag_id  &u32
     /// @return         Result<Vec<Article, Error>
     /// ---
     #[sql(
-        RB, "SELECT a.* FROM article a WHERE a.id IN (SELECT at2.article_id FROM article_tag at2 WHERE at2.tag_id = ?)"
+        RB, "SELECT a.* FROM article a WHERE a.status = 'published' AND a.id IN (SELECT at2.article_id FROM article_to_tag at2 WHERE at2.tag_id = ?)"
     )]
-    pub async fn find_articles_by_tagid(tag_id: &str) -> Result<Vec<Article>, Error> {}
+    pub async fn find_articles_by_tagid(tag_id: &u32) -> Result<Vec<Article>, Error> {}
 
     /// 新增标签
     /// ---
     /// @parameter      name        &str
+    /// @parameter      slug        &str
     /// @return         Result<DBExecResult, Error>
     /// ---
-    pub async fn create(name: &str) -> Result<DBExecResult, Error> {
+    pub async fn create(name: &str, slug: &str) -> Result<DBExecResult, Error> {
         let tag = Tag {
             id: None,
             name: Some(name.to_string()),
+            slug: Some(slug.to_string()),
             created_at: Some(Local::now().timestamp()),
             updated_at: Some(Local::now().timestamp()),
         };
-        RB.save(&tag, &[]).await
+        RB.save(&tag, &[Skip::Value(Bson::Null)]).await
     }
 
-    /// 更新标签
+    /// 更新标签，修改 slug 时记录旧 slug
     /// ---
     /// @parameter      id          u32
-    /// @parameter      new_name    u32
+    /// @parameter      new_name    &str
+    /// @parameter      new_slug    &str
     /// @return         Result<u64, Error>
     /// ---
-    pub async fn update(id: u32, new_name: &str) -> Result<u64, Error> {
-        let old_tag = Self::find_by_id(id).await;
-        match old_tag {
-            Ok(otag) => match otag {
-                Some(t) => {
-                    let ntag = Tag {
-                        name: Some(new_name.to_string()),
-                        updated_at: Some(Local::now().timestamp()),
-                        ..t
-                    };
-                    let w = RB.new_wrapper().eq("id", id);
-                    return RB.update_by_wrapper(&ntag, w, &[]).await;
-                }
-                None => return Err(Error::E("该标签不存在".to_string())),
-            },
-            Err(e) => {
-                return Err(e);
-            }
-        }
+    pub async fn update(id: u32, new_name: &str, new_slug: &str) -> Result<u64, Error> {
+        let old_tag = match Self::find_by_id(id).await? {
+            Some(t) => t,
+            None => return Err(Error::E("该标签不存在".to_string())),
+        };
+        let mut uow = UnitOfWork::begin(&RB).await?;
+        let res = uow
+            .exec(
+                "update_tag",
+                "UPDATE tag SET name = ?, slug = ?, updated_at = ? WHERE id = ?;",
+                vec![
+                    bson!(new_name),
+                    bson!(new_slug),
+                    bson!(Local::now().timestamp()),
+                    bson!(id),
+                ],
+            )
+            .await?;
+        SlugHistory::record(
+            &mut uow,
+            SlugKind::Tag,
+            id,
+            old_tag.slug.as_deref(),
+            new_slug,
+        )
+        .await?;
+        uow.commit().await?;
+        Ok(res.rows_affected)
     }
 
-    /// 删除标签
+    /// 删除标签及其旧 slug
     /// ---
     /// @parameter      id      u32
     /// @return         Result<u64, Error>
     /// ---
     pub async fn remove(id: u32) -> Result<u64, Error> {
-        let tag = Self::find_by_id(id).await;
-        match tag {
-            Ok(t1) => match t1 {
-                Some(t2) => return RB.remove_by_column::<Tag, _>("id", t2.id).await,
-                None => return Err(Error::E("该标签不存在".to_string())),
-            },
-            Err(e) => {
-                return Err(e);
-            }
+        if Self::find_by_id(id).await?.is_none() {
+            return Err(Error::E("该标签不存在".to_string()));
         }
+        let mut uow = UnitOfWork::begin(&RB).await?;
+        SlugHistory::forget(&mut uow, SlugKind::Tag, id).await?;
+        let res = uow
+            .exec(
+                "delete_tag",
+                "DELETE FROM tag WHERE id = ?;",
+                vec![bson!(id)],
+            )
+            .await?;
+        uow.commit().await?;
+        Ok(res.rows_affected)
     }
 }
@@ -0,0 +1,86 @@
+// 访问令牌吊销列表模型及关联函数
+use crate::RB;
+use chrono::Local;
+use rbatis::crud::{Skip, CRUD};
+use rbatis::executor::Executor;
+use rbatis::Error;
+use rbson::{bson, Bson};
+
+// 吊销记录表
+// jti 不为空时吊销单个令牌；jti 为空时吊销该用户在 revoked_at 及之前签发的全部令牌
+// revoked_at 为毫秒时间戳，与访问令牌中的 iat_ms 比较
+#[crud_table(table_name:token_revocation)]
+#[derive(Clone, Debug)]
+pub struct TokenRevocation {
+    pub id: Option<u32>,
+    pub jti: Option<String>,
+    pub user_id: Option<u32>,
+    pub revoked_at: Option<i64>,
+    pub expires_at: Option<i64>,
+}
+
+impl TokenRevocation {
+    /// 吊销单个访问令牌
+    /// ---
+    /// @parameter  jti         &str
+    /// @parameter  user_id     u32
+    /// @parameter  expires_at  i64     令牌过期后记录即可清理
+    /// @return     Result<(), Error>
+    /// ---
+    pub async fn revoke_token(jti: &str, user_id: u32, expires_at: i64) -> Result<(), Error> {
+        let record = TokenRevocation {
+            id: None,
+            jti: Some(jti.to_string()),
+            user_id: Some(user_id),
+            revoked_at: Some(Local::now().timestamp_millis()),
+            expires_at: Some(expires_at),
+        };
+        RB.save(&record, &[Skip::Value(Bson::Null)]).await?;
+        Self::purge_expired().await
+    }
+
+    /// 吊销用户当前已签发的全部访问令牌
+    /// ---
+    /// @parameter  user_id     u32
+    /// @parameter  expires_at  i64     不早于最后一个令牌的过期时间
+    /// @return     Result<(), Error>
+    /// ---
+    pub async fn revoke_user(user_id: u32, expires_at: i64) -> Result<(), Error> {
+        let record = TokenRevocation {
+            id: None,
+            jti: None,
+            user_id: Some(user_id),
+            revoked_at: Some(Local::now().timestamp_millis()),
+            expires_at: Some(expires_at),
+        };
+        RB.save(&record, &[Skip::Value(Bson::Null)]).await?;
+        Self::purge_expired().await
+    }
+
+    /// 检查令牌是否已被吊销
+    /// ---
+    /// @parameter  jti         &str
+    /// @parameter  user_id     u32
+    /// @parameter  issued_at   i64     签发时间（毫秒）
+    /// @return     Result<bool, Error>
+    /// ---
+    pub async fn is_revoked(jti: &str, user_id: u32, issued_at: i64) -> Result<bool, Error> {
+        let count: u64 = RB
+            .fetch(
+                "SELECT COUNT(*) FROM token_revocation WHERE jti = ? OR (jti IS NULL AND user_id = ? AND revoked_at >= ?)",
+                vec![bson!(jti), bson!(user_id), bson!(issued_at)],
+            )
+            .await?;
+        Ok(count > 0)
+    }
+
+    /// 清理已过期的吊销记录
+    async fn purge_expired() -> Result<(), Error> {
+        RB.exec(
+            "DELETE FROM token_revocation WHERE expires_at < ?",
+            vec![bson!(Local::now().timestamp())],
+        )
+        .await?;
+        Ok(())
+    }
+}
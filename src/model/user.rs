@@ -1,10 +1,17 @@
 // 用户模型及关联函数
 use crate::RB;
-use rbatis::crud::CRUD;
+use chrono::Local;
+use rbatis::crud::{Skip, CRUD};
+use rbatis::executor::Executor;
 use rbatis::Error;
+use rbson::{bson, Bson};
 
-use crypto::digest::Digest;
-use crypto::md5::Md5;
+use super::refresh_token::RefreshToken;
+use super::token_revocation::TokenRevocation;
+use crate::util::dialect::insert_returning_id;
+use crate::util::password::{self, Verified};
+use crate::util::token::TOKEN_CONFIG;
+use crate::util::transaction::UnitOfWork;
 
 // 分类表
 #[crud_table(table_name:user)]
@@ -14,26 +21,52 @@ pub struct User {
     pub username: Option<String>,
     pub password: Option<String>,
     pub nickname: Option<String>,
+    pub role: Option<String>,
+    pub avatar: Option<String>,
+    pub bio: Option<String>,
+    pub email: Option<String>,
+    pub website: Option<String>,
+    /// 已启用的 TOTP 密钥（base32），为空表示未开启两步验证
+    pub totp_secret: Option<String>,
+    /// 等待确认的 TOTP 密钥
+    pub totp_pending_secret: Option<String>,
+    /// 最近一次使用的 TOTP 时间步，防止动态码重放
+    pub totp_last_step: Option<i64>,
+    pub created_at: Option<i64>,
+    pub updated_at: Option<i64>,
 }
 
-// 分类表Vo
+// 分类表Vo（公开资料，不含密码）
 #[crud_table(table_name:user)]
 #[derive(Clone, Debug)]
 pub struct UserVo {
     pub id: Option<u32>,
     pub username: Option<String>,
     pub nickname: Option<String>,
+    pub role: Option<String>,
+    pub avatar: Option<String>,
+    pub bio: Option<String>,
+    pub email: Option<String>,
+    pub website: Option<String>,
+    pub created_at: Option<i64>,
 }
 
-/// md5 加密函数
-/// ---
-/// @parameter  input           S
-/// @return     encodeString    String
-/// ---
-fn md5<S: Into<String>>(input: S) -> String {
-    let mut md5 = Md5::new();
-    md5.input_str(&input.into());
-    md5.result_str()
+// 新增用户时提交的数据
+pub struct NewUser {
+    pub username: String,
+    pub password: String,
+    pub role: String,
+    pub profile: UserProfile,
+}
+
+// 可修改的用户资料，为 None 的字段保持不变
+#[derive(Default)]
+pub struct UserProfile {
+    pub nickname: Option<String>,
+    pub avatar: Option<String>,
+    pub bio: Option<String>,
+    pub email: Option<String>,
+    pub website: Option<String>,
 }
 
 impl User {
@@ -44,16 +77,280 @@ impl User {
     /// @return     Result<User, Error>
     /// ---
     pub async fn login(username: &str, password: &str) -> Result<Option<User>, Error> {
-        let encode_pwd = md5(password);
-        println!("加密后的密码： {}", encode_pwd);
-        let w = RB
-            .new_wrapper()
-            .eq("username", username)
-            .and()
-            .eq("password", encode_pwd);
+        let user = match Self::find_by_username(username).await? {
+            Some(u) => u,
+            None => {
+                password::dummy_verify(password).await;
+                return Ok(None);
+            }
+        };
+
+        let stored = user.password.clone().unwrap_or_default();
+        match password::verify_password(password, &stored).await {
+            Verified::Valid => Ok(Some(user)),
+            Verified::NeedsRehash => {
+                // 旧版 md5 或参数过时的哈希，登录成功后透明升级
+                if let Some(id) = user.id {
+                    if let Err(e) = Self::update_password(id, password).await {
+                        log::warn!("用户 {} 密码哈希升级失败: {}", id, e);
+                    }
+                }
+                Ok(Some(user))
+            }
+            Verified::Invalid => Ok(None),
+        }
+    }
+
+    /// 更新密码（Argon2id 哈希后保存）
+    /// ---
+    /// @parameter  id          u32
+    /// @parameter  password    &str
+    /// @return     Result<u64, Error>
+    /// ---
+    pub async fn update_password(id: u32, password: &str) -> Result<u64, Error> {
+        let hash = password::hash_password(password).await?;
+        let res = RB
+            .exec(
+                "UPDATE user SET password = ? WHERE id = ?",
+                vec![bson!(hash), bson!(id)],
+            )
+            .await?;
+        Ok(res.rows_affected)
+    }
+
+    /// 根据id查询用户
+    /// ---
+    /// @parameter  id          u32
+    /// @return     Result<Option<User>, Error>
+    /// ---
+    pub async fn find_by_id(id: u32) -> Result<Option<User>, Error> {
+        let w = RB.new_wrapper().eq("id", id);
+        RB.fetch_by_wrapper(w).await
+    }
+
+    /// 根据用户名查询用户
+    /// ---
+    /// @parameter  username    &str
+    /// @return     Result<Option<User>, Error>
+    /// ---
+    pub async fn find_by_username(username: &str) -> Result<Option<User>, Error> {
+        let w = RB.new_wrapper().eq("username", username);
         RB.fetch_by_wrapper(w).await
     }
 
+    /// 用户名是否已存在
+    /// ---
+    /// @parameter  username    &str
+    /// @return     Result<bool, Error>
+    /// ---
+    pub async fn username_exists(username: &str) -> Result<bool, Error> {
+        let w = RB.new_wrapper().eq("username", username);
+        let count = RB.fetch_count_by_wrapper::<User>(w).await?;
+        Ok(count > 0)
+    }
+
+    /// 新增用户
+    /// ---
+    /// @parameter  new_user    NewUser
+    /// @return     Result<Option<i64>, Error>     新用户 id
+    /// ---
+    pub async fn create(new_user: NewUser) -> Result<Option<i64>, Error> {
+        if Self::username_exists(&new_user.username).await? {
+            return Err(Error::E("用户名已存在".to_string()));
+        }
+        let hash = password::hash_password(&new_user.password).await?;
+        let now = Local::now().timestamp();
+        let profile = new_user.profile;
+        let nickname = profile
+            .nickname
+            .unwrap_or_else(|| new_user.username.clone());
+        let mut conn = RB.acquire().await?;
+        insert_returning_id(
+            &mut conn,
+            "INSERT INTO user (username, password, nickname, role, avatar, bio, email, website, created_at, updated_at) VALUES (?,?,?,?,?,?,?,?,?,?)",
+            vec![
+                bson!(new_user.username),
+                bson!(hash),
+                bson!(nickname),
+                bson!(new_user.role),
+                bson!(profile.avatar),
+                bson!(profile.bio),
+                bson!(profile.email),
+                bson!(profile.website),
+                bson!(now),
+                bson!(now),
+            ],
+        )
+        .await
+    }
+
+    /// 更新用户资料
+    /// ---
+    /// @parameter  id          u32
+    /// @parameter  profile     UserProfile
+    /// @parameter  role        Option<String>  仅管理员可以修改
+    /// @return     Result<u64, Error>
+    /// ---
+    pub async fn update_profile(
+        id: u32,
+        profile: UserProfile,
+        role: Option<String>,
+    ) -> Result<u64, Error> {
+        if Self::find_by_id(id).await?.is_none() {
+            return Err(Error::E("用户不存在".to_string()));
+        }
+        let user = User {
+            id: None,
+            username: None,
+            password: None,
+            nickname: profile.nickname,
+            role,
+            avatar: profile.avatar,
+            bio: profile.bio,
+            email: profile.email,
+            website: profile.website,
+            totp_secret: None,
+            totp_pending_secret: None,
+            totp_last_step: None,
+            created_at: None,
+            updated_at: Some(Local::now().timestamp()),
+        };
+        let w = RB.new_wrapper().eq("id", id);
+        RB.update_by_wrapper(&user, w, &[Skip::Value(Bson::Null)])
+            .await
+    }
+
+    /// 修改密码（需校验旧密码）
+    /// ---
+    /// @parameter  id              u32
+    /// @parameter  old_password    &str
+    /// @parameter  new_password    &str
+    /// @return     Result<bool, Error>     旧密码错误时返回 false
+    /// ---
+    pub async fn change_password(
+        id: u32,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<bool, Error> {
+        if !Self::check_password(id, old_password).await? {
+            return Ok(false);
+        }
+        Self::update_password(id, new_password).await?;
+        Ok(true)
+    }
+
+    /// 吊销用户的全部访问令牌和刷新令牌
+    /// ---
+    /// @parameter  id          u32
+    /// @return     Result<(), Error>
+    /// ---
+    pub async fn revoke_sessions(id: u32) -> Result<(), Error> {
+        // 访问令牌最迟在一个有效期后全部过期
+        let expires_at = Local::now().timestamp() + TOKEN_CONFIG.access_ttl_secs as i64;
+        TokenRevocation::revoke_user(id, expires_at).await?;
+        RefreshToken::revoke_all_for_user(id).await?;
+        Ok(())
+    }
+
+    /// 校验用户密码
+    /// ---
+    /// @parameter  id          u32
+    /// @parameter  password    &str
+    /// @return     Result<bool, Error>
+    /// ---
+    pub async fn check_password(id: u32, password: &str) -> Result<bool, Error> {
+        let user = match Self::find_by_id(id).await? {
+            Some(u) => u,
+            None => return Err(Error::E("用户不存在".to_string())),
+        };
+        let stored = user.password.unwrap_or_default();
+        Ok(password::verify_password(password, &stored).await != Verified::Invalid)
+    }
+
+    /// 是否已开启两步验证
+    pub fn two_factor_enabled(&self) -> bool {
+        self.totp_secret.as_deref().is_some_and(|s| !s.is_empty())
+    }
+
+    /// 保存等待确认的 TOTP 密钥
+    /// ---
+    /// @parameter  id          u32
+    /// @parameter  secret      &str
+    /// @return     Result<u64, Error>
+    /// ---
+    pub async fn set_pending_totp(id: u32, secret: &str) -> Result<u64, Error> {
+        let res = RB
+            .exec(
+                "UPDATE user SET totp_pending_secret = ? WHERE id = ?",
+                vec![bson!(secret), bson!(id)],
+            )
+            .await?;
+        Ok(res.rows_affected)
+    }
+
+    /// 确认并启用 TOTP 密钥
+    /// ---
+    /// @parameter  id          u32
+    /// @parameter  secret      &str
+    /// @parameter  step        i64         确认时使用的时间步
+    /// @return     Result<u64, Error>
+    /// ---
+    pub async fn enable_totp(id: u32, secret: &str, step: i64) -> Result<u64, Error> {
+        let res = RB
+            .exec(
+                "UPDATE user SET totp_secret = ?, totp_pending_secret = NULL, totp_last_step = ? WHERE id = ?",
+                vec![bson!(secret), bson!(step), bson!(id)],
+            )
+            .await?;
+        Ok(res.rows_affected)
+    }
+
+    /// 关闭两步验证并删除恢复码
+    /// ---
+    /// @parameter  id          u32
+    /// @return     Result<u64, Error>
+    /// ---
+    pub async fn disable_totp(id: u32) -> Result<u64, Error> {
+        let mut uow = UnitOfWork::begin(&RB).await?;
+
+        // 事务1: 清除密钥
+        let res = uow
+            .exec(
+                "clear_totp_secret",
+                "UPDATE user SET totp_secret = NULL, totp_pending_secret = NULL, totp_last_step = NULL WHERE id = ?;",
+                vec![bson!(id)],
+            )
+            .await?;
+
+        // 事务2: 删除恢复码
+        uow.exec(
+            "delete_recovery_codes",
+            "DELETE FROM recovery_code WHERE user_id = ?;",
+            vec![bson!(id)],
+        )
+        .await?;
+
+        // 提交事务
+        uow.commit().await?;
+        Ok(res.rows_affected)
+    }
+
+    /// 记录已使用的 TOTP 时间步，同一时间步的动态码只能使用一次
+    /// ---
+    /// @parameter  id          u32
+    /// @parameter  step        i64
+    /// @return     Result<bool, Error>     并发请求中只有一个返回 true
+    /// ---
+    pub async fn accept_totp_step(id: u32, step: i64) -> Result<bool, Error> {
+        let res = RB
+            .exec(
+                "UPDATE user SET totp_last_step = ? WHERE id = ? AND (totp_last_step IS NULL OR totp_last_step < ?)",
+                vec![bson!(step), bson!(id), bson!(step)],
+            )
+            .await?;
+        Ok(res.rows_affected == 1)
+    }
+
     /// 获取用户列表
     /// ---
     /// @return     Result<Vec<User>, Error>
@@ -62,11 +359,61 @@ impl User {
         RB.fetch_list::<UserVo>().await
     }
 
+    /// 管理员数量
+    /// ---
+    /// @return     Result<u64, Error>
+    /// ---
+    pub async fn count_admins() -> Result<u64, Error> {
+        let w = RB.new_wrapper().eq("role", "admin");
+        RB.fetch_count_by_wrapper::<User>(w).await
+    }
+
     /// 删除用户
     /// ---
+    /// @parameter  id              u32
+    /// @parameter  reassign_to     Option<u32>     该用户的文章转给此用户
     /// @return     Result<u64, Error>
     /// ---
-    pub async fn delete_user(id: &str) -> Result<u64, Error> {
-        RB.remove_by_column::<User, _>("id", id).await
+    pub async fn delete_user(id: u32, reassign_to: Option<u32>) -> Result<u64, Error> {
+        let mut uow = UnitOfWork::begin(&RB).await?;
+
+        // 事务1: 转移文章
+        if let Some(new_author) = reassign_to {
+            uow.exec(
+                "reassign_articles",
+                "UPDATE article SET author_id = ? WHERE author_id = ?;",
+                vec![bson!(new_author), bson!(id)],
+            )
+            .await?;
+        }
+
+        // 事务2: 删除刷新令牌
+        uow.exec(
+            "delete_refresh_tokens",
+            "DELETE FROM refresh_token WHERE user_id = ?;",
+            vec![bson!(id)],
+        )
+        .await?;
+
+        // 事务3: 删除恢复码
+        uow.exec(
+            "delete_recovery_codes",
+            "DELETE FROM recovery_code WHERE user_id = ?;",
+            vec![bson!(id)],
+        )
+        .await?;
+
+        // 事务4: 删除用户
+        let res = uow
+            .exec(
+                "delete_user",
+                "DELETE FROM user WHERE id = ?;",
+                vec![bson!(id)],
+            )
+            .await?;
+
+        // 提交事务
+        uow.commit().await?;
+        Ok(res.rows_affected)
     }
 }
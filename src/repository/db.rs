@@ -0,0 +1,321 @@
+// 数据库实现：委托给模型中基于全局 RB 的函数
+use rbatis::db::DBExecResult;
+use rbatis::Error;
+
+use super::{ArticleRepository, CategoryRepository, SlugRepository, TagRepository, UserRepository};
+use crate::model::article::{Article, ArticleEditVo, ArticleForUpdateVo, ArticleStatus, ArticleVo};
+use crate::model::article_revision::{ArticleRevision, ArticleRevisionVo};
+use crate::model::category::{Category, CategoryVo};
+use crate::model::login_event::LoginEvent;
+use crate::model::recovery_code::RecoveryCode;
+use crate::model::refresh_token::{RefreshToken, Rotation};
+use crate::model::slug_history::{SlugHistory, SlugKind};
+use crate::model::tag::{Tag, TagVo};
+use crate::model::token_revocation::TokenRevocation;
+use crate::model::user::{NewUser, User, UserProfile, UserVo};
+
+/// 基于 rbatis 的数据访问
+pub struct DbRepository;
+
+#[rocket::async_trait]
+impl ArticleRepository for DbRepository {
+    async fn count_published(&self) -> Result<u64, Error> {
+        Article::count_published().await
+    }
+
+    async fn find_published_page(
+        &self,
+        per_page: u64,
+        offset: u64,
+    ) -> Result<Vec<ArticleVo>, Error> {
+        Article::find_all_by_pagination_with_category(&per_page, &offset).await
+    }
+
+    async fn find_by_id(&self, id: u32) -> Result<Option<Article>, Error> {
+        Article::find_by_id(id).await
+    }
+
+    async fn find_by_slug(&self, slug: &str) -> Result<Option<Article>, Error> {
+        Article::find_by_slug(slug).await
+    }
+
+    async fn find_published_by_id(&self, id: u32) -> Result<Option<Article>, Error> {
+        Article::find_published_by_id(id).await
+    }
+
+    async fn find_by_id_with_category(&self, id: u32) -> Result<Option<ArticleVo>, Error> {
+        Article::find_by_id_with_category(&id).await
+    }
+
+    async fn find_editing_by_id(&self, id: u32) -> Result<Option<ArticleEditVo>, Error> {
+        Article::find_editing_by_id(id).await
+    }
+
+    async fn hot_list(&self) -> Result<Vec<Article>, Error> {
+        Article::hot_list().await
+    }
+
+    async fn search(&self, keyword: &str) -> Result<Vec<Article>, Error> {
+        Article::search(keyword).await
+    }
+
+    async fn admin_search(&self, title: String, category: u32) -> Result<Vec<Article>, Error> {
+        Article::admin_search(title, category).await
+    }
+
+    async fn count_by_author(&self, author_id: u32) -> Result<u64, Error> {
+        Article::count_by_author(author_id).await
+    }
+
+    async fn add(&self, article: Article, tag_ids: Vec<u32>) -> Result<(), Error> {
+        Article::add_article(article, tag_ids).await
+    }
+
+    async fn update(&self, id: u32, article: ArticleForUpdateVo) -> Result<(), Error> {
+        Article::update(id, article).await
+    }
+
+    async fn remove(&self, id: u32) -> Result<(), Error> {
+        Article::remove(id).await
+    }
+
+    async fn revisions(&self, article_id: u32) -> Result<Vec<ArticleRevisionVo>, Error> {
+        ArticleRevision::list(article_id).await
+    }
+
+    async fn find_revision(
+        &self,
+        article_id: u32,
+        revision: u32,
+    ) -> Result<Option<ArticleRevision>, Error> {
+        ArticleRevision::find(article_id, revision).await
+    }
+
+    async fn restore_revision(
+        &self,
+        article_id: u32,
+        revision: ArticleRevision,
+        editor_id: u32,
+        status: ArticleStatus,
+        published_at: Option<i64>,
+    ) -> Result<(), Error> {
+        Article::restore_revision(article_id, revision, editor_id, status, published_at).await
+    }
+
+    async fn publish_due(&self, now: i64) -> Result<u64, Error> {
+        Article::publish_due(now).await
+    }
+}
+
+#[rocket::async_trait]
+impl CategoryRepository for DbRepository {
+    async fn find_all_with_blogcount(&self) -> Result<Vec<CategoryVo>, Error> {
+        Category::find_all_with_blogcount().await
+    }
+
+    async fn find_by_id(&self, id: u32) -> Result<Option<Category>, Error> {
+        Category::find_by_id(id).await
+    }
+
+    async fn find_by_name(&self, name: &str) -> Result<Option<Category>, Error> {
+        Category::find_by_name(name).await
+    }
+
+    async fn find_by_slug(&self, slug: &str) -> Result<Option<Category>, Error> {
+        Category::find_by_slug(slug).await
+    }
+
+    async fn find_articles(&self, cate_id: u32) -> Result<Vec<Article>, Error> {
+        Category::find_articles_by_cateid(&cate_id).await
+    }
+
+    async fn create(&self, name: &str, slug: &str) -> Result<DBExecResult, Error> {
+        Category::create(name, slug).await
+    }
+
+    async fn update(&self, id: u32, name: &str, slug: &str) -> Result<u64, Error> {
+        Category::update(id, name, slug).await
+    }
+
+    async fn remove(&self, id: u32) -> Result<u64, Error> {
+        Category::remove(id).await
+    }
+}
+
+#[rocket::async_trait]
+impl TagRepository for DbRepository {
+    async fn find_all_with_blogcount(&self) -> Result<Vec<TagVo>, Error> {
+        Tag::find_all_with_blogcount().await
+    }
+
+    async fn find_by_id(&self, id: u32) -> Result<Option<Tag>, Error> {
+        Tag::find_by_id(id).await
+    }
+
+    async fn find_by_name(&self, name: &str) -> Result<Option<Tag>, Error> {
+        Tag::find_by_name(name).await
+    }
+
+    async fn find_by_slug(&self, slug: &str) -> Result<Option<Tag>, Error> {
+        Tag::find_by_slug(slug).await
+    }
+
+    async fn find_by_ids(&self, ids: &[u32]) -> Result<Vec<Tag>, Error> {
+        Tag::find_by_ids(ids).await
+    }
+
+    async fn find_articles(&self, tag_id: u32) -> Result<Vec<Article>, Error> {
+        Tag::find_articles_by_tagid(&tag_id).await
+    }
+
+    async fn create(&self, name: &str, slug: &str) -> Result<DBExecResult, Error> {
+        Tag::create(name, slug).await
+    }
+
+    async fn update(&self, id: u32, name: &str, slug: &str) -> Result<u64, Error> {
+        Tag::update(id, name, slug).await
+    }
+
+    async fn remove(&self, id: u32) -> Result<u64, Error> {
+        Tag::remove(id).await
+    }
+}
+
+#[rocket::async_trait]
+impl SlugRepository for DbRepository {
+    async fn find_target(&self, kind: SlugKind, slug: &str) -> Result<Option<u32>, Error> {
+        SlugHistory::find_target(kind, slug).await
+    }
+}
+
+#[rocket::async_trait]
+impl UserRepository for DbRepository {
+    async fn find_by_id(&self, id: u32) -> Result<Option<User>, Error> {
+        User::find_by_id(id).await
+    }
+
+    async fn username_exists(&self, username: &str) -> Result<bool, Error> {
+        User::username_exists(username).await
+    }
+
+    async fn create(&self, new_user: NewUser) -> Result<Option<i64>, Error> {
+        User::create(new_user).await
+    }
+
+    async fn update_profile(
+        &self,
+        id: u32,
+        profile: UserProfile,
+        role: Option<String>,
+    ) -> Result<u64, Error> {
+        User::update_profile(id, profile, role).await
+    }
+
+    async fn update_password(&self, id: u32, password: &str) -> Result<u64, Error> {
+        User::update_password(id, password).await
+    }
+
+    async fn login(&self, username: &str, password: &str) -> Result<Option<User>, Error> {
+        User::login(username, password).await
+    }
+
+    async fn verify_password(&self, id: u32, password: &str) -> Result<bool, Error> {
+        User::check_password(id, password).await
+    }
+
+    async fn change_password(
+        &self,
+        id: u32,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<bool, Error> {
+        User::change_password(id, old_password, new_password).await
+    }
+
+    async fn list(&self) -> Result<Vec<UserVo>, Error> {
+        User::get_user_list().await
+    }
+
+    async fn count_admins(&self) -> Result<u64, Error> {
+        User::count_admins().await
+    }
+
+    async fn delete(&self, id: u32, reassign_to: Option<u32>) -> Result<u64, Error> {
+        User::delete_user(id, reassign_to).await
+    }
+
+    async fn revoke_sessions(&self, id: u32) -> Result<(), Error> {
+        User::revoke_sessions(id).await
+    }
+
+    async fn is_token_revoked(
+        &self,
+        jti: &str,
+        user_id: u32,
+        issued_at: i64,
+    ) -> Result<bool, Error> {
+        TokenRevocation::is_revoked(jti, user_id, issued_at).await
+    }
+
+    async fn revoke_token(&self, jti: &str, user_id: u32, expires_at: i64) -> Result<(), Error> {
+        TokenRevocation::revoke_token(jti, user_id, expires_at).await
+    }
+
+    async fn issue_refresh_token(
+        &self,
+        user_id: u32,
+        family_id: Option<&str>,
+        ttl_secs: i64,
+    ) -> Result<String, Error> {
+        RefreshToken::issue(user_id, family_id, ttl_secs).await
+    }
+
+    async fn rotate_refresh_token(&self, token: &str, ttl_secs: i64) -> Result<Rotation, Error> {
+        RefreshToken::rotate(token, ttl_secs).await
+    }
+
+    async fn revoke_refresh_token(&self, token: &str, user_id: u32) -> Result<(), Error> {
+        RefreshToken::revoke(token, user_id).await
+    }
+
+    async fn set_pending_totp(&self, id: u32, secret: &str) -> Result<u64, Error> {
+        User::set_pending_totp(id, secret).await
+    }
+
+    async fn enable_totp(&self, id: u32, secret: &str, step: i64) -> Result<u64, Error> {
+        User::enable_totp(id, secret, step).await
+    }
+
+    async fn disable_totp(&self, id: u32) -> Result<u64, Error> {
+        User::disable_totp(id).await
+    }
+
+    async fn accept_totp_step(&self, id: u32, step: i64) -> Result<bool, Error> {
+        User::accept_totp_step(id, step).await
+    }
+
+    async fn replace_recovery_codes(&self, id: u32, codes: &[String]) -> Result<(), Error> {
+        RecoveryCode::replace(id, codes).await
+    }
+
+    async fn consume_recovery_code(&self, id: u32, code: &str) -> Result<bool, Error> {
+        RecoveryCode::consume(id, code).await
+    }
+
+    async fn remaining_recovery_codes(&self, id: u32) -> Result<u64, Error> {
+        RecoveryCode::remaining(id).await
+    }
+
+    async fn record_login_event(
+        &self,
+        username: &str,
+        ip: Option<String>,
+        event: &str,
+    ) -> Result<(), Error> {
+        LoginEvent::record(username, ip, event).await
+    }
+
+    async fn recent_login_events(&self, limit: u64) -> Result<Vec<LoginEvent>, Error> {
+        LoginEvent::recent(limit).await
+    }
+}
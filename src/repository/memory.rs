@@ -0,0 +1,1047 @@
+// 内存实现：不连接数据库，用于测试控制器及业务规则
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard};
+
+use chrono::Local;
+use rbatis::db::DBExecResult;
+use rbatis::Error;
+
+use super::{ArticleRepository, CategoryRepository, SlugRepository, TagRepository, UserRepository};
+use crate::model::article::{Article, ArticleEditVo, ArticleForUpdateVo, ArticleStatus, ArticleVo};
+use crate::model::article_revision::{ArticleRevision, ArticleRevisionVo};
+use crate::model::category::{Category, CategoryVo};
+use crate::model::login_event::LoginEvent;
+use crate::model::recovery_code::RecoveryCode;
+use crate::model::refresh_token::{RefreshToken, Rotation};
+use crate::model::slug_history::SlugKind;
+use crate::model::tag::{Tag, TagVo};
+use crate::model::user::{NewUser, User, UserProfile, UserVo};
+use crate::util::crypto::{random_token, sha256_hex as digest};
+use crate::util::password::{self, Verified};
+use crate::util::totp::normalize_recovery_code;
+
+/// 内存中的全部数据，按 id 排序
+#[derive(Default)]
+struct Store {
+    next_id: u32,
+    articles: BTreeMap<u32, Article>,
+    /// 文章与标签关联 (article_id, tag_id)
+    article_tags: Vec<(u32, u32)>,
+    /// 文章的历史版本，按保存顺序
+    revisions: Vec<ArticleRevision>,
+    /// 旧 slug (类型, slug, 数据 id)
+    slug_history: Vec<(SlugKind, String, u32)>,
+    categories: BTreeMap<u32, Category>,
+    tags: BTreeMap<u32, Tag>,
+    users: BTreeMap<u32, User>,
+    /// 用户全部会话的吊销时间（毫秒）
+    revoked_users: HashMap<u32, i64>,
+    /// 已吊销的单个访问令牌 (jti)
+    revoked_tokens: HashSet<String>,
+    refresh_tokens: Vec<RefreshToken>,
+    recovery_codes: Vec<RecoveryCode>,
+    login_events: Vec<LoginEvent>,
+}
+
+impl Store {
+    /// 自增 id，各表共用一个序列
+    fn next_id(&mut self) -> u32 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn tag_ids_of(&self, article_id: u32) -> Vec<u32> {
+        let mut ids: Vec<u32> = self
+            .article_tags
+            .iter()
+            .filter(|(a, _)| *a == article_id)
+            .map(|(_, t)| *t)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// 修改 slug 时记录旧 slug，新 slug 从历史中移除
+    fn record_slug(&mut self, kind: SlugKind, id: u32, old: Option<String>, new: &str) {
+        if old.as_deref() == Some(new) {
+            return;
+        }
+        self.slug_history
+            .retain(|(k, s, _)| !(*k == kind && s == new));
+        if let Some(old) = old {
+            self.slug_history.push((kind, old, id));
+        }
+    }
+
+    /// 删除已删除数据的旧 slug
+    fn forget_slugs(&mut self, kind: SlugKind, id: u32) {
+        self.slug_history
+            .retain(|(k, _, t)| !(*k == kind && *t == id));
+    }
+
+    /// 文章及其分类名称
+    fn article_vo(&self, a: &Article) -> ArticleVo {
+        ArticleVo {
+            id: a.id,
+            title: a.title.clone(),
+            slug: a.slug.clone(),
+            description: a.description.clone(),
+            content: a.content.clone(),
+            content_html: a.content_html.clone(),
+            toc: a.toc.clone(),
+            word_count: a.word_count,
+            reading_minutes: a.reading_minutes,
+            excerpt: a.excerpt.clone(),
+            cate_id: a.cate_id,
+            cate_name: a
+                .cate_id
+                .and_then(|id| self.categories.get(&id))
+                .and_then(|c| c.name.clone()),
+            author_id: a.author_id,
+            istop: a.istop,
+            status: a.status.clone(),
+            published_at: a.published_at,
+            created_at: a.created_at,
+            updated_at: a.updated_at,
+        }
+    }
+
+    /// 吊销刷新令牌族
+    fn revoke_family(&mut self, family_id: &str) {
+        let now = Local::now().timestamp();
+        for record in &mut self.refresh_tokens {
+            if record.family_id.as_deref() == Some(family_id) && record.revoked_at.is_none() {
+                record.revoked_at = Some(now);
+            }
+        }
+    }
+
+    /// 签发刷新令牌
+    fn issue_refresh_token(
+        &mut self,
+        user_id: u32,
+        family_id: Option<&str>,
+        ttl_secs: i64,
+    ) -> String {
+        let now = Local::now().timestamp();
+        let token = random_token(32);
+        let id = self.next_id();
+        self.refresh_tokens.push(RefreshToken {
+            id: Some(id),
+            user_id: Some(user_id),
+            family_id: Some(
+                family_id
+                    .map(|f| f.to_string())
+                    .unwrap_or_else(|| random_token(16)),
+            ),
+            token_hash: Some(digest(&token)),
+            expires_at: Some(now + ttl_secs),
+            revoked_at: None,
+            created_at: Some(now),
+        });
+        token
+    }
+
+    /// 记录文章的新版本，版本号在文章内递增
+    fn record_revision(&mut self, article_id: u32, author_id: Option<u32>) {
+        let article = &self.articles[&article_id];
+        let revision = self
+            .revisions
+            .iter()
+            .filter(|r| r.article_id == Some(article_id))
+            .filter_map(|r| r.revision)
+            .max()
+            .unwrap_or(0)
+            + 1;
+        let snapshot = ArticleRevision {
+            id: None,
+            article_id: Some(article_id),
+            revision: Some(revision),
+            title: article.title.clone(),
+            description: article.description.clone(),
+            content: article.content.clone(),
+            author_id,
+            created_at: article.updated_at,
+        };
+        let id = self.next_id();
+        self.revisions.push(ArticleRevision {
+            id: Some(id),
+            ..snapshot
+        });
+    }
+}
+
+/// 内存数据访问，各接口共享同一个 Store
+#[derive(Default)]
+pub struct MemoryRepository {
+    store: Mutex<Store>,
+}
+
+impl MemoryRepository {
+    fn store(&self) -> MutexGuard<'_, Store> {
+        self.store.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// 新增一条记录的执行结果
+fn inserted(id: u32) -> DBExecResult {
+    DBExecResult {
+        rows_affected: 1,
+        last_insert_id: Some(id as i64),
+    }
+}
+
+/// 是否已发布（前台可见）
+fn is_published(article: &Article) -> bool {
+    article.status.as_deref() == Some(ArticleStatus::Published.as_str())
+}
+
+/// 与 SQL 中 `LIKE '%keyword%'` 相同的匹配
+fn title_contains(article: &Article, keyword: &str) -> bool {
+    article
+        .title
+        .as_deref()
+        .unwrap_or_default()
+        .contains(keyword)
+}
+
+#[rocket::async_trait]
+impl ArticleRepository for MemoryRepository {
+    async fn count_published(&self) -> Result<u64, Error> {
+        Ok(self
+            .store()
+            .articles
+            .values()
+            .filter(|a| is_published(a))
+            .count() as u64)
+    }
+
+    async fn find_published_page(
+        &self,
+        per_page: u64,
+        offset: u64,
+    ) -> Result<Vec<ArticleVo>, Error> {
+        let store = self.store();
+        Ok(store
+            .articles
+            .values()
+            .filter(|a| is_published(a))
+            .filter(|a| {
+                store
+                    .categories
+                    .contains_key(&a.cate_id.unwrap_or_default())
+            })
+            .map(|a| store.article_vo(a))
+            .skip(offset as usize)
+            .take(per_page as usize)
+            .collect())
+    }
+
+    async fn find_by_id(&self, id: u32) -> Result<Option<Article>, Error> {
+        Ok(self.store().articles.get(&id).cloned())
+    }
+
+    async fn find_by_slug(&self, slug: &str) -> Result<Option<Article>, Error> {
+        Ok(self
+            .store()
+            .articles
+            .values()
+            .find(|a| a.slug.as_deref() == Some(slug))
+            .cloned())
+    }
+
+    async fn find_published_by_id(&self, id: u32) -> Result<Option<Article>, Error> {
+        Ok(self
+            .store()
+            .articles
+            .get(&id)
+            .filter(|a| is_published(a))
+            .cloned())
+    }
+
+    async fn find_by_id_with_category(&self, id: u32) -> Result<Option<ArticleVo>, Error> {
+        let store = self.store();
+        Ok(store.articles.get(&id).map(|a| store.article_vo(a)))
+    }
+
+    async fn find_editing_by_id(&self, id: u32) -> Result<Option<ArticleEditVo>, Error> {
+        let store = self.store();
+        Ok(store.articles.get(&id).map(|a| {
+            let tags = store.tag_ids_of(id);
+            ArticleEditVo {
+                id: a.id,
+                title: a.title.clone(),
+                slug: a.slug.clone(),
+                description: a.description.clone(),
+                content: a.content.clone(),
+                cate_id: a.cate_id,
+                cate_name: None,
+                is_top: None,
+                tags: (!tags.is_empty()).then(|| {
+                    tags.iter()
+                        .map(|t| t.to_string())
+                        .collect::<Vec<_>>()
+                        .join(",")
+                }),
+                status: a.status.clone(),
+                published_at: a.published_at,
+                created_at: None,
+                updated_at: None,
+            }
+        }))
+    }
+
+    async fn hot_list(&self) -> Result<Vec<Article>, Error> {
+        let mut articles: Vec<Article> = self
+            .store()
+            .articles
+            .values()
+            .filter(|a| is_published(a))
+            .cloned()
+            .collect();
+        articles.sort_by_key(|a| std::cmp::Reverse(a.created_at));
+        articles.truncate(9);
+        Ok(articles)
+    }
+
+    async fn search(&self, keyword: &str) -> Result<Vec<Article>, Error> {
+        Ok(self
+            .store()
+            .articles
+            .values()
+            .filter(|a| is_published(a) && title_contains(a, keyword))
+            .cloned()
+            .collect())
+    }
+
+    async fn admin_search(&self, title: String, category: u32) -> Result<Vec<Article>, Error> {
+        Ok(self
+            .store()
+            .articles
+            .values()
+            .filter(|a| title_contains(a, &title))
+            .filter(|a| category == 0 || a.cate_id == Some(category))
+            .cloned()
+            .collect())
+    }
+
+    async fn count_by_author(&self, author_id: u32) -> Result<u64, Error> {
+        Ok(self
+            .store()
+            .articles
+            .values()
+            .filter(|a| a.author_id == Some(author_id))
+            .count() as u64)
+    }
+
+    async fn add(&self, article: Article, tag_ids: Vec<u32>) -> Result<(), Error> {
+        let mut store = self.store();
+        let id = store.next_id();
+        let mut article = Article {
+            id: Some(id),
+            ..article
+        };
+        article.render_content();
+        let author_id = article.author_id;
+        store.articles.insert(id, article);
+        store.record_revision(id, author_id);
+        store
+            .article_tags
+            .extend(tag_ids.into_iter().map(|t| (id, t)));
+        Ok(())
+    }
+
+    async fn update(&self, id: u32, article: ArticleForUpdateVo) -> Result<(), Error> {
+        let mut store = self.store();
+        let old = match store.articles.get_mut(&id) {
+            Some(a) => a,
+            None => return Err(Error::E("文章不存在".to_string())),
+        };
+        let old_slug = old.slug.replace(article.slug.clone());
+        old.title = Some(article.title);
+        old.description = Some(article.description);
+        old.content = Some(article.content);
+        old.render_content();
+        old.cate_id = Some(article.cate_id);
+        old.status = Some(article.status);
+        old.published_at = article.published_at;
+        old.updated_at = Some(Local::now().timestamp());
+        store.record_revision(id, Some(article.editor_id));
+        store.record_slug(SlugKind::Article, id, old_slug, &article.slug);
+        store.article_tags.retain(|(a, _)| *a != id);
+        store
+            .article_tags
+            .extend(article.tags.into_iter().map(|t| (id, t)));
+        Ok(())
+    }
+
+    async fn remove(&self, id: u32) -> Result<(), Error> {
+        let mut store = self.store();
+        if store.articles.remove(&id).is_none() {
+            return Err(Error::E("文章不存在".to_string()));
+        }
+        store.article_tags.retain(|(a, _)| *a != id);
+        store.revisions.retain(|r| r.article_id != Some(id));
+        store.forget_slugs(SlugKind::Article, id);
+        Ok(())
+    }
+
+    async fn revisions(&self, article_id: u32) -> Result<Vec<ArticleRevisionVo>, Error> {
+        Ok(self
+            .store()
+            .revisions
+            .iter()
+            .rev()
+            .filter(|r| r.article_id == Some(article_id))
+            .map(ArticleRevision::summary)
+            .collect())
+    }
+
+    async fn find_revision(
+        &self,
+        article_id: u32,
+        revision: u32,
+    ) -> Result<Option<ArticleRevision>, Error> {
+        Ok(self
+            .store()
+            .revisions
+            .iter()
+            .find(|r| r.article_id == Some(article_id) && r.revision == Some(revision))
+            .cloned())
+    }
+
+    async fn restore_revision(
+        &self,
+        article_id: u32,
+        revision: ArticleRevision,
+        editor_id: u32,
+        status: ArticleStatus,
+        published_at: Option<i64>,
+    ) -> Result<(), Error> {
+        let mut store = self.store();
+        let article = match store.articles.get_mut(&article_id) {
+            Some(a) => a,
+            None => return Err(Error::E("文章不存在".to_string())),
+        };
+        article.title = revision.title;
+        article.description = revision.description;
+        article.content = revision.content;
+        article.status = Some(status.as_str().to_string());
+        article.published_at = published_at;
+        article.render_content();
+        article.updated_at = Some(Local::now().timestamp());
+        store.record_revision(article_id, Some(editor_id));
+        Ok(())
+    }
+
+    async fn publish_due(&self, now: i64) -> Result<u64, Error> {
+        let mut published = 0;
+        for a in self.store().articles.values_mut() {
+            let scheduled = a.status.as_deref() == Some(ArticleStatus::Scheduled.as_str());
+            if scheduled && a.published_at.is_some_and(|t| t <= now) {
+                a.status = Some(ArticleStatus::Published.as_str().to_string());
+                published += 1;
+            }
+        }
+        Ok(published)
+    }
+}
+
+#[rocket::async_trait]
+impl CategoryRepository for MemoryRepository {
+    async fn find_all_with_blogcount(&self) -> Result<Vec<CategoryVo>, Error> {
+        let store = self.store();
+        Ok(store
+            .categories
+            .values()
+            .map(|c| CategoryVo {
+                id: c.id,
+                name: c.name.clone(),
+                slug: c.slug.clone(),
+                blog_count: Some(
+                    store
+                        .articles
+                        .values()
+                        .filter(|a| is_published(a) && a.cate_id == c.id)
+                        .count() as u32,
+                ),
+                created_at: c.created_at,
+                updated_at: c.updated_at,
+            })
+            .collect())
+    }
+
+    async fn find_by_id(&self, id: u32) -> Result<Option<Category>, Error> {
+        Ok(self.store().categories.get(&id).cloned())
+    }
+
+    async fn find_by_name(&self, name: &str) -> Result<Option<Category>, Error> {
+        Ok(self
+            .store()
+            .categories
+            .values()
+            .find(|c| c.name.as_deref() == Some(name))
+            .cloned())
+    }
+
+    async fn find_by_slug(&self, slug: &str) -> Result<Option<Category>, Error> {
+        Ok(self
+            .store()
+            .categories
+            .values()
+            .find(|c| c.slug.as_deref() == Some(slug))
+            .cloned())
+    }
+
+    async fn find_articles(&self, cate_id: u32) -> Result<Vec<Article>, Error> {
+        let store = self.store();
+        if !store.categories.contains_key(&cate_id) {
+            return Ok(vec![]);
+        }
+        Ok(store
+            .articles
+            .values()
+            .filter(|a| is_published(a) && a.cate_id == Some(cate_id))
+            .cloned()
+            .collect())
+    }
+
+    async fn create(&self, name: &str, slug: &str) -> Result<DBExecResult, Error> {
+        let mut store = self.store();
+        let id = store.next_id();
+        let now = Local::now().timestamp();
+        store.categories.insert(
+            id,
+            Category {
+                id: Some(id),
+                name: Some(name.to_string()),
+                slug: Some(slug.to_string()),
+                created_at: Some(now),
+                updated_at: Some(now),
+            },
+        );
+        Ok(inserted(id))
+    }
+
+    async fn update(&self, id: u32, name: &str, slug: &str) -> Result<u64, Error> {
+        let mut store = self.store();
+        let old_slug = match store.categories.get_mut(&id) {
+            Some(c) => {
+                c.name = Some(name.to_string());
+                c.updated_at = Some(Local::now().timestamp());
+                c.slug.replace(slug.to_string())
+            }
+            None => return Err(Error::E("该分类不存在".to_string())),
+        };
+        store.record_slug(SlugKind::Category, id, old_slug, slug);
+        Ok(1)
+    }
+
+    async fn remove(&self, id: u32) -> Result<u64, Error> {
+        let mut store = self.store();
+        match store.categories.remove(&id) {
+            Some(_) => {
+                store.forget_slugs(SlugKind::Category, id);
+                Ok(1)
+            }
+            None => Err(Error::E("该分类不存在".to_string())),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl TagRepository for MemoryRepository {
+    async fn find_all_with_blogcount(&self) -> Result<Vec<TagVo>, Error> {
+        let store = self.store();
+        Ok(store
+            .tags
+            .values()
+            .map(|t| TagVo {
+                id: t.id,
+                name: t.name.clone(),
+                slug: t.slug.clone(),
+                blog_count: Some(
+                    store
+                        .article_tags
+                        .iter()
+                        .filter(|(article_id, tag_id)| {
+                            Some(*tag_id) == t.id
+                                && store.articles.get(article_id).is_some_and(is_published)
+                        })
+                        .count() as u32,
+                ),
+                created_at: t.created_at,
+                updated_at: t.updated_at,
+            })
+            .collect())
+    }
+
+    async fn find_by_id(&self, id: u32) -> Result<Option<Tag>, Error> {
+        Ok(self.store().tags.get(&id).cloned())
+    }
+
+    async fn find_by_name(&self, name: &str) -> Result<Option<Tag>, Error> {
+        Ok(self
+            .store()
+            .tags
+            .values()
+            .find(|t| t.name.as_deref() == Some(name))
+            .cloned())
+    }
+
+    async fn find_by_slug(&self, slug: &str) -> Result<Option<Tag>, Error> {
+        Ok(self
+            .store()
+            .tags
+            .values()
+            .find(|t| t.slug.as_deref() == Some(slug))
+            .cloned())
+    }
+
+    async fn find_by_ids(&self, ids: &[u32]) -> Result<Vec<Tag>, Error> {
+        let store = self.store();
+        Ok(ids
+            .iter()
+            .filter_map(|id| store.tags.get(id).cloned())
+            .collect())
+    }
+
+    async fn find_articles(&self, tag_id: u32) -> Result<Vec<Article>, Error> {
+        let store = self.store();
+        Ok(store
+            .articles
+            .values()
+            .filter(|a| is_published(a))
+            .filter(|a| {
+                store
+                    .article_tags
+                    .contains(&(a.id.unwrap_or_default(), tag_id))
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn create(&self, name: &str, slug: &str) -> Result<DBExecResult, Error> {
+        let mut store = self.store();
+        let id = store.next_id();
+        let now = Local::now().timestamp();
+        store.tags.insert(
+            id,
+            Tag {
+                id: Some(id),
+                name: Some(name.to_string()),
+                slug: Some(slug.to_string()),
+                created_at: Some(now),
+                updated_at: Some(now),
+            },
+        );
+        Ok(inserted(id))
+    }
+
+    async fn update(&self, id: u32, name: &str, slug: &str) -> Result<u64, Error> {
+        let mut store = self.store();
+        let old_slug = match store.tags.get_mut(&id) {
+            Some(t) => {
+                t.name = Some(name.to_string());
+                t.updated_at = Some(Local::now().timestamp());
+                t.slug.replace(slug.to_string())
+            }
+            None => return Err(Error::E("该标签不存在".to_string())),
+        };
+        store.record_slug(SlugKind::Tag, id, old_slug, slug);
+        Ok(1)
+    }
+
+    async fn remove(&self, id: u32) -> Result<u64, Error> {
+        let mut store = self.store();
+        match store.tags.remove(&id) {
+            Some(_) => {
+                store.forget_slugs(SlugKind::Tag, id);
+                Ok(1)
+            }
+            None => Err(Error::E("该标签不存在".to_string())),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl SlugRepository for MemoryRepository {
+    async fn find_target(&self, kind: SlugKind, slug: &str) -> Result<Option<u32>, Error> {
+        Ok(self
+            .store()
+            .slug_history
+            .iter()
+            .find(|(k, s, _)| *k == kind && s == slug)
+            .map(|(_, _, id)| *id))
+    }
+}
+
+#[rocket::async_trait]
+impl UserRepository for MemoryRepository {
+    async fn find_by_id(&self, id: u32) -> Result<Option<User>, Error> {
+        Ok(self.store().users.get(&id).cloned())
+    }
+
+    async fn username_exists(&self, username: &str) -> Result<bool, Error> {
+        Ok(self
+            .store()
+            .users
+            .values()
+            .any(|u| u.username.as_deref() == Some(username)))
+    }
+
+    async fn create(&self, new_user: NewUser) -> Result<Option<i64>, Error> {
+        if self.username_exists(&new_user.username).await? {
+            return Err(Error::E("用户名已存在".to_string()));
+        }
+        let hash = password::hash_password(&new_user.password).await?;
+        let now = Local::now().timestamp();
+        let mut store = self.store();
+        let id = store.next_id();
+        let profile = new_user.profile;
+        store.users.insert(
+            id,
+            User {
+                id: Some(id),
+                username: Some(new_user.username),
+                password: Some(hash),
+                nickname: profile.nickname,
+                role: Some(new_user.role),
+                avatar: profile.avatar,
+                bio: profile.bio,
+                email: profile.email,
+                website: profile.website,
+                totp_secret: None,
+                totp_pending_secret: None,
+                totp_last_step: None,
+                created_at: Some(now),
+                updated_at: Some(now),
+            },
+        );
+        Ok(Some(id as i64))
+    }
+
+    async fn update_profile(
+        &self,
+        id: u32,
+        profile: UserProfile,
+        role: Option<String>,
+    ) -> Result<u64, Error> {
+        let mut store = self.store();
+        let user = match store.users.get_mut(&id) {
+            Some(u) => u,
+            None => return Err(Error::E("用户不存在".to_string())),
+        };
+        user.nickname = profile.nickname.or(user.nickname.take());
+        user.avatar = profile.avatar.or(user.avatar.take());
+        user.bio = profile.bio.or(user.bio.take());
+        user.email = profile.email.or(user.email.take());
+        user.website = profile.website.or(user.website.take());
+        user.role = role.or(user.role.take());
+        user.updated_at = Some(Local::now().timestamp());
+        Ok(1)
+    }
+
+    async fn update_password(&self, id: u32, password: &str) -> Result<u64, Error> {
+        let hash = password::hash_password(password).await?;
+        match self.store().users.get_mut(&id) {
+            Some(u) => {
+                u.password = Some(hash);
+                Ok(1)
+            }
+            None => Ok(0),
+        }
+    }
+
+    async fn login(&self, username: &str, password: &str) -> Result<Option<User>, Error> {
+        let user = self
+            .store()
+            .users
+            .values()
+            .find(|u| u.username.as_deref() == Some(username))
+            .cloned();
+        let user = match user {
+            Some(u) => u,
+            None => {
+                password::dummy_verify(password).await;
+                return Ok(None);
+            }
+        };
+        let stored = user.password.clone().unwrap_or_default();
+        match password::verify_password(password, &stored).await {
+            Verified::Valid => Ok(Some(user)),
+            Verified::NeedsRehash => {
+                self.update_password(user.id.unwrap_or_default(), password)
+                    .await?;
+                Ok(Some(user))
+            }
+            Verified::Invalid => Ok(None),
+        }
+    }
+
+    async fn verify_password(&self, id: u32, password: &str) -> Result<bool, Error> {
+        let stored = match self.store().users.get(&id) {
+            Some(u) => u.password.clone().unwrap_or_default(),
+            None => return Err(Error::E("用户不存在".to_string())),
+        };
+        Ok(password::verify_password(password, &stored).await != Verified::Invalid)
+    }
+
+    async fn change_password(
+        &self,
+        id: u32,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<bool, Error> {
+        if !self.verify_password(id, old_password).await? {
+            return Ok(false);
+        }
+        self.update_password(id, new_password).await?;
+        Ok(true)
+    }
+
+    async fn list(&self) -> Result<Vec<UserVo>, Error> {
+        Ok(self
+            .store()
+            .users
+            .values()
+            .map(|u| UserVo {
+                id: u.id,
+                username: u.username.clone(),
+                nickname: u.nickname.clone(),
+                role: u.role.clone(),
+                avatar: u.avatar.clone(),
+                bio: u.bio.clone(),
+                email: u.email.clone(),
+                website: u.website.clone(),
+                created_at: u.created_at,
+            })
+            .collect())
+    }
+
+    async fn count_admins(&self) -> Result<u64, Error> {
+        Ok(self
+            .store()
+            .users
+            .values()
+            .filter(|u| u.role.as_deref() == Some("admin"))
+            .count() as u64)
+    }
+
+    async fn delete(&self, id: u32, reassign_to: Option<u32>) -> Result<u64, Error> {
+        let mut store = self.store();
+        if let Some(new_author) = reassign_to {
+            for article in store.articles.values_mut() {
+                if article.author_id == Some(id) {
+                    article.author_id = Some(new_author);
+                }
+            }
+        }
+        Ok(store.users.remove(&id).map_or(0, |_| 1))
+    }
+
+    async fn revoke_sessions(&self, id: u32) -> Result<(), Error> {
+        let mut store = self.store();
+        let now = Local::now();
+        store.revoked_users.insert(id, now.timestamp_millis());
+        for record in &mut store.refresh_tokens {
+            if record.user_id == Some(id) && record.revoked_at.is_none() {
+                record.revoked_at = Some(now.timestamp());
+            }
+        }
+        Ok(())
+    }
+
+    async fn is_token_revoked(
+        &self,
+        jti: &str,
+        user_id: u32,
+        issued_at: i64,
+    ) -> Result<bool, Error> {
+        let store = self.store();
+        Ok(store.revoked_tokens.contains(jti)
+            || store
+                .revoked_users
+                .get(&user_id)
+                .is_some_and(|revoked_at| *revoked_at >= issued_at))
+    }
+
+    async fn revoke_token(&self, jti: &str, _user_id: u32, _expires_at: i64) -> Result<(), Error> {
+        self.store().revoked_tokens.insert(jti.to_string());
+        Ok(())
+    }
+
+    async fn issue_refresh_token(
+        &self,
+        user_id: u32,
+        family_id: Option<&str>,
+        ttl_secs: i64,
+    ) -> Result<String, Error> {
+        Ok(self
+            .store()
+            .issue_refresh_token(user_id, family_id, ttl_secs))
+    }
+
+    async fn rotate_refresh_token(&self, token: &str, ttl_secs: i64) -> Result<Rotation, Error> {
+        let now = Local::now().timestamp();
+        let hash = digest(token);
+        let mut store = self.store();
+        let record = match store
+            .refresh_tokens
+            .iter_mut()
+            .find(|r| r.token_hash.as_deref() == Some(hash.as_str()))
+        {
+            Some(r) => r,
+            None => return Ok(Rotation::Invalid),
+        };
+        let (user_id, family_id) = match (record.user_id, record.family_id.clone()) {
+            (Some(uid), Some(fid)) => (uid, fid),
+            _ => return Ok(Rotation::Invalid),
+        };
+        if record.revoked_at.is_some() {
+            store.revoke_family(&family_id);
+            return Ok(Rotation::Reused);
+        }
+        if record.expires_at.unwrap_or(0) < now {
+            return Ok(Rotation::Invalid);
+        }
+        record.revoked_at = Some(now);
+        let refresh_token = store.issue_refresh_token(user_id, Some(&family_id), ttl_secs);
+        Ok(Rotation::Rotated {
+            user_id,
+            refresh_token,
+        })
+    }
+
+    async fn revoke_refresh_token(&self, token: &str, user_id: u32) -> Result<(), Error> {
+        let hash = digest(token);
+        let mut store = self.store();
+        let family_id = store
+            .refresh_tokens
+            .iter()
+            .find(|r| r.token_hash.as_deref() == Some(hash.as_str()) && r.user_id == Some(user_id))
+            .and_then(|r| r.family_id.clone());
+        if let Some(family_id) = family_id {
+            store.revoke_family(&family_id);
+        }
+        Ok(())
+    }
+
+    async fn set_pending_totp(&self, id: u32, secret: &str) -> Result<u64, Error> {
+        Ok(match self.store().users.get_mut(&id) {
+            Some(u) => {
+                u.totp_pending_secret = Some(secret.to_string());
+                1
+            }
+            None => 0,
+        })
+    }
+
+    async fn enable_totp(&self, id: u32, secret: &str, step: i64) -> Result<u64, Error> {
+        Ok(match self.store().users.get_mut(&id) {
+            Some(u) => {
+                u.totp_secret = Some(secret.to_string());
+                u.totp_pending_secret = None;
+                u.totp_last_step = Some(step);
+                1
+            }
+            None => 0,
+        })
+    }
+
+    async fn disable_totp(&self, id: u32) -> Result<u64, Error> {
+        let mut store = self.store();
+        store.recovery_codes.retain(|c| c.user_id != Some(id));
+        Ok(match store.users.get_mut(&id) {
+            Some(u) => {
+                u.totp_secret = None;
+                u.totp_pending_secret = None;
+                u.totp_last_step = None;
+                1
+            }
+            None => 0,
+        })
+    }
+
+    async fn accept_totp_step(&self, id: u32, step: i64) -> Result<bool, Error> {
+        Ok(match self.store().users.get_mut(&id) {
+            Some(u) if u.totp_last_step.is_none_or(|last| last < step) => {
+                u.totp_last_step = Some(step);
+                true
+            }
+            _ => false,
+        })
+    }
+
+    async fn replace_recovery_codes(&self, id: u32, codes: &[String]) -> Result<(), Error> {
+        let now = Local::now().timestamp();
+        let mut store = self.store();
+        store.recovery_codes.retain(|c| c.user_id != Some(id));
+        for code in codes {
+            let code_id = store.next_id();
+            store.recovery_codes.push(RecoveryCode {
+                id: Some(code_id),
+                user_id: Some(id),
+                code_hash: Some(digest(&normalize_recovery_code(code))),
+                used_at: None,
+                created_at: Some(now),
+            });
+        }
+        Ok(())
+    }
+
+    async fn consume_recovery_code(&self, id: u32, code: &str) -> Result<bool, Error> {
+        let hash = digest(&normalize_recovery_code(code));
+        Ok(
+            match self.store().recovery_codes.iter_mut().find(|c| {
+                c.user_id == Some(id)
+                    && c.code_hash.as_deref() == Some(hash.as_str())
+                    && c.used_at.is_none()
+            }) {
+                Some(c) => {
+                    c.used_at = Some(Local::now().timestamp());
+                    true
+                }
+                None => false,
+            },
+        )
+    }
+
+    async fn remaining_recovery_codes(&self, id: u32) -> Result<u64, Error> {
+        Ok(self
+            .store()
+            .recovery_codes
+            .iter()
+            .filter(|c| c.user_id == Some(id) && c.used_at.is_none())
+            .count() as u64)
+    }
+
+    async fn record_login_event(
+        &self,
+        username: &str,
+        ip: Option<String>,
+        event: &str,
+    ) -> Result<(), Error> {
+        let mut store = self.store();
+        let id = store.next_id();
+        store.login_events.push(LoginEvent {
+            id: Some(id),
+            username: Some(username.chars().take(64).collect()),
+            ip,
+            event: Some(event.to_string()),
+            created_at: Some(Local::now().timestamp()),
+        });
+        Ok(())
+    }
+
+    async fn recent_login_events(&self, limit: u64) -> Result<Vec<LoginEvent>, Error> {
+        Ok(self
+            .store()
+            .login_events
+            .iter()
+            .rev()
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+}
@@ -0,0 +1,233 @@
+// 数据访问接口：控制器通过 Rocket 状态中的 Repositories 访问数据，
+// 运行时使用数据库实现，测试中使用内存实现
+use std::sync::Arc;
+
+use rbatis::db::DBExecResult;
+use rbatis::Error;
+
+use crate::model::article::{Article, ArticleEditVo, ArticleForUpdateVo, ArticleStatus, ArticleVo};
+use crate::model::article_revision::{ArticleRevision, ArticleRevisionVo};
+use crate::model::category::{Category, CategoryVo};
+use crate::model::login_event::LoginEvent;
+use crate::model::refresh_token::Rotation;
+use crate::model::slug_history::SlugKind;
+use crate::model::tag::{Tag, TagVo};
+use crate::model::user::{NewUser, User, UserProfile, UserVo};
+
+pub mod db;
+#[cfg(test)]
+pub mod memory;
+
+use db::DbRepository;
+
+/// 文章
+#[rocket::async_trait]
+pub trait ArticleRepository: Send + Sync {
+    /// 已发布文章总数
+    async fn count_published(&self) -> Result<u64, Error>;
+    /// 分页查询已发布的文章列表（带分类名称）
+    async fn find_published_page(
+        &self,
+        per_page: u64,
+        offset: u64,
+    ) -> Result<Vec<ArticleVo>, Error>;
+    /// 任意状态的文章
+    async fn find_by_id(&self, id: u32) -> Result<Option<Article>, Error>;
+    /// 当前 slug 为 slug 的文章（任意状态）
+    async fn find_by_slug(&self, slug: &str) -> Result<Option<Article>, Error>;
+    /// 已发布的文章
+    async fn find_published_by_id(&self, id: u32) -> Result<Option<Article>, Error>;
+    /// 文章详情（任意状态，带分类名称）
+    async fn find_by_id_with_category(&self, id: u32) -> Result<Option<ArticleVo>, Error>;
+    /// 正在编辑的文章（带逗号分隔的标签 id）
+    async fn find_editing_by_id(&self, id: u32) -> Result<Option<ArticleEditVo>, Error>;
+    /// 最热文章（已发布）
+    async fn hot_list(&self) -> Result<Vec<Article>, Error>;
+    /// 前台搜索：已发布且标题包含关键字
+    async fn search(&self, keyword: &str) -> Result<Vec<Article>, Error>;
+    /// 后台搜索：按标题及分类（0 表示全部分类）
+    async fn admin_search(&self, title: String, category: u32) -> Result<Vec<Article>, Error>;
+    /// 作者的文章数量
+    async fn count_by_author(&self, author_id: u32) -> Result<u64, Error>;
+    /// 新增文章及其标签，并记录第 1 个版本
+    async fn add(&self, article: Article, tag_ids: Vec<u32>) -> Result<(), Error>;
+    /// 更新文章及其标签，并记录新版本
+    async fn update(&self, id: u32, article: ArticleForUpdateVo) -> Result<(), Error>;
+    /// 删除文章及其标签关联、历史版本
+    async fn remove(&self, id: u32) -> Result<(), Error>;
+    /// 文章的全部版本（不含正文），最新的在前
+    async fn revisions(&self, article_id: u32) -> Result<Vec<ArticleRevisionVo>, Error>;
+    /// 文章的某个版本
+    async fn find_revision(
+        &self,
+        article_id: u32,
+        revision: u32,
+    ) -> Result<Option<ArticleRevision>, Error>;
+    /// 恢复历史版本的标题、描述及正文，并记录为新版本
+    async fn restore_revision(
+        &self,
+        article_id: u32,
+        revision: ArticleRevision,
+        editor_id: u32,
+        status: ArticleStatus,
+        published_at: Option<i64>,
+    ) -> Result<(), Error>;
+    /// 发布到达发布时间的定时文章，返回发布的数量
+    async fn publish_due(&self, now: i64) -> Result<u64, Error>;
+}
+
+/// 分类
+#[rocket::async_trait]
+pub trait CategoryRepository: Send + Sync {
+    /// 全部分类（带已发布文章数量）
+    async fn find_all_with_blogcount(&self) -> Result<Vec<CategoryVo>, Error>;
+    async fn find_by_id(&self, id: u32) -> Result<Option<Category>, Error>;
+    async fn find_by_name(&self, name: &str) -> Result<Option<Category>, Error>;
+    async fn find_by_slug(&self, slug: &str) -> Result<Option<Category>, Error>;
+    /// 分类下已发布的文章
+    async fn find_articles(&self, cate_id: u32) -> Result<Vec<Article>, Error>;
+    async fn create(&self, name: &str, slug: &str) -> Result<DBExecResult, Error>;
+    /// 更新名称及 slug，修改 slug 时记录旧 slug
+    async fn update(&self, id: u32, name: &str, slug: &str) -> Result<u64, Error>;
+    async fn remove(&self, id: u32) -> Result<u64, Error>;
+}
+
+/// 标签
+#[rocket::async_trait]
+pub trait TagRepository: Send + Sync {
+    /// 全部标签（带已发布文章数量）
+    async fn find_all_with_blogcount(&self) -> Result<Vec<TagVo>, Error>;
+    async fn find_by_id(&self, id: u32) -> Result<Option<Tag>, Error>;
+    async fn find_by_name(&self, name: &str) -> Result<Option<Tag>, Error>;
+    async fn find_by_slug(&self, slug: &str) -> Result<Option<Tag>, Error>;
+    /// 按 id 列表查询存在的标签
+    async fn find_by_ids(&self, ids: &[u32]) -> Result<Vec<Tag>, Error>;
+    /// 标签下已发布的文章
+    async fn find_articles(&self, tag_id: u32) -> Result<Vec<Article>, Error>;
+    async fn create(&self, name: &str, slug: &str) -> Result<DBExecResult, Error>;
+    /// 更新名称及 slug，修改 slug 时记录旧 slug
+    async fn update(&self, id: u32, name: &str, slug: &str) -> Result<u64, Error>;
+    async fn remove(&self, id: u32) -> Result<u64, Error>;
+}
+
+/// slug 历史
+#[rocket::async_trait]
+pub trait SlugRepository: Send + Sync {
+    /// 旧 slug 指向的数据 id
+    async fn find_target(&self, kind: SlugKind, slug: &str) -> Result<Option<u32>, Error>;
+}
+
+/// 用户及其会话
+#[rocket::async_trait]
+pub trait UserRepository: Send + Sync {
+    async fn find_by_id(&self, id: u32) -> Result<Option<User>, Error>;
+    async fn username_exists(&self, username: &str) -> Result<bool, Error>;
+    /// 新增用户，返回新用户 id
+    async fn create(&self, new_user: NewUser) -> Result<Option<i64>, Error>;
+    /// 更新资料，为 None 的字段保持不变
+    async fn update_profile(
+        &self,
+        id: u32,
+        profile: UserProfile,
+        role: Option<String>,
+    ) -> Result<u64, Error>;
+    /// 设置新密码，返回受影响行数（用户不存在时为 0）
+    async fn update_password(&self, id: u32, password: &str) -> Result<u64, Error>;
+    /// 校验用户名和密码，旧版哈希在校验通过后透明升级
+    async fn login(&self, username: &str, password: &str) -> Result<Option<User>, Error>;
+    /// 校验用户密码
+    async fn verify_password(&self, id: u32, password: &str) -> Result<bool, Error>;
+    /// 校验旧密码后设置新密码，旧密码错误时返回 false
+    async fn change_password(
+        &self,
+        id: u32,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<bool, Error>;
+    async fn list(&self) -> Result<Vec<UserVo>, Error>;
+    async fn count_admins(&self) -> Result<u64, Error>;
+    /// 删除用户，reassign_to 不为空时将其文章转给该用户
+    async fn delete(&self, id: u32, reassign_to: Option<u32>) -> Result<u64, Error>;
+    /// 吊销用户的全部会话
+    async fn revoke_sessions(&self, id: u32) -> Result<(), Error>;
+    /// 令牌是否已被吊销，issued_at 为签发时间（毫秒）
+    async fn is_token_revoked(
+        &self,
+        jti: &str,
+        user_id: u32,
+        issued_at: i64,
+    ) -> Result<bool, Error>;
+    /// 吊销单个访问令牌
+    async fn revoke_token(&self, jti: &str, user_id: u32, expires_at: i64) -> Result<(), Error>;
+    /// 签发刷新令牌，family_id 为空时开启新的令牌族
+    async fn issue_refresh_token(
+        &self,
+        user_id: u32,
+        family_id: Option<&str>,
+        ttl_secs: i64,
+    ) -> Result<String, Error>;
+    /// 轮换刷新令牌，令牌被重复使用时吊销整个令牌族
+    async fn rotate_refresh_token(&self, token: &str, ttl_secs: i64) -> Result<Rotation, Error>;
+    /// 吊销刷新令牌所在的令牌族（只能吊销自己的令牌）
+    async fn revoke_refresh_token(&self, token: &str, user_id: u32) -> Result<(), Error>;
+    /// 保存等待确认的 TOTP 密钥
+    async fn set_pending_totp(&self, id: u32, secret: &str) -> Result<u64, Error>;
+    /// 确认并启用 TOTP 密钥
+    async fn enable_totp(&self, id: u32, secret: &str, step: i64) -> Result<u64, Error>;
+    /// 关闭两步验证并删除恢复码
+    async fn disable_totp(&self, id: u32) -> Result<u64, Error>;
+    /// 记录已使用的 TOTP 时间步，并发请求中只有一个返回 true
+    async fn accept_totp_step(&self, id: u32, step: i64) -> Result<bool, Error>;
+    /// 替换用户的全部恢复码
+    async fn replace_recovery_codes(&self, id: u32, codes: &[String]) -> Result<(), Error>;
+    /// 使用恢复码，有效且未使用时返回 true
+    async fn consume_recovery_code(&self, id: u32, code: &str) -> Result<bool, Error>;
+    /// 剩余可用的恢复码数量
+    async fn remaining_recovery_codes(&self, id: u32) -> Result<u64, Error>;
+    /// 记录登录失败事件
+    async fn record_login_event(
+        &self,
+        username: &str,
+        ip: Option<String>,
+        event: &str,
+    ) -> Result<(), Error>;
+    /// 最近的登录失败事件
+    async fn recent_login_events(&self, limit: u64) -> Result<Vec<LoginEvent>, Error>;
+}
+
+/// 全部数据访问接口，作为 Rocket 状态管理
+#[derive(Clone)]
+pub struct Repositories {
+    pub articles: Arc<dyn ArticleRepository>,
+    pub categories: Arc<dyn CategoryRepository>,
+    pub tags: Arc<dyn TagRepository>,
+    pub slugs: Arc<dyn SlugRepository>,
+    pub users: Arc<dyn UserRepository>,
+}
+
+impl Repositories {
+    /// 数据库实现（使用全局 RB）
+    pub fn db() -> Repositories {
+        let repo = Arc::new(DbRepository);
+        Repositories {
+            articles: repo.clone(),
+            categories: repo.clone(),
+            tags: repo.clone(),
+            slugs: repo.clone(),
+            users: repo,
+        }
+    }
+
+    /// 内存实现，各接口共享同一份数据
+    #[cfg(test)]
+    pub fn memory() -> Repositories {
+        let repo = Arc::new(memory::MemoryRepository::default());
+        Repositories {
+            articles: repo.clone(),
+            categories: repo.clone(),
+            tags: repo.clone(),
+            slugs: repo.clone(),
+            users: repo,
+        }
+    }
+}
@@ -0,0 +1,201 @@
+// 接口错误：按错误类型返回对应的 HTTP 状态码及统一的错误响应体
+use std::borrow::Cow;
+use std::fmt;
+
+use rocket::http::{Header, Status};
+use rocket::request::Request;
+use rocket::response::{self, Responder, Response};
+use rocket::serde::json::{serde_json::json, Value};
+use rocket::serde::Serialize;
+
+/// 字段校验错误
+#[derive(Serialize, Clone, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct FieldError {
+    /// 字段名（与请求体中的字段名一致）
+    pub field: &'static str,
+    /// 错误码，如 `required`、`too_long`
+    pub error: Cow<'static, str>,
+    pub msg: String,
+}
+
+impl FieldError {
+    pub fn new(field: &'static str, error: &'static str, msg: impl Into<String>) -> FieldError {
+        FieldError {
+            field,
+            error: Cow::Borrowed(error),
+            msg: msg.into(),
+        }
+    }
+}
+
+/// 错误响应体
+/// ---
+/// `{ "code": 404, "error": "article_not_found", "msg": "Article not found.", "details": ... }`
+/// ---
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+struct ErrorBody<'a> {
+    /// HTTP 状态码
+    code: u16,
+    /// 机器可读的错误码
+    error: &'a str,
+    msg: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<Value>,
+}
+
+/// 接口错误
+#[derive(Debug)]
+pub enum AppError {
+    /// 400：请求不合法（与单个字段无关）
+    BadRequest {
+        error: &'static str,
+        msg: &'static str,
+    },
+    /// 401：未登录或凭据无效
+    Unauthorized {
+        error: &'static str,
+        msg: &'static str,
+    },
+    /// 403：没有权限
+    Forbidden {
+        error: &'static str,
+        msg: &'static str,
+    },
+    /// 404：资源不存在
+    NotFound {
+        error: &'static str,
+        msg: &'static str,
+    },
+    /// 409：与现有数据冲突
+    Conflict {
+        error: &'static str,
+        msg: &'static str,
+        details: Option<Value>,
+    },
+    /// 422：字段校验失败
+    Validation(Vec<FieldError>),
+    /// 429：请求过于频繁，带 Retry-After 响应头
+    TooManyRequests { retry_after: i64 },
+    /// 500：内部错误，详细信息只写入日志
+    Internal(String),
+}
+
+impl AppError {
+    pub fn bad_request(error: &'static str, msg: &'static str) -> AppError {
+        AppError::BadRequest { error, msg }
+    }
+
+    pub fn unauthorized(error: &'static str, msg: &'static str) -> AppError {
+        AppError::Unauthorized { error, msg }
+    }
+
+    /// 没有权限（通用错误码 `forbidden`）
+    pub fn forbidden() -> AppError {
+        AppError::Forbidden {
+            error: "forbidden",
+            msg: "Forbidden.",
+        }
+    }
+
+    pub fn not_found(error: &'static str, msg: &'static str) -> AppError {
+        AppError::NotFound { error, msg }
+    }
+
+    pub fn conflict(error: &'static str, msg: &'static str) -> AppError {
+        AppError::Conflict {
+            error,
+            msg,
+            details: None,
+        }
+    }
+
+    /// 单个字段校验失败
+    pub fn invalid_field(
+        field: &'static str,
+        error: &'static str,
+        msg: impl Into<String>,
+    ) -> AppError {
+        AppError::Validation(vec![FieldError::new(field, error, msg)])
+    }
+
+    /// 对应的 HTTP 状态码
+    pub fn status(&self) -> Status {
+        match self {
+            AppError::BadRequest { .. } => Status::BadRequest,
+            AppError::Unauthorized { .. } => Status::Unauthorized,
+            AppError::Forbidden { .. } => Status::Forbidden,
+            AppError::NotFound { .. } => Status::NotFound,
+            AppError::Conflict { .. } => Status::Conflict,
+            AppError::Validation(_) => Status::UnprocessableEntity,
+            AppError::TooManyRequests { .. } => Status::TooManyRequests,
+            AppError::Internal(_) => Status::InternalServerError,
+        }
+    }
+
+    /// 错误响应体
+    pub fn body(&self) -> Value {
+        let (error, msg, details) = match self {
+            AppError::BadRequest { error, msg }
+            | AppError::Unauthorized { error, msg }
+            | AppError::Forbidden { error, msg }
+            | AppError::NotFound { error, msg } => (*error, *msg, None),
+            AppError::Conflict {
+                error,
+                msg,
+                details,
+            } => (*error, *msg, details.clone()),
+            AppError::Validation(fields) => (
+                "validation_failed",
+                "Validation failed.",
+                Some(json!(fields)),
+            ),
+            AppError::TooManyRequests { retry_after } => (
+                "too_many_requests",
+                "Too many login attempts, please retry later.",
+                Some(json!({ "retry_after": retry_after })),
+            ),
+            AppError::Internal(_) => ("internal_error", "Internal server error.", None),
+        };
+        json!(ErrorBody {
+            code: self.status().code,
+            error,
+            msg,
+            details,
+        })
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Internal(e) => write!(f, "internal error: {}", e),
+            e => write!(f, "{}", e.body()),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+/// 数据库错误统一作为内部错误
+impl From<rbatis::Error> for AppError {
+    fn from(e: rbatis::Error) -> Self {
+        AppError::Internal(e.to_string())
+    }
+}
+
+impl<'r> Responder<'r, 'static> for AppError {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        if let AppError::Internal(e) = &self {
+            log::error!("{} {}: {}", req.method(), req.uri(), e);
+        }
+        let mut res = Response::build_from(self.body().respond_to(req)?)
+            .status(self.status())
+            .finalize();
+        if let AppError::TooManyRequests { retry_after } = self {
+            res.set_header(Header::new("Retry-After", retry_after.to_string()));
+        }
+        Ok(res)
+    }
+}
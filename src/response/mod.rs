@@ -1 +1,2 @@
+pub mod error;
 pub mod resp_obj;
@@ -1,3 +1,7 @@
+use rocket::http::Header;
+use rocket::response::Redirect;
+use rocket::serde::json::Value;
+use rocket::Responder;
 use serde::Serialize;
 
 /// 封装响应数据结结构体
@@ -18,3 +22,27 @@ pub struct RespWithPagination<'a, T> {
     pub page_size: u64,
     pub total: u64,
 }
+
+/// 按 slug 查询的响应：找到时返回数据，旧 slug 永久重定向（301）到当前 slug
+#[derive(Responder)]
+pub enum SlugResp {
+    Found(Value),
+    Moved(Redirect),
+}
+
+/// 样式表响应（浏览器可缓存一天）
+#[derive(Responder)]
+#[response(content_type = "css")]
+pub struct StyleSheet {
+    pub css: &'static str,
+    pub cache_control: Header<'static>,
+}
+
+impl StyleSheet {
+    pub fn new(css: &'static str) -> StyleSheet {
+        StyleSheet {
+            css,
+            cache_control: Header::new("Cache-Control", "public, max-age=86400"),
+        }
+    }
+}
@@ -0,0 +1,407 @@
+use rocket::http::{ContentType, Header, Status};
+use rocket::serde::json::serde_json::json;
+use rocket::serde::json::Value;
+
+use super::{json, TestApp};
+use crate::util::permission::Role;
+
+/// 发布一篇文章，返回其 id
+async fn publish(app: &TestApp, auth: Header<'static>, title: &str, tags: &str) -> u64 {
+    let cate_id = match app
+        .repos
+        .categories
+        .find_all_with_blogcount()
+        .await
+        .unwrap()
+        .first()
+    {
+        Some(c) => c.id.unwrap(),
+        None => app.add_category("default").await,
+    };
+    let res = app
+        .client
+        .post("/api/article")
+        .header(ContentType::JSON)
+        .header(auth)
+        .body(format!(
+            r#"{{"title":"{}","description":"d","cate_id":{},"content":"c","tags":{}}}"#,
+            title, cate_id, tags
+        ))
+        .dispatch()
+        .await;
+    assert_eq!(json(res).await["code"], 200);
+    let found = app.repos.articles.search(title).await.unwrap();
+    found[0].id.unwrap() as u64
+}
+
+#[rocket::async_test]
+async fn list_is_paginated_with_category_name() {
+    let app = TestApp::new().await;
+    let (_, author) = app.login_as("author", Role::Author).await;
+    for i in 0..3 {
+        publish(&app, author.clone(), &format!("post-{}", i), "[]").await;
+    }
+
+    let res = app
+        .client
+        .get("/api/articles?page=2&pageSize=2")
+        .dispatch()
+        .await;
+    let body = json(res).await;
+    assert_eq!(body["total"], 3);
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+    assert_eq!(body["data"][0]["title"], "post-2");
+    assert_eq!(body["data"][0]["cate_name"], "default");
+}
+
+#[rocket::async_test]
+async fn detail_includes_category_and_metadata() {
+    let app = TestApp::new().await;
+    let (_, author) = app.login_as("author", Role::Author).await;
+    let id = publish(&app, author, "post", "[]").await;
+
+    for uri in [
+        format!("/api/article/{}", id),
+        "/api/article/slug/post".to_string(),
+    ] {
+        let body = json(app.client.get(uri).dispatch().await).await;
+        assert_eq!(body["data"]["cate_name"], "default");
+        assert_eq!(body["data"]["content"], "c");
+        assert_eq!(body["data"]["content_html"], json!(null));
+        assert_eq!(body["data"]["toc"], json!([]));
+        assert_eq!(body["data"]["word_count"], 1);
+        assert_eq!(body["data"]["reading_minutes"], 1);
+    }
+}
+
+#[rocket::async_test]
+async fn create_sets_author_and_tags() {
+    let app = TestApp::new().await;
+    let (user, author) = app.login_as("author", Role::Author).await;
+    let (rust, web) = (app.add_tag("rust").await, app.add_tag("web").await);
+    let id = publish(
+        &app,
+        author.clone(),
+        "hello",
+        &format!("[{},{}]", web, rust),
+    )
+    .await;
+
+    let res = app
+        .client
+        .get(format!("/api/article/{}", id))
+        .dispatch()
+        .await;
+    assert_eq!(json(res).await["data"]["author_id"], json!(user.id));
+
+    let res = app
+        .client
+        .get(format!("/api/article/edit/{}", id))
+        .header(author)
+        .dispatch()
+        .await;
+    assert_eq!(json(res).await["data"]["tags"], format!("{},{}", rust, web));
+}
+
+#[rocket::async_test]
+async fn contributor_cannot_delete_articles() {
+    let app = TestApp::new().await;
+    let (_, author) = app.login_as("author", Role::Author).await;
+    let (_, contributor) = app.login_as("contributor", Role::Contributor).await;
+    let id = publish(&app, author, "hello", "[]").await;
+
+    let res = app
+        .client
+        .delete(format!("/api/article/{}", id))
+        .header(contributor)
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Forbidden);
+    assert!(app
+        .repos
+        .articles
+        .find_by_id(id as u32)
+        .await
+        .unwrap()
+        .is_some());
+}
+
+#[rocket::async_test]
+async fn authors_only_edit_their_own_articles() {
+    let app = TestApp::new().await;
+    let (_, alice) = app.login_as("alice", Role::Author).await;
+    let (_, bob) = app.login_as("bob", Role::Author).await;
+    let (_, editor) = app.login_as("editor", Role::Editor).await;
+    let id = publish(&app, alice.clone(), "alice's", "[]").await;
+    let cate_id = app
+        .repos
+        .articles
+        .find_by_id(id as u32)
+        .await
+        .unwrap()
+        .unwrap()
+        .cate_id
+        .unwrap();
+    let body = format!(
+        r#"{{"title":"edited","description":"d","cate_id":{},"content":"c","tags":[]}}"#,
+        cate_id
+    );
+
+    let res = app
+        .client
+        .put(format!("/api/article/{}", id))
+        .header(ContentType::JSON)
+        .header(bob.clone())
+        .body(&body)
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Forbidden);
+    let res = app
+        .client
+        .delete(format!("/api/article/{}", id))
+        .header(bob)
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Forbidden);
+
+    let res = app
+        .client
+        .put(format!("/api/article/{}", id))
+        .header(ContentType::JSON)
+        .header(editor)
+        .body(&body)
+        .dispatch()
+        .await;
+    assert_eq!(json(res).await["code"], 200);
+    let article = app
+        .repos
+        .articles
+        .find_by_id(id as u32)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(article.title.as_deref(), Some("edited"));
+
+    let res = app
+        .client
+        .delete(format!("/api/article/{}", id))
+        .header(alice)
+        .dispatch()
+        .await;
+    assert_eq!(json(res).await["code"], 200);
+    assert!(app
+        .repos
+        .articles
+        .find_by_id(id as u32)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[rocket::async_test]
+async fn search_matches_title() {
+    let app = TestApp::new().await;
+    let (_, author) = app.login_as("author", Role::Author).await;
+    publish(&app, author.clone(), "learning rust", "[]").await;
+    publish(&app, author.clone(), "cooking", "[]").await;
+
+    let res = app.client.get("/api/article/search/rust").dispatch().await;
+    let body = json(res).await;
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+    assert_eq!(body["data"][0]["title"], "learning rust");
+
+    let res = app
+        .client
+        .get("/api/article/search?title=&category=0")
+        .header(author)
+        .dispatch()
+        .await;
+    assert_eq!(json(res).await["data"].as_array().unwrap().len(), 2);
+}
+
+#[rocket::async_test]
+async fn admin_search_only_returns_editable_articles() {
+    let app = TestApp::new().await;
+    let (_, alice) = app.login_as("alice", Role::Author).await;
+    let (_, bob) = app.login_as("bob", Role::Contributor).await;
+    let (_, editor) = app.login_as("editor", Role::Editor).await;
+    publish(&app, alice.clone(), "alice's post", "[]").await;
+    let cate_id = app.add_category("drafts").await;
+    let res = app
+        .client
+        .post("/api/article")
+        .header(ContentType::JSON)
+        .header(bob.clone())
+        .body(
+            json!({ "title": "bob's draft", "content": "c", "cate_id": cate_id, "tags": [] })
+                .to_string(),
+        )
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Ok);
+
+    let titles = |body: Value| -> Vec<String> {
+        body["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|a| a["title"].as_str().unwrap().to_string())
+            .collect()
+    };
+    let search = |auth: Header<'static>| {
+        app.client
+            .get("/api/article/search?title=&category=0")
+            .header(auth)
+            .dispatch()
+    };
+    assert_eq!(titles(json(search(alice).await).await), ["alice's post"]);
+    assert_eq!(titles(json(search(bob).await).await), ["bob's draft"]);
+    assert_eq!(
+        json(search(editor).await).await["data"]
+            .as_array()
+            .unwrap()
+            .len(),
+        2
+    );
+
+    let res = app
+        .client
+        .get("/api/article/search?title=&category=0")
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Unauthorized);
+}
+
+#[rocket::async_test]
+async fn list_rejects_oversized_pages() {
+    let app = TestApp::new().await;
+
+    let res = app
+        .client
+        .get("/api/articles?page=1&pageSize=101")
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::UnprocessableEntity);
+    let body = json(res).await;
+    assert_eq!(body["details"][0]["field"], "pageSize");
+    assert_eq!(body["details"][0]["error"], "too_large");
+
+    let res = app
+        .client
+        .get(format!("/api/articles?page={}&pageSize=100", u64::MAX))
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::UnprocessableEntity);
+    assert_eq!(json(res).await["details"][0]["field"], "page");
+
+    let res = app
+        .client
+        .get("/api/articles?page=1&pageSize=100")
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Ok);
+}
+
+#[rocket::async_test]
+async fn errors_use_status_codes() {
+    let app = TestApp::new().await;
+    let (_, author) = app.login_as("author", Role::Author).await;
+
+    let res = app.client.get("/api/article/404").dispatch().await;
+    assert_eq!(res.status(), Status::NotFound);
+    assert_eq!(json(res).await["error"], "article_not_found");
+
+    let res = app
+        .client
+        .get("/api/articles?page=0&pageSize=10")
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::BadRequest);
+    assert_eq!(json(res).await["error"], "invalid_page");
+
+    let res = app
+        .client
+        .post("/api/article")
+        .header(ContentType::JSON)
+        .header(author.clone())
+        .body(r#"{"title":"t""#)
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::BadRequest);
+    assert_eq!(json(res).await["error"], "bad_request");
+
+    let res = app
+        .client
+        .post("/api/article")
+        .header(ContentType::JSON)
+        .header(author)
+        .body(r#"{"title":"t"}"#)
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::UnprocessableEntity);
+
+    let res = app.client.get("/api/nothing").dispatch().await;
+    assert_eq!(res.status(), Status::NotFound);
+    assert_eq!(json(res).await["error"], "route_not_found");
+}
+
+#[rocket::async_test]
+async fn create_reports_every_invalid_field() {
+    let app = TestApp::new().await;
+    let (_, author) = app.login_as("author", Role::Author).await;
+    let tag = app.add_tag("rust").await;
+
+    let res = app
+        .client
+        .post("/api/article")
+        .header(ContentType::JSON)
+        .header(author.clone())
+        .body(format!(
+            r#"{{"title":"  ","description":"{}","cate_id":404,"content":"c","tags":[{},404]}}"#,
+            "d".repeat(501),
+            tag
+        ))
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::UnprocessableEntity);
+    let details = json(res).await["details"].clone();
+    let errors: Vec<(&str, &str)> = details
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| (e["field"].as_str().unwrap(), e["error"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            ("cate_id", "not_found"),
+            ("description", "too_long"),
+            ("tags", "not_found"),
+            ("title", "required"),
+        ]
+    );
+    assert_eq!(details[2]["msg"], "Tags do not exist: 404.");
+
+    let res = app
+        .client
+        .post("/api/article")
+        .header(ContentType::JSON)
+        .header(author)
+        .body(format!(
+            r#"{{"title":"t","content":"c","tags":[{},{}]}}"#,
+            tag, tag
+        ))
+        .dispatch()
+        .await;
+    let body = json(res).await;
+    assert_eq!(body["details"][0]["field"], "cate_id");
+    assert_eq!(body["details"][0]["error"], "required");
+    assert_eq!(body["details"][1]["error"], "duplicate");
+    assert!(app
+        .repos
+        .articles
+        .admin_search(String::new(), 0)
+        .await
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,147 @@
+use rocket::http::{ContentType, Status};
+use rocket::serde::json::serde_json::json;
+
+use super::{json, TestApp};
+use crate::util::permission::Role;
+
+#[rocket::async_test]
+async fn create_requires_manage_taxonomy() {
+    let app = TestApp::new().await;
+    let (_, author) = app.login_as("author", Role::Author).await;
+
+    let res = app
+        .client
+        .post("/api/categories")
+        .header(ContentType::JSON)
+        .body(r#"{"name":"rust"}"#)
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Unauthorized);
+
+    let res = app
+        .client
+        .post("/api/categories")
+        .header(ContentType::JSON)
+        .header(author)
+        .body(r#"{"name":"rust"}"#)
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Forbidden);
+    assert!(app
+        .repos
+        .categories
+        .find_all_with_blogcount()
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[rocket::async_test]
+async fn create_update_and_delete() {
+    let app = TestApp::new().await;
+    let (_, editor) = app.login_as("editor", Role::Editor).await;
+
+    let res = app
+        .client
+        .post("/api/categories")
+        .header(ContentType::JSON)
+        .header(editor.clone())
+        .body(r#"{"name":"rust"}"#)
+        .dispatch()
+        .await;
+    assert_eq!(json(res).await["code"], 200);
+
+    let res = app.client.get("/api/categories").dispatch().await;
+    let body = json(res).await;
+    assert_eq!(body["data"][0]["name"], "rust");
+    assert_eq!(body["data"][0]["blog_count"], 0);
+    let id = body["data"][0]["id"].as_u64().unwrap();
+
+    let res = app
+        .client
+        .put(format!("/api/categories/{}", id))
+        .header(ContentType::JSON)
+        .header(editor.clone())
+        .body(r#"{"name":"golang"}"#)
+        .dispatch()
+        .await;
+    assert_eq!(json(res).await["code"], 200);
+    let res = app
+        .client
+        .get(format!("/api/category/{}", id))
+        .dispatch()
+        .await;
+    assert_eq!(json(res).await["data"]["name"], "golang");
+
+    let res = app
+        .client
+        .delete(format!("/api/categories/{}", id))
+        .header(editor)
+        .dispatch()
+        .await;
+    assert_eq!(json(res).await["code"], 200);
+    let res = app
+        .client
+        .get(format!("/api/category/{}", id))
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::NotFound);
+    assert_eq!(json(res).await["error"], "category_not_found");
+}
+
+#[rocket::async_test]
+async fn update_missing_category_is_not_found() {
+    let app = TestApp::new().await;
+    let (_, editor) = app.login_as("editor", Role::Editor).await;
+
+    let res = app
+        .client
+        .put("/api/categories/404")
+        .header(ContentType::JSON)
+        .header(editor)
+        .body(r#"{"name":"golang"}"#)
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::NotFound);
+    assert_eq!(
+        json(res).await,
+        json!({ "code": 404, "error": "category_not_found", "msg": "Category not found." })
+    );
+}
+
+#[rocket::async_test]
+async fn names_are_required_and_unique() {
+    let app = TestApp::new().await;
+    let (_, editor) = app.login_as("editor", Role::Editor).await;
+    let rust = app.add_category("rust").await;
+    let go = app.add_category("go").await;
+    let put = |id: u32, body: &'static str| {
+        app.client
+            .put(format!("/api/categories/{}", id))
+            .header(ContentType::JSON)
+            .header(editor.clone())
+            .body(body)
+    };
+
+    let res = put(go, r#"{"name":" rust "}"#).dispatch().await;
+    assert_eq!(res.status(), Status::UnprocessableEntity);
+    assert_eq!(json(res).await["details"][0]["error"], "taken");
+
+    let res = put(go, r#"{}"#).dispatch().await;
+    assert_eq!(json(res).await["details"][0]["error"], "required");
+
+    let res = put(rust, r#"{"name":"rust"}"#).dispatch().await;
+    assert_eq!(res.status(), Status::Ok);
+
+    let res = app
+        .client
+        .post("/api/categories")
+        .header(ContentType::JSON)
+        .header(editor.clone())
+        .body(format!(r#"{{"name":"{}"}}"#, "x".repeat(65)))
+        .dispatch()
+        .await;
+    let body = json(res).await;
+    assert_eq!(body["details"][0]["error"], "too_long");
+    assert_eq!(body["details"][0]["msg"], "Must be at most 64 characters.");
+}
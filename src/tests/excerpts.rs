@@ -0,0 +1,148 @@
+use rocket::http::{ContentType, Header, Status};
+use rocket::serde::json::serde_json::json;
+use rocket::serde::json::Value;
+
+use super::{json, TestApp};
+use crate::util::permission::Role;
+
+/// 提交已发布的文章，返回其 id
+async fn post_article(
+    app: &TestApp,
+    auth: Header<'static>,
+    title: &str,
+    description: &str,
+    content: &str,
+) -> u32 {
+    let cate_id = match app.repos.categories.find_by_name("default").await.unwrap() {
+        Some(category) => category.id.unwrap(),
+        None => app.add_category("default").await,
+    };
+    let body = json!({
+        "title": title,
+        "description": description,
+        "content": content,
+        "cate_id": cate_id,
+        "tags": [],
+    });
+    let res = app
+        .client
+        .post("/api/article")
+        .header(ContentType::JSON)
+        .header(auth)
+        .body(body.to_string())
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Ok);
+    app.repos.articles.search(title).await.unwrap()[0]
+        .id
+        .unwrap()
+}
+
+/// 文章的摘要
+async fn excerpt(app: &TestApp, id: u32) -> String {
+    app.repos
+        .articles
+        .find_by_id(id)
+        .await
+        .unwrap()
+        .unwrap()
+        .excerpt
+        .unwrap()
+}
+
+#[rocket::async_test]
+async fn excerpts_are_generated_when_description_is_empty() {
+    let app = TestApp::new().await;
+    let (_, author) = app.login_as("author", Role::Author).await;
+
+    // 填写了描述时使用描述
+    let id = post_article(&app, author.clone(), "described", "手写的描述", "# 正文").await;
+    assert_eq!(excerpt(&app, id).await, "手写的描述");
+
+    // 去除 Markdown 标记、代码块及图片
+    let content = "# Title\n\nSome **bold** and `code`.\n\n```rust\nfn main() {}\n```\n\n![alt](a.png) [link](b)\n";
+    let id = post_article(&app, author.clone(), "plain", "", content).await;
+    assert_eq!(excerpt(&app, id).await, "Title Some bold and code. link");
+
+    // 取 <!-- more --> 之前的全部内容
+    let content = format!("第一段。\n\n{}\n\n<!-- more -->\n\n后文", "长".repeat(300));
+    let id = post_article(&app, author.clone(), "more", "", &content).await;
+    assert_eq!(
+        excerpt(&app, id).await,
+        format!("第一段。{}", "长".repeat(300))
+    );
+
+    // 在句末截断
+    let content = format!("{}。{}", "句".repeat(150), "尾".repeat(100));
+    let id = post_article(&app, author.clone(), "sentence", "", &content).await;
+    assert_eq!(excerpt(&app, id).await, format!("{}。", "句".repeat(150)));
+
+    // 没有句末时在单词之间截断
+    let id = post_article(&app, author.clone(), "words", "", &"word ".repeat(60)).await;
+    let words = excerpt(&app, id).await;
+    assert_eq!(words, format!("{}…", "word ".repeat(40).trim_end()));
+
+    // 汉字之间均可截断
+    let id = post_article(&app, author, "cjk", "", &"字".repeat(300)).await;
+    assert_eq!(excerpt(&app, id).await, format!("{}…", "字".repeat(200)));
+}
+
+#[rocket::async_test]
+async fn lists_return_excerpt_instead_of_content() {
+    let app = TestApp::new().await;
+    let (_, author) = app.login_as("author", Role::Author).await;
+    let id = post_article(
+        &app,
+        author.clone(),
+        "listed",
+        "",
+        "Intro.\n\n<!-- more -->\n\nRest",
+    )
+    .await;
+    let cate_id = app
+        .repos
+        .articles
+        .find_by_id(id)
+        .await
+        .unwrap()
+        .unwrap()
+        .cate_id
+        .unwrap();
+
+    for uri in [
+        "/api/articles?page=1&pageSize=10".to_string(),
+        "/api/article/hot".to_string(),
+        "/api/article/search/listed".to_string(),
+        format!("/api/category/{}/artlist", cate_id),
+    ] {
+        let res = app.client.get(uri.clone()).dispatch().await;
+        let body: Value = json(res).await;
+        let article = &body["data"][0];
+        assert_eq!(article["excerpt"], "Intro.", "{}", uri);
+        assert!(article["content"].is_null(), "{}", uri);
+        assert!(article["content_html"].is_null(), "{}", uri);
+    }
+
+    // 详情仍返回正文
+    let res = app
+        .client
+        .get(format!("/api/article/{}", id))
+        .dispatch()
+        .await;
+    let body = json(res).await;
+    assert_eq!(body["data"]["content"], "Intro.\n\n<!-- more -->\n\nRest");
+    assert_eq!(body["data"]["excerpt"], "Intro.");
+
+    // 修改时填写了描述，摘要改为描述
+    let body = json!({ "title": "listed", "description": "Manual", "content": "New", "cate_id": cate_id, "tags": [] });
+    let res = app
+        .client
+        .put(format!("/api/article/{}", id))
+        .header(ContentType::JSON)
+        .header(author)
+        .body(body.to_string())
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Ok);
+    assert_eq!(excerpt(&app, id).await, "Manual");
+}
@@ -0,0 +1,265 @@
+use rocket::http::{ContentType, Header, Status};
+use rocket::serde::json::serde_json::json;
+
+use super::{json, TestApp};
+use crate::util::permission::Role;
+
+const SOURCE: &str = "## Intro\n\n| a | b |\n|---|:-:|\n| 1 | 2 |\n\n- [x] done\n\nText[^n] <script>alert(1)</script><a href=\"javascript:alert(1)\" onclick=\"x()\">link</a>\n\n## Intro\n\n[^n]: Note.\n";
+
+/// 提交文章，返回其 id
+async fn post_article(app: &TestApp, auth: Header<'static>, content: &str) -> u32 {
+    let cate_id = app.add_category("default").await;
+    let body = json!({ "title": "markdown", "content": content, "cate_id": cate_id, "tags": [] });
+    let res = app
+        .client
+        .post("/api/article")
+        .header(ContentType::JSON)
+        .header(auth)
+        .body(body.to_string())
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Ok);
+    app.repos.articles.search("markdown").await.unwrap()[0]
+        .id
+        .unwrap()
+}
+
+/// 每个 input 标签排序后的属性
+fn input_attributes(html: &str) -> Vec<Vec<&str>> {
+    html.split("<input ")
+        .skip(1)
+        .map(|rest| {
+            let mut attributes: Vec<&str> = rest[..rest.find('>').unwrap()].split(' ').collect();
+            attributes.sort_unstable();
+            attributes
+        })
+        .collect()
+}
+
+#[rocket::async_test]
+async fn content_is_rendered_and_sanitized_on_save() {
+    let app = TestApp::new().await;
+    let (_, author) = app.login_as("author", Role::Author).await;
+    let id = post_article(&app, author, SOURCE).await;
+
+    let html = app
+        .repos
+        .articles
+        .find_by_id(id)
+        .await
+        .unwrap()
+        .unwrap()
+        .content_html
+        .unwrap();
+    assert!(html.contains(r#"<h2 id="user-content-intro">Intro</h2>"#));
+    assert!(html.contains(r#"<h2 id="user-content-intro-2">Intro</h2>"#));
+    assert!(html.contains(r#"<th style="text-align:center">b</th>"#));
+    // 强制设置的属性顺序不固定
+    assert_eq!(
+        input_attributes(&html),
+        vec![vec!["checked=\"\"", "disabled=\"\"", "type=\"checkbox\""]]
+    );
+    assert!(html.contains(r##"<sup class="footnote-reference"><a href="#user-content-n""##));
+    assert!(html.contains(r#"<div class="footnote-definition" id="user-content-n">"#));
+    assert!(!html.contains("script"));
+    assert!(!html.contains("javascript"));
+    assert!(!html.contains("onclick"));
+}
+
+#[rocket::async_test]
+async fn authored_ids_are_prefixed() {
+    let app = TestApp::new().await;
+    let (_, author) = app.login_as("author", Role::Author).await;
+    let id = post_article(
+        &app,
+        author,
+        "<div id=\"login\">x</div>\n\n<h2 id=\"user-content-x\">y</h2>\n\n[top](#login) [site](https://example.com/#login)\n",
+    )
+    .await;
+
+    let html = app
+        .repos
+        .articles
+        .find_by_id(id)
+        .await
+        .unwrap()
+        .unwrap()
+        .content_html
+        .unwrap();
+    assert!(html.contains(r#"<div id="user-content-login">"#));
+    assert!(html.contains(r#"<h2 id="user-content-x">"#));
+    assert!(html.contains(r##"<a href="#user-content-login""##));
+    assert!(html.contains(r##"<a href="https://example.com/#login""##));
+    assert!(!html.contains(r#"id="login""#));
+}
+
+#[rocket::async_test]
+async fn author_inputs_are_read_only_checkboxes() {
+    let app = TestApp::new().await;
+    let (_, author) = app.login_as("author", Role::Author).await;
+    let id = post_article(
+        &app,
+        author,
+        "<input type=\"text\" name=\"q\" value=\"x\">\n",
+    )
+    .await;
+
+    let html = app
+        .repos
+        .articles
+        .find_by_id(id)
+        .await
+        .unwrap()
+        .unwrap()
+        .content_html
+        .unwrap();
+    assert_eq!(
+        input_attributes(&html),
+        vec![vec!["disabled=\"\"", "type=\"checkbox\""]]
+    );
+}
+
+#[rocket::async_test]
+async fn detail_returns_source_or_html() {
+    let app = TestApp::new().await;
+    let (_, author) = app.login_as("author", Role::Author).await;
+    let id = post_article(&app, author.clone(), "# Title").await;
+
+    let res = app
+        .client
+        .get(format!("/api/article/{}", id))
+        .dispatch()
+        .await;
+    let body = json(res).await;
+    assert_eq!(body["data"]["content"], "# Title");
+    assert!(body["data"]["content_html"].is_null());
+
+    let res = app
+        .client
+        .get(format!("/api/article/{}?format=html", id))
+        .dispatch()
+        .await;
+    let body = json(res).await;
+    assert!(body["data"]["content"].is_null());
+    assert_eq!(
+        body["data"]["content_html"],
+        "<h1 id=\"user-content-title\">Title</h1>\n"
+    );
+
+    let res = app
+        .client
+        .get("/api/article/slug/markdown?format=html")
+        .dispatch()
+        .await;
+    assert_eq!(
+        json(res).await["data"]["content_html"],
+        "<h1 id=\"user-content-title\">Title</h1>\n"
+    );
+
+    let res = app
+        .client
+        .get(format!("/api/article/{}?format=pdf", id))
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::BadRequest);
+    assert_eq!(json(res).await["error"], "invalid_format");
+
+    // 修改正文后重新渲染
+    let article = app.repos.articles.find_by_id(id).await.unwrap().unwrap();
+    let body =
+        json!({ "title": "markdown", "content": "*new*", "cate_id": article.cate_id, "tags": [] });
+    let res = app
+        .client
+        .put(format!("/api/article/{}", id))
+        .header(ContentType::JSON)
+        .header(author)
+        .body(body.to_string())
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Ok);
+    let res = app
+        .client
+        .get(format!("/api/article/{}?format=html", id))
+        .dispatch()
+        .await;
+    assert_eq!(
+        json(res).await["data"]["content_html"],
+        "<p><em>new</em></p>\n"
+    );
+}
+
+#[rocket::async_test]
+async fn code_blocks_are_highlighted_with_matching_css() {
+    let app = TestApp::new().await;
+    let (_, author) = app.login_as("author", Role::Author).await;
+    let id = post_article(
+        &app,
+        author,
+        "```rust\nfn main() {}\n```\n\n    <b>plain</b>\n",
+    )
+    .await;
+
+    let html = app
+        .repos
+        .articles
+        .find_by_id(id)
+        .await
+        .unwrap()
+        .unwrap()
+        .content_html
+        .unwrap();
+    assert!(html.contains(r#"<pre class="hl-code"><code><span class="hl-source hl-rust">"#));
+    assert!(html.contains(r#"<span class="hl-storage hl-type hl-function hl-rust">fn</span>"#));
+    // 未标注语言的代码块按纯文本转义
+    assert!(html.contains("&lt;b&gt;plain&lt;/b&gt;"));
+
+    let res = app.client.get("/api/highlight.css").dispatch().await;
+    assert_eq!(res.status(), Status::Ok);
+    assert_eq!(res.content_type(), Some(ContentType::CSS));
+    let css = res.into_string().await.unwrap();
+    assert!(css.contains(".hl-code {"));
+    assert!(css.contains(".hl-storage"));
+}
+
+#[rocket::async_test]
+async fn detail_returns_toc_and_word_count() {
+    let app = TestApp::new().await;
+    let (_, author) = app.login_as("author", Role::Author).await;
+    let id = post_article(
+        &app,
+        author,
+        "# A\n\n## B\n\n### C\n\n## D\n\n你好世界 hello world's end\n",
+    )
+    .await;
+
+    let res = app
+        .client
+        .get(format!("/api/article/{}", id))
+        .dispatch()
+        .await;
+    let body = json(res).await;
+    let toc = &body["data"]["toc"];
+    assert!(toc.is_array());
+    assert_eq!(toc[0]["id"], "user-content-a");
+    assert_eq!(toc[0]["level"], 1);
+    assert_eq!(toc[0]["children"][0]["text"], "B");
+    assert_eq!(toc[0]["children"][0]["children"][0]["id"], "user-content-c");
+    assert_eq!(toc[0]["children"][1]["id"], "user-content-d");
+    // 标题 4 个单词，正文 4 个汉字及 3 个单词
+    assert_eq!(body["data"]["word_count"], 11);
+    assert_eq!(body["data"]["reading_minutes"], 1);
+}
+
+#[rocket::async_test]
+async fn reading_time_grows_with_content() {
+    let app = TestApp::new().await;
+    let (_, author) = app.login_as("author", Role::Author).await;
+    // 600 个汉字（2 分钟）及 300 个单词（1.5 分钟）
+    let content = format!("{}\n\n{}", "汉".repeat(600), "word ".repeat(300));
+    let id = post_article(&app, author, &content).await;
+
+    let article = app.repos.articles.find_by_id(id).await.unwrap().unwrap();
+    assert_eq!(article.word_count, Some(900));
+    assert_eq!(article.reading_minutes, Some(4));
+    assert_eq!(article.toc.unwrap().0, vec![]);
+}
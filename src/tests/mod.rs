@@ -0,0 +1,96 @@
+// 接口测试：使用内存数据访问实现，不需要数据库
+use rocket::figment::Figment;
+use rocket::http::Header;
+use rocket::local::asynchronous::{Client, LocalResponse};
+use rocket::serde::json::Value;
+
+use crate::model::user::{NewUser, User, UserProfile};
+use crate::repository::Repositories;
+use crate::util::keyring::{self, KeyRing};
+use crate::util::permission::Role;
+use crate::util::slug::slugify;
+use crate::util::throttle::{LoginThrottle, ThrottleConfig};
+use crate::util::token::create_token;
+
+mod articles;
+mod categories;
+mod excerpts;
+mod markdown;
+mod publishing;
+mod revisions;
+mod slugs;
+mod tags;
+mod two_factor;
+mod users;
+
+/// 测试用的应用及其数据
+pub struct TestApp {
+    pub client: Client,
+    pub repos: Repositories,
+}
+
+impl TestApp {
+    /// 使用空的内存数据启动应用
+    pub async fn new() -> TestApp {
+        // 未配置密钥时使用临时密钥
+        keyring::init(KeyRing::from_figment(&Figment::new()).expect("keyring"));
+        let repos = Repositories::memory();
+        let figment = rocket::Config::figment().merge(("log_level", "off"));
+        let rocket = crate::app(rocket::custom(figment))
+            .manage(repos.clone())
+            .manage(LoginThrottle::new(ThrottleConfig::default()));
+        let client = Client::tracked(rocket).await.expect("valid rocket");
+        TestApp { client, repos }
+    }
+
+    /// 新增用户
+    pub async fn add_user(&self, username: &str, role: Role) -> User {
+        let new_user = NewUser {
+            username: username.to_string(),
+            password: "password".to_string(),
+            role: role.as_str().to_string(),
+            profile: UserProfile::default(),
+        };
+        let id = self.repos.users.create(new_user).await.unwrap().unwrap();
+        self.repos
+            .users
+            .find_by_id(id as u32)
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    /// 新增分类，返回其 id
+    pub async fn add_category(&self, name: &str) -> u32 {
+        let res = self
+            .repos
+            .categories
+            .create(name, &slugify(name))
+            .await
+            .unwrap();
+        res.last_insert_id.unwrap() as u32
+    }
+
+    /// 新增标签，返回其 id
+    pub async fn add_tag(&self, name: &str) -> u32 {
+        let res = self.repos.tags.create(name, &slugify(name)).await.unwrap();
+        res.last_insert_id.unwrap() as u32
+    }
+
+    /// 新增用户并返回其访问令牌
+    pub async fn login_as(&self, username: &str, role: Role) -> (User, Header<'static>) {
+        let user = self.add_user(username, role).await;
+        let header = auth(&user);
+        (user, header)
+    }
+}
+
+/// 用户的 Authorization 请求头
+pub fn auth(user: &User) -> Header<'static> {
+    Header::new("Authorization", create_token(user))
+}
+
+/// 读取响应的 json
+pub async fn json(response: LocalResponse<'_>) -> Value {
+    response.into_json::<Value>().await.expect("json body")
+}
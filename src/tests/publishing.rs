@@ -0,0 +1,214 @@
+use chrono::Local;
+use rocket::http::{ContentType, Header, Status};
+use rocket::serde::json::Value;
+
+use super::{json, TestApp};
+use crate::util::permission::Role;
+
+/// 提交文章，返回响应的 json
+async fn post_article(app: &TestApp, auth: Header<'static>, extra: &str) -> (Status, Value) {
+    let cate_id = match app.repos.categories.find_by_name("default").await.unwrap() {
+        Some(category) => category.id.unwrap(),
+        None => app.add_category("default").await,
+    };
+    let tag_id = match app.repos.tags.find_by_name("rust").await.unwrap() {
+        Some(tag) => tag.id.unwrap(),
+        None => app.add_tag("rust").await,
+    };
+    let res = app
+        .client
+        .post("/api/article")
+        .header(ContentType::JSON)
+        .header(auth)
+        .body(format!(
+            r#"{{"title":"post","content":"c","cate_id":{},"tags":[{}]{}}}"#,
+            cate_id, tag_id, extra
+        ))
+        .dispatch()
+        .await;
+    (res.status(), json(res).await)
+}
+
+/// 文章 id 及其在前台各接口中出现的次数
+async fn public_visibility(app: &TestApp) -> (u32, Vec<usize>) {
+    let article = app
+        .repos
+        .articles
+        .admin_search(String::new(), 0)
+        .await
+        .unwrap()[0]
+        .clone();
+    let (id, cate_id) = (article.id.unwrap(), article.cate_id.unwrap());
+    let tag_id = app
+        .repos
+        .tags
+        .find_by_name("rust")
+        .await
+        .unwrap()
+        .unwrap()
+        .id
+        .unwrap();
+
+    let mut counts = vec![];
+    for uri in [
+        "/api/articles?page=1&pageSize=10".to_string(),
+        "/api/article/hot".to_string(),
+        "/api/article/search/post".to_string(),
+        format!("/api/category/{}/artlist", cate_id),
+        format!("/api/tag/{}/articles", tag_id),
+    ] {
+        let body = json(app.client.get(uri).dispatch().await).await;
+        counts.push(body["data"].as_array().unwrap().len());
+    }
+    let res = app
+        .client
+        .get(format!("/api/article/{}", id))
+        .dispatch()
+        .await;
+    counts.push((res.status() == Status::Ok) as usize);
+    (id, counts)
+}
+
+#[rocket::async_test]
+async fn drafts_are_hidden_until_published() {
+    let app = TestApp::new().await;
+    let (_, author) = app.login_as("author", Role::Author).await;
+    let (status, _) = post_article(&app, author.clone(), r#","status":"draft""#).await;
+    assert_eq!(status, Status::Ok);
+
+    let (id, counts) = public_visibility(&app).await;
+    assert_eq!(counts, [0, 0, 0, 0, 0, 0]);
+    let res = app.client.get("/api/categories").dispatch().await;
+    assert_eq!(json(res).await["data"][0]["blog_count"], 0);
+
+    let res = app
+        .client
+        .get(format!("/api/article/edit/{}", id))
+        .header(author.clone())
+        .dispatch()
+        .await;
+    let body = json(res).await;
+    assert_eq!(body["data"]["status"], "draft");
+    assert_eq!(body["data"]["published_at"], Value::Null);
+
+    let article = app.repos.articles.find_by_id(id).await.unwrap().unwrap();
+    let res = app
+        .client
+        .put(format!("/api/article/{}", id))
+        .header(ContentType::JSON)
+        .header(author)
+        .body(format!(
+            r#"{{"title":"post","content":"c","cate_id":{},"tags":[],"status":"published"}}"#,
+            article.cate_id.unwrap()
+        ))
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Ok);
+    let (_, counts) = public_visibility(&app).await;
+    assert_eq!(counts, [1, 1, 1, 1, 0, 1]);
+    let article = app.repos.articles.find_by_id(id).await.unwrap().unwrap();
+    assert!(article.published_at.is_some());
+}
+
+#[rocket::async_test]
+async fn contributors_only_write_drafts() {
+    let app = TestApp::new().await;
+    let (_, contributor) = app.login_as("contributor", Role::Contributor).await;
+
+    let (status, _) = post_article(&app, contributor.clone(), r#","status":"published""#).await;
+    assert_eq!(status, Status::Forbidden);
+
+    let (status, _) = post_article(&app, contributor.clone(), "").await;
+    assert_eq!(status, Status::Ok);
+    let article = app
+        .repos
+        .articles
+        .admin_search(String::new(), 0)
+        .await
+        .unwrap()[0]
+        .clone();
+    assert_eq!(article.status.as_deref(), Some("draft"));
+}
+
+#[rocket::async_test]
+async fn contributor_changes_to_live_articles_return_to_draft() {
+    let app = TestApp::new().await;
+    let (_, editor) = app.login_as("editor", Role::Editor).await;
+    let (_, contributor) = app.login_as("contributor", Role::Contributor).await;
+    post_article(&app, contributor.clone(), "").await;
+    let (id, _) = public_visibility(&app).await;
+    let cate_id = app.repos.categories.find_by_name("default").await.unwrap();
+    let cate_id = cate_id.unwrap().id.unwrap();
+
+    let put = |auth: &Header<'static>, title: &str, content: &str, extra: &str| {
+        app.client
+            .put(format!("/api/article/{}", id))
+            .header(ContentType::JSON)
+            .header(auth.clone())
+            .body(format!(
+                r#"{{"title":"{}","content":"{}","cate_id":{},"tags":[]{}}}"#,
+                title, content, cate_id, extra
+            ))
+    };
+    let res = put(&editor, "post", "c", r#","status":"published""#)
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Ok);
+
+    // 只改标题时保持发布
+    let res = put(&contributor, "renamed", "c", "").dispatch().await;
+    assert_eq!(res.status(), Status::Ok);
+    let article = app.repos.articles.find_by_id(id).await.unwrap().unwrap();
+    assert_eq!(article.status.as_deref(), Some("published"));
+
+    // 修改正文后需要重新发布
+    let res = put(
+        &contributor,
+        "renamed",
+        "changed",
+        r#","status":"published""#,
+    )
+    .dispatch()
+    .await;
+    assert_eq!(res.status(), Status::Ok);
+    let article = app.repos.articles.find_by_id(id).await.unwrap().unwrap();
+    assert_eq!(article.status.as_deref(), Some("draft"));
+    assert_eq!(article.published_at, None);
+    let (_, counts) = public_visibility(&app).await;
+    assert_eq!(counts, [0, 0, 0, 0, 0, 0]);
+}
+
+#[rocket::async_test]
+async fn scheduled_articles_go_live_when_due() {
+    let app = TestApp::new().await;
+    let (_, author) = app.login_as("author", Role::Author).await;
+    let now = Local::now().timestamp();
+
+    let (status, body) = post_article(&app, author.clone(), r#","status":"scheduled""#).await;
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(body["details"][0]["field"], "published_at");
+    assert_eq!(body["details"][0]["error"], "required");
+    let past = format!(r#","status":"scheduled","published_at":{}"#, now - 60);
+    let (_, body) = post_article(&app, author.clone(), &past).await;
+    assert_eq!(body["details"][0]["error"], "in_past");
+
+    let at = now + 3600;
+    let future = format!(r#","status":"scheduled","published_at":{}"#, at);
+    let (status, _) = post_article(&app, author, &future).await;
+    assert_eq!(status, Status::Ok);
+    let (id, counts) = public_visibility(&app).await;
+    assert_eq!(counts, [0, 0, 0, 0, 0, 0]);
+
+    assert_eq!(
+        crate::util::scheduler::publish_due(&app.repos)
+            .await
+            .unwrap(),
+        0
+    );
+    assert_eq!(app.repos.articles.publish_due(at).await.unwrap(), 1);
+    let (_, counts) = public_visibility(&app).await;
+    assert_eq!(counts, [1, 1, 1, 1, 1, 1]);
+    let article = app.repos.articles.find_by_id(id).await.unwrap().unwrap();
+    assert_eq!(article.status.as_deref(), Some("published"));
+    assert_eq!(article.published_at, Some(at));
+}
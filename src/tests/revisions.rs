@@ -0,0 +1,235 @@
+use rocket::http::{ContentType, Header, Status};
+use rocket::serde::json::Value;
+
+use super::{json, TestApp};
+use crate::util::permission::Role;
+
+/// 保存文章（id 为 None 时新增），返回文章 id
+async fn save(
+    app: &TestApp,
+    auth: Header<'static>,
+    id: Option<u32>,
+    title: &str,
+    content: &str,
+) -> u32 {
+    let cate_id = match app.repos.categories.find_by_name("default").await.unwrap() {
+        Some(category) => category.id.unwrap(),
+        None => app.add_category("default").await,
+    };
+    let body = rocket::serde::json::serde_json::json!({
+        "title": title,
+        "cate_id": cate_id,
+        "content": content,
+    })
+    .to_string();
+    let req = match id {
+        Some(id) => app.client.put(format!("/api/article/{}", id)),
+        None => app.client.post("/api/article"),
+    };
+    let res = req
+        .header(ContentType::JSON)
+        .header(auth)
+        .body(body)
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Ok);
+    match id {
+        Some(id) => id,
+        None => app
+            .repos
+            .articles
+            .admin_search(String::new(), 0)
+            .await
+            .unwrap()
+            .last()
+            .unwrap()
+            .id
+            .unwrap(),
+    }
+}
+
+async fn get(app: &TestApp, auth: Header<'static>, uri: String) -> (Status, Value) {
+    let res = app.client.get(uri).header(auth).dispatch().await;
+    (res.status(), json(res).await)
+}
+
+#[rocket::async_test]
+async fn every_save_is_a_revision_and_can_be_diffed() {
+    let app = TestApp::new().await;
+    let (user, author) = app.login_as("author", Role::Author).await;
+    let id = save(&app, author.clone(), None, "post", "one\ntwo\nthree\n").await;
+    save(
+        &app,
+        author.clone(),
+        Some(id),
+        "post v2",
+        "one\n2\nthree\nfour\n",
+    )
+    .await;
+
+    let (status, body) = get(
+        &app,
+        author.clone(),
+        format!("/api/article/{}/revisions", id),
+    )
+    .await;
+    assert_eq!(status, Status::Ok);
+    let revisions = body["data"].as_array().unwrap();
+    assert_eq!(revisions.len(), 2);
+    assert_eq!(revisions[0]["revision"], 2);
+    assert_eq!(revisions[0]["title"], "post v2");
+    assert_eq!(revisions[0]["author_id"], user.id.unwrap());
+    assert!(revisions[0].get("content").is_none());
+    assert_eq!(revisions[1]["revision"], 1);
+
+    let (_, body) = get(
+        &app,
+        author.clone(),
+        format!("/api/article/{}/revisions/1", id),
+    )
+    .await;
+    assert_eq!(body["data"]["content"], "one\ntwo\nthree\n");
+
+    let uri = format!("/api/article/{}/revisions/diff?from=1&to=2", id);
+    let (status, body) = get(&app, author, uri).await;
+    assert_eq!(status, Status::Ok);
+    let content = &body["data"]["content"];
+    assert_eq!(content["insertions"], 2);
+    assert_eq!(content["deletions"], 1);
+    let ops: Vec<(&str, &str)> = content["hunks"][0]["lines"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|l| (l["op"].as_str().unwrap(), l["text"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        ops,
+        [
+            ("equal", "one"),
+            ("delete", "two"),
+            ("insert", "2"),
+            ("equal", "three"),
+            ("insert", "four")
+        ]
+    );
+    assert_eq!(body["data"]["title"]["deletions"], 1);
+    assert_eq!(body["data"]["description"]["hunks"], Value::Array(vec![]));
+}
+
+#[rocket::async_test]
+async fn restore_saves_a_new_revision() {
+    let app = TestApp::new().await;
+    let (_, author) = app.login_as("author", Role::Author).await;
+    let (editor, editor_auth) = app.login_as("editor", Role::Editor).await;
+    let id = save(&app, author.clone(), None, "post", "first").await;
+    save(&app, author, Some(id), "post", "mistake").await;
+
+    let res = app
+        .client
+        .post(format!("/api/article/{}/revisions/1/restore", id))
+        .header(editor_auth.clone())
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Ok);
+    let article = app.repos.articles.find_by_id(id).await.unwrap().unwrap();
+    assert_eq!(article.content.as_deref(), Some("first"));
+
+    let (_, body) = get(
+        &app,
+        editor_auth.clone(),
+        format!("/api/article/{}/revisions", id),
+    )
+    .await;
+    assert_eq!(body["data"].as_array().unwrap().len(), 3);
+    assert_eq!(body["data"][0]["revision"], 3);
+    assert_eq!(body["data"][0]["author_id"], editor.id.unwrap());
+
+    let res = app
+        .client
+        .post(format!("/api/article/{}/revisions/9/restore", id))
+        .header(editor_auth)
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::NotFound);
+    assert_eq!(json(res).await["error"], "revision_not_found");
+}
+
+#[rocket::async_test]
+async fn contributor_restores_on_live_articles_return_to_draft() {
+    let app = TestApp::new().await;
+    let (_, contributor) = app.login_as("contributor", Role::Contributor).await;
+    let (_, editor) = app.login_as("editor", Role::Editor).await;
+    let id = save(&app, contributor.clone(), None, "post", "first").await;
+    let cate_id = app.repos.categories.find_by_name("default").await.unwrap();
+    let body = rocket::serde::json::serde_json::json!({
+        "title": "post",
+        "cate_id": cate_id.unwrap().id,
+        "content": "reviewed",
+        "status": "published",
+    });
+    let res = app
+        .client
+        .put(format!("/api/article/{}", id))
+        .header(ContentType::JSON)
+        .header(editor.clone())
+        .body(body.to_string())
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Ok);
+
+    // 编辑恢复当前正文时保持发布
+    let res = app
+        .client
+        .post(format!("/api/article/{}/revisions/2/restore", id))
+        .header(editor)
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Ok);
+    let article = app.repos.articles.find_by_id(id).await.unwrap().unwrap();
+    assert_eq!(article.status.as_deref(), Some("published"));
+
+    // 投稿者恢复未审核的版本后需要重新发布
+    let res = app
+        .client
+        .post(format!("/api/article/{}/revisions/1/restore", id))
+        .header(contributor)
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Ok);
+    let article = app.repos.articles.find_by_id(id).await.unwrap().unwrap();
+    assert_eq!(article.content.as_deref(), Some("first"));
+    assert_eq!(article.status.as_deref(), Some("draft"));
+    assert_eq!(article.published_at, None);
+}
+
+#[rocket::async_test]
+async fn revisions_need_edit_permission() {
+    let app = TestApp::new().await;
+    let (_, author) = app.login_as("author", Role::Author).await;
+    let (_, other) = app.login_as("other", Role::Author).await;
+    let id = save(&app, author, None, "post", "c").await;
+
+    let res = app
+        .client
+        .get(format!("/api/article/{}/revisions", id))
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Unauthorized);
+    let (status, _) = get(
+        &app,
+        other.clone(),
+        format!("/api/article/{}/revisions", id),
+    )
+    .await;
+    assert_eq!(status, Status::Forbidden);
+    let res = app
+        .client
+        .post(format!("/api/article/{}/revisions/1/restore", id))
+        .header(other)
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Forbidden);
+
+    app.repos.articles.remove(id).await.unwrap();
+    assert!(app.repos.articles.revisions(id).await.unwrap().is_empty());
+}
@@ -0,0 +1,242 @@
+use rocket::http::{ContentType, Header, Status};
+use rocket::serde::json::Value;
+
+use super::{json, TestApp};
+use crate::util::permission::Role;
+
+/// 提交文章，返回响应的 json
+async fn post_article(
+    app: &TestApp,
+    auth: Header<'static>,
+    title: &str,
+    status: &str,
+) -> (Status, Value) {
+    let cate_id = match app.repos.categories.find_by_name("default").await.unwrap() {
+        Some(category) => category.id.unwrap(),
+        None => app.add_category("default").await,
+    };
+    let res = app
+        .client
+        .post("/api/article")
+        .header(ContentType::JSON)
+        .header(auth)
+        .body(format!(
+            r#"{{"title":"{}","content":"c","cate_id":{},"tags":[],"status":"{}"}}"#,
+            title, cate_id, status
+        ))
+        .dispatch()
+        .await;
+    (res.status(), json(res).await)
+}
+
+/// 修改文章，返回响应状态及 json
+async fn put_article(
+    app: &TestApp,
+    auth: Header<'static>,
+    id: u32,
+    extra: &str,
+) -> (Status, Value) {
+    let article = app.repos.articles.find_by_id(id).await.unwrap().unwrap();
+    let res = app
+        .client
+        .put(format!("/api/article/{}", id))
+        .header(ContentType::JSON)
+        .header(auth)
+        .body(format!(
+            r#"{{"title":"{}","content":"c","cate_id":{},"tags":[],"status":"published"{}}}"#,
+            article.title.unwrap(),
+            article.cate_id.unwrap(),
+            extra
+        ))
+        .dispatch()
+        .await;
+    (res.status(), json(res).await)
+}
+
+/// 按 slug 查找文章 id
+async fn article_id(app: &TestApp, slug: &str) -> u32 {
+    app.repos
+        .articles
+        .find_by_slug(slug)
+        .await
+        .unwrap()
+        .unwrap()
+        .id
+        .unwrap()
+}
+
+#[rocket::async_test]
+async fn slugs_are_generated_from_titles() {
+    let app = TestApp::new().await;
+    let (_, author) = app.login_as("author", Role::Author).await;
+
+    for title in ["Rust 入门", "Rust 入门", "!!!"] {
+        let (status, _) = post_article(&app, author.clone(), title, "published").await;
+        assert_eq!(status, Status::Ok);
+    }
+    assert!(app
+        .repos
+        .articles
+        .find_by_slug("rust-ru-men")
+        .await
+        .unwrap()
+        .is_some());
+    assert!(app
+        .repos
+        .articles
+        .find_by_slug("rust-ru-men-2")
+        .await
+        .unwrap()
+        .is_some());
+    assert!(app
+        .repos
+        .articles
+        .find_by_slug("article")
+        .await
+        .unwrap()
+        .is_some());
+
+    let res = app
+        .client
+        .get("/api/article/slug/rust-ru-men-2")
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Ok);
+    assert_eq!(json(res).await["data"]["slug"], "rust-ru-men-2");
+
+    // 草稿不能按 slug 访问
+    let (_, body) = post_article(&app, author, "draft", "draft").await;
+    assert_eq!(body["code"], 200);
+    let res = app.client.get("/api/article/slug/draft").dispatch().await;
+    assert_eq!(res.status(), Status::NotFound);
+    assert_eq!(json(res).await["error"], "article_not_found");
+}
+
+#[rocket::async_test]
+async fn only_admins_change_slugs() {
+    let app = TestApp::new().await;
+    let (_, author) = app.login_as("author", Role::Author).await;
+    let (_, admin) = app.login_as("admin", Role::Admin).await;
+    post_article(&app, author.clone(), "hello world", "published").await;
+    post_article(&app, author.clone(), "other", "published").await;
+    let id = article_id(&app, "hello-world").await;
+
+    // 提交原 slug 不算修改
+    let (status, _) = put_article(&app, author.clone(), id, r#","slug":"hello-world""#).await;
+    assert_eq!(status, Status::Ok);
+    let (status, _) = put_article(&app, author, id, r#","slug":"hello""#).await;
+    assert_eq!(status, Status::Forbidden);
+
+    let (status, body) = put_article(&app, admin.clone(), id, r#","slug":"Hello World""#).await;
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(body["details"][0]["field"], "slug");
+    assert_eq!(body["details"][0]["error"], "invalid_format");
+    let (_, body) = put_article(&app, admin.clone(), id, r#","slug":"other""#).await;
+    assert_eq!(body["details"][0]["error"], "taken");
+
+    let (status, _) = put_article(&app, admin.clone(), id, r#","slug":"hello""#).await;
+    assert_eq!(status, Status::Ok);
+    let res = app
+        .client
+        .get("/api/article/slug/hello-world")
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::MovedPermanently);
+    assert_eq!(
+        res.headers().get_one("Location"),
+        Some("/api/article/slug/hello")
+    );
+
+    // 旧 slug 仍指向原文章，不能被其他文章使用
+    let other = article_id(&app, "other").await;
+    let (_, body) = put_article(&app, admin.clone(), other, r#","slug":"hello-world""#).await;
+    assert_eq!(body["details"][0]["error"], "taken");
+
+    // 改回旧 slug 后不再重定向
+    let (status, _) = put_article(&app, admin, id, r#","slug":"hello-world""#).await;
+    assert_eq!(status, Status::Ok);
+    let res = app
+        .client
+        .get("/api/article/slug/hello-world")
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Ok);
+    let res = app.client.get("/api/article/slug/hello").dispatch().await;
+    assert_eq!(
+        res.headers().get_one("Location"),
+        Some("/api/article/slug/hello-world")
+    );
+}
+
+#[rocket::async_test]
+async fn category_and_tag_slugs_redirect() {
+    let app = TestApp::new().await;
+    let (_, admin) = app.login_as("admin", Role::Admin).await;
+    let cate_id = app.add_category("编程").await;
+    let tag_id = app.add_tag("rust").await;
+
+    let res = app
+        .client
+        .get("/api/category/slug/bian-cheng")
+        .dispatch()
+        .await;
+    assert_eq!(json(res).await["data"]["id"], cate_id);
+    let res = app
+        .client
+        .get("/api/category/slug/bian-cheng/artlist")
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Ok);
+    let res = app
+        .client
+        .get("/api/tag/slug/rust/articles")
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Ok);
+
+    let res = app
+        .client
+        .put(format!("/api/categories/{}", cate_id))
+        .header(ContentType::JSON)
+        .header(admin.clone())
+        .body(r#"{"name":"编程","slug":"programming"}"#)
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Ok);
+    let res = app
+        .client
+        .get("/api/category/slug/bian-cheng/artlist")
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::MovedPermanently);
+    assert_eq!(
+        res.headers().get_one("Location"),
+        Some("/api/category/slug/programming/artlist")
+    );
+
+    let res = app
+        .client
+        .put(format!("/api/tags/{}", tag_id))
+        .header(ContentType::JSON)
+        .header(admin)
+        .body(r#"{"name":"rust","slug":"rust-lang"}"#)
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Ok);
+    let res = app
+        .client
+        .get("/api/tag/slug/rust/articles")
+        .dispatch()
+        .await;
+    assert_eq!(
+        res.headers().get_one("Location"),
+        Some("/api/tag/slug/rust-lang/articles")
+    );
+
+    let res = app
+        .client
+        .get("/api/category/slug/unknown")
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::NotFound);
+}
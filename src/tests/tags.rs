@@ -0,0 +1,66 @@
+use rocket::http::{ContentType, Status};
+
+use super::{json, TestApp};
+use crate::util::permission::Role;
+
+#[rocket::async_test]
+async fn contributor_cannot_manage_tags() {
+    let app = TestApp::new().await;
+    let (_, contributor) = app.login_as("contributor", Role::Contributor).await;
+
+    let res = app
+        .client
+        .post("/api/tags")
+        .header(ContentType::JSON)
+        .header(contributor)
+        .body(r#"{"name":"web"}"#)
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Forbidden);
+}
+
+#[rocket::async_test]
+async fn list_counts_tagged_articles() {
+    let app = TestApp::new().await;
+    let (_, admin) = app.login_as("admin", Role::Admin).await;
+
+    for name in ["web", "db"] {
+        let res = app
+            .client
+            .post("/api/tags")
+            .header(ContentType::JSON)
+            .header(admin.clone())
+            .body(format!(r#"{{"name":"{}"}}"#, name))
+            .dispatch()
+            .await;
+        assert_eq!(json(res).await["code"], 200);
+    }
+    let tags = app.repos.tags.find_all_with_blogcount().await.unwrap();
+    let web = tags[0].id.unwrap();
+    let cate_id = app.add_category("default").await;
+
+    let res = app
+        .client
+        .post("/api/article")
+        .header(ContentType::JSON)
+        .header(admin)
+        .body(format!(
+            r#"{{"title":"t","description":"d","cate_id":{},"content":"c","tags":[{}]}}"#,
+            cate_id, web
+        ))
+        .dispatch()
+        .await;
+    assert_eq!(json(res).await["code"], 200);
+
+    let res = app.client.get("/api/tags").dispatch().await;
+    let body = json(res).await;
+    assert_eq!(body["data"][0]["blog_count"], 1);
+    assert_eq!(body["data"][1]["blog_count"], 0);
+
+    let res = app
+        .client
+        .get(format!("/api/tag/{}/articles", web))
+        .dispatch()
+        .await;
+    assert_eq!(json(res).await["data"][0]["title"], "t");
+}
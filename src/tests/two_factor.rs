@@ -0,0 +1,61 @@
+use rocket::http::{ContentType, Status};
+
+use super::{json, TestApp};
+use crate::util::permission::Role;
+use crate::util::totp;
+
+#[rocket::async_test]
+async fn second_factor_checks_are_throttled() {
+    let app = TestApp::new().await;
+    let (admin, admin_auth) = app.login_as("admin", Role::Admin).await;
+    let (author, author_auth) = app.login_as("author", Role::Author).await;
+    for user in [&admin, &author] {
+        app.repos
+            .users
+            .enable_totp(user.id.unwrap(), &totp::generate_secret(), 0)
+            .await
+            .unwrap();
+    }
+
+    // 第一次失败后进入退避，紧接着的尝试被拒绝
+    let regenerate = || {
+        app.client
+            .post("/api/admin/2fa/recovery-codes")
+            .header(ContentType::JSON)
+            .header(author_auth.clone())
+            .body(r#"{"code":"not a code"}"#)
+    };
+    let res = regenerate().dispatch().await;
+    assert_eq!(res.status(), Status::BadRequest);
+    assert_eq!(json(res).await["error"], "invalid_code");
+    let res = regenerate().dispatch().await;
+    assert_eq!(res.status(), Status::TooManyRequests);
+
+    let disable = || {
+        app.client
+            .post("/api/admin/2fa/disable")
+            .header(ContentType::JSON)
+            .header(admin_auth.clone())
+            .body(r#"{"password":"wrong password","code":"not a code"}"#)
+    };
+    let res = disable().dispatch().await;
+    assert_eq!(res.status(), Status::BadRequest);
+    assert_eq!(json(res).await["error"], "invalid_password");
+    let res = disable().dispatch().await;
+    assert_eq!(res.status(), Status::TooManyRequests);
+
+    let events = app.repos.users.recent_login_events(10).await.unwrap();
+    let events: Vec<_> = events
+        .iter()
+        .map(|e| (e.username.as_deref().unwrap(), e.event.as_deref().unwrap()))
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            ("admin", "throttled"),
+            ("admin", "failed"),
+            ("author", "throttled"),
+            ("author", "2fa_failed"),
+        ]
+    );
+}
@@ -0,0 +1,447 @@
+use rocket::http::{ContentType, Header, Status};
+use rocket::serde::json::{serde_json::json, Value};
+
+use super::{auth, json, TestApp};
+use crate::util::permission::Role;
+
+#[rocket::async_test]
+async fn only_admins_manage_users() {
+    let app = TestApp::new().await;
+    let (_, editor) = app.login_as("editor", Role::Editor).await;
+
+    let res = app.client.get("/api/users").header(editor).dispatch().await;
+    assert_eq!(res.status(), Status::Forbidden);
+    assert_eq!(json(res).await["error"], "forbidden");
+
+    let res = app.client.get("/api/users").dispatch().await;
+    assert_eq!(res.status(), Status::Unauthorized);
+    assert_eq!(json(res).await["error"], "unauthorized");
+}
+
+#[rocket::async_test]
+async fn reset_password_of_missing_user_is_not_found() {
+    let app = TestApp::new().await;
+    let (_, admin) = app.login_as("admin", Role::Admin).await;
+
+    let res = app
+        .client
+        .put("/api/user/9999/password")
+        .header(ContentType::JSON)
+        .header(admin)
+        .body(r#"{"new_password":"new password"}"#)
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::NotFound);
+    assert_eq!(json(res).await["error"], "user_not_found");
+}
+
+#[rocket::async_test]
+async fn create_validates_and_rejects_duplicates() {
+    let app = TestApp::new().await;
+    let (_, admin) = app.login_as("admin", Role::Admin).await;
+    let create = |body: &'static str| {
+        app.client
+            .post("/api/users")
+            .header(ContentType::JSON)
+            .header(admin.clone())
+            .body(body)
+    };
+
+    for (body, field) in [
+        (
+            r#"{"username":"a","password":"password","role":"author"}"#,
+            "username",
+        ),
+        (
+            r#"{"username":"writer","password":"short","role":"author"}"#,
+            "password",
+        ),
+        (
+            r#"{"username":"writer","password":"password","role":"owner"}"#,
+            "role",
+        ),
+    ] {
+        let res = create(body).dispatch().await;
+        assert_eq!(res.status(), Status::UnprocessableEntity);
+        let body = json(res).await;
+        assert_eq!(body["error"], "validation_failed");
+        assert_eq!(body["details"][0]["field"], field);
+    }
+
+    let res = create(r#"{"username":"writer","password":"password","role":"author"}"#)
+        .dispatch()
+        .await;
+    assert_eq!(json(res).await["code"], 200);
+    let res = create(r#"{"username":"writer","password":"password","role":"author"}"#)
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::UnprocessableEntity);
+    let body = json(res).await;
+    assert_eq!(body["details"][0]["field"], "username");
+    assert_eq!(body["details"][0]["error"], "taken");
+
+    let res = app.client.get("/api/users").header(admin).dispatch().await;
+    let body = json(res).await;
+    assert_eq!(body["data"].as_array().unwrap().len(), 2);
+    assert!(body["data"][1].get("password").is_none());
+}
+
+#[rocket::async_test]
+async fn only_admins_change_roles() {
+    let app = TestApp::new().await;
+    let (author, author_auth) = app.login_as("author", Role::Author).await;
+    let (_, admin) = app.login_as("admin", Role::Admin).await;
+    let uri = format!("/api/user/{}", author.id.unwrap());
+
+    let res = app
+        .client
+        .put(uri.clone())
+        .header(ContentType::JSON)
+        .header(author_auth.clone())
+        .body(r#"{"nickname":"Writer"}"#)
+        .dispatch()
+        .await;
+    assert_eq!(json(res).await["code"], 200);
+    let res = app
+        .client
+        .put(uri.clone())
+        .header(ContentType::JSON)
+        .header(author_auth)
+        .body(r#"{"role":"admin"}"#)
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Forbidden);
+
+    let res = app
+        .client
+        .put(uri)
+        .header(ContentType::JSON)
+        .header(admin)
+        .body(r#"{"role":"editor"}"#)
+        .dispatch()
+        .await;
+    assert_eq!(json(res).await["code"], 200);
+    let user = app
+        .repos
+        .users
+        .find_by_id(author.id.unwrap())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(user.role.as_deref(), Some("editor"));
+    assert_eq!(user.nickname.as_deref(), Some("Writer"));
+}
+
+#[rocket::async_test]
+async fn cannot_demote_the_last_admin() {
+    let app = TestApp::new().await;
+    let (admin, admin_auth) = app.login_as("admin", Role::Admin).await;
+    let demote = |id: u32| {
+        app.client
+            .put(format!("/api/user/{}", id))
+            .header(ContentType::JSON)
+            .header(admin_auth.clone())
+            .body(r#"{"role":"editor"}"#)
+    };
+
+    let res = demote(admin.id.unwrap()).dispatch().await;
+    assert_eq!(res.status(), Status::Conflict);
+    assert_eq!(json(res).await["error"], "last_admin");
+    let user = app
+        .repos
+        .users
+        .find_by_id(admin.id.unwrap())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(user.role.as_deref(), Some("admin"));
+
+    // 有其他管理员时可以降级
+    let other = app.add_user("other", Role::Admin).await;
+    let res = demote(other.id.unwrap()).dispatch().await;
+    assert_eq!(res.status(), Status::Ok);
+    let res = demote(admin.id.unwrap()).dispatch().await;
+    assert_eq!(res.status(), Status::Conflict);
+}
+
+#[rocket::async_test]
+async fn reset_password_revokes_sessions() {
+    let app = TestApp::new().await;
+    let (_, admin) = app.login_as("admin", Role::Admin).await;
+    let (author, author_auth) = app.login_as("author", Role::Author).await;
+
+    let res = app
+        .client
+        .get("/api/admin/me")
+        .header(author_auth.clone())
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Ok);
+
+    let res = app
+        .client
+        .put(format!("/api/user/{}/password", author.id.unwrap()))
+        .header(ContentType::JSON)
+        .header(admin)
+        .body(r#"{"new_password":"new password"}"#)
+        .dispatch()
+        .await;
+    assert_eq!(json(res).await["code"], 200);
+
+    let res = app
+        .client
+        .get("/api/admin/me")
+        .header(author_auth)
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Unauthorized);
+
+    // 吊销之后立即签发的令牌有效
+    let body = login(&app, "author", "new password").await;
+    let token = body["data"]["token"].as_str().unwrap().to_string();
+    let res = app
+        .client
+        .get("/api/admin/me")
+        .header(Header::new("Authorization", token))
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Ok);
+}
+
+#[rocket::async_test]
+async fn cannot_delete_yourself() {
+    let app = TestApp::new().await;
+    let (admin, admin_auth) = app.login_as("admin", Role::Admin).await;
+    let other = app.add_user("other", Role::Admin).await;
+
+    let res = app
+        .client
+        .delete(format!("/api/user/{}", admin.id.unwrap()))
+        .header(admin_auth.clone())
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::BadRequest);
+    assert_eq!(json(res).await["error"], "cannot_delete_self");
+
+    let res = app
+        .client
+        .delete(format!("/api/user/{}", other.id.unwrap()))
+        .header(admin_auth)
+        .dispatch()
+        .await;
+    assert_eq!(json(res).await["code"], 200);
+    assert_eq!(app.repos.users.count_admins().await.unwrap(), 1);
+}
+
+#[rocket::async_test]
+async fn deleting_an_author_requires_reassigning_articles() {
+    let app = TestApp::new().await;
+    let (_, admin) = app.login_as("admin", Role::Admin).await;
+    let author = app.add_user("author", Role::Author).await;
+    let heir = app.add_user("heir", Role::Author).await;
+    let (author_id, heir_id) = (author.id.unwrap(), heir.id.unwrap());
+    let cate_id = app.add_category("default").await;
+
+    let res = app
+        .client
+        .post("/api/article")
+        .header(ContentType::JSON)
+        .header(auth(&author))
+        .body(format!(
+            r#"{{"title":"t","description":"d","cate_id":{},"content":"c","tags":[]}}"#,
+            cate_id
+        ))
+        .dispatch()
+        .await;
+    assert_eq!(json(res).await["code"], 200);
+
+    let res = app
+        .client
+        .delete(format!("/api/user/{}", author_id))
+        .header(admin.clone())
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Conflict);
+    let body = json(res).await;
+    assert_eq!(body["error"], "user_has_articles");
+    assert_eq!(body["details"]["articles"], 1);
+
+    let res = app
+        .client
+        .delete(format!("/api/user/{}?reassign_to={}", author_id, author_id))
+        .header(admin.clone())
+        .dispatch()
+        .await;
+    assert_eq!(json(res).await["error"], "invalid_reassign_target");
+    let res = app
+        .client
+        .delete(format!("/api/user/{}?reassign_to=9999", author_id))
+        .header(admin.clone())
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::BadRequest);
+
+    let res = app
+        .client
+        .delete(format!("/api/user/{}?reassign_to={}", author_id, heir_id))
+        .header(admin)
+        .dispatch()
+        .await;
+    assert_eq!(json(res).await["code"], 200);
+    assert!(app
+        .repos
+        .users
+        .find_by_id(author_id)
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        app.repos.articles.count_by_author(heir_id).await.unwrap(),
+        1
+    );
+
+    // 已删除用户的令牌失效
+    let res = app
+        .client
+        .get("/api/admin/me")
+        .header(auth(&author))
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Unauthorized);
+}
+
+/// 登录，返回响应的 json
+async fn login(app: &TestApp, username: &str, password: &str) -> Value {
+    let body = json!({ "username": username, "password": password }).to_string();
+    let res = app
+        .client
+        .post("/api/admin/login")
+        .header(ContentType::JSON)
+        .body(body)
+        .dispatch()
+        .await;
+    json(res).await
+}
+
+/// 刷新令牌，返回响应的 json
+async fn refresh(app: &TestApp, refresh_token: &Value) -> Value {
+    let body = json!({ "refresh_token": refresh_token }).to_string();
+    let res = app
+        .client
+        .post("/api/admin/token/refresh")
+        .header(ContentType::JSON)
+        .body(body)
+        .dispatch()
+        .await;
+    json(res).await
+}
+
+#[rocket::async_test]
+async fn login_issues_tokens_and_records_failures() {
+    let app = TestApp::new().await;
+    let (_, admin) = app.login_as("admin", Role::Admin).await;
+
+    let body = login(&app, "admin", "wrong password").await;
+    assert_eq!(body["error"], "invalid_credentials");
+    let body = login(&app, "nobody", "password").await;
+    assert_eq!(body["error"], "invalid_credentials");
+
+    let res = app
+        .client
+        .get("/api/admin/login-events")
+        .header(admin)
+        .dispatch()
+        .await;
+    let events = json(res).await;
+    let events: Vec<_> = events["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| (e["username"].clone(), e["event"].clone()))
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            (json!("nobody"), json!("failed")),
+            (json!("admin"), json!("failed")),
+        ]
+    );
+
+    let body = login(&app, "admin", "password").await;
+    assert_eq!(body["code"], 200);
+    let res = app
+        .client
+        .get("/api/admin/me")
+        .header(Header::new(
+            "Authorization",
+            body["data"]["token"].as_str().unwrap().to_string(),
+        ))
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Ok);
+
+    // 刷新令牌只能使用一次，重复使用时整个令牌族失效
+    let first = body["data"]["refresh_token"].clone();
+    let rotated = refresh(&app, &first).await;
+    assert_eq!(rotated["code"], 200);
+    assert_eq!(refresh(&app, &first).await["error"], "refresh_token_reused");
+    assert_eq!(
+        refresh(&app, &rotated["data"]["refresh_token"]).await["error"],
+        "refresh_token_reused"
+    );
+}
+
+#[rocket::async_test]
+async fn change_password_checks_the_old_password() {
+    let app = TestApp::new().await;
+    let (_, admin) = app.login_as("admin", Role::Admin).await;
+    let (_, author) = app.login_as("author", Role::Author).await;
+    let refresh_token = login(&app, "author", "password").await["data"]["refresh_token"].clone();
+
+    let change = |body: &'static str| {
+        app.client
+            .put("/api/admin/password")
+            .header(ContentType::JSON)
+            .header(author.clone())
+            .body(body)
+    };
+    let res = change(r#"{"old_password":"wrong password","new_password":"new password"}"#)
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::BadRequest);
+    assert_eq!(json(res).await["error"], "invalid_password");
+
+    // 原密码错误与登录共用失败计数
+    let res = change(r#"{"old_password":"password","new_password":"new password"}"#)
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::TooManyRequests);
+    assert_eq!(
+        login(&app, "author", "password").await["error"],
+        "too_many_requests"
+    );
+    let res = app
+        .client
+        .delete("/api/admin/lockouts?username=author")
+        .header(admin)
+        .dispatch()
+        .await;
+    assert_eq!(res.status(), Status::Ok);
+    assert_eq!(login(&app, "author", "password").await["code"], 200);
+
+    let res = change(r#"{"old_password":"password","new_password":"new password"}"#)
+        .dispatch()
+        .await;
+    assert_eq!(json(res).await["code"], 200);
+    assert_eq!(login(&app, "author", "new password").await["code"], 200);
+    assert_eq!(
+        login(&app, "author", "password").await["error"],
+        "invalid_credentials"
+    );
+
+    // 修改密码前签发的刷新令牌已失效
+    assert_eq!(
+        refresh(&app, &refresh_token).await["error"],
+        "refresh_token_reused"
+    );
+}
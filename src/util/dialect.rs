@@ -0,0 +1,294 @@
+// 数据库方言：根据连接地址选择，生成不同数据库各自的 SQL 片段
+use std::sync::OnceLock;
+
+use rbatis::executor::ExecutorMut;
+use rbatis::plugin::intercept::SqlIntercept;
+use rbatis::rbatis::Rbatis;
+use rbatis::Error;
+use rbson::Bson;
+use rocket::serde::{Deserialize, Deserializer};
+
+/// 全局方言，连接数据库时由 `init` 设置
+static DIALECT: OnceLock<Dialect> = OnceLock::new();
+
+/// 支持的数据库
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dialect {
+    MySql,
+    Sqlite,
+    Postgres,
+}
+
+/// 需要加引号的保留字（作为表名或列名使用时）
+const RESERVED: &[&str] = &["user"];
+
+impl Dialect {
+    /// 根据连接地址的协议识别数据库
+    /// ---
+    /// @parameter  url     &str
+    /// @return     Option<Dialect>     无法识别时返回 None
+    /// ---
+    pub fn from_url(url: &str) -> Option<Dialect> {
+        let scheme = url.split(':').next().unwrap_or_default();
+        match scheme.to_lowercase().as_str() {
+            "mysql" => Some(Dialect::MySql),
+            "sqlite" => Some(Dialect::Sqlite),
+            "postgres" | "postgresql" => Some(Dialect::Postgres),
+            _ => None,
+        }
+    }
+
+    /// 名称，对应 cargo feature
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dialect::MySql => "mysql",
+            Dialect::Sqlite => "sqlite",
+            Dialect::Postgres => "postgres",
+        }
+    }
+
+    /// 当前二进制是否编译了该数据库的驱动
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            Dialect::MySql => cfg!(feature = "mysql"),
+            Dialect::Sqlite => cfg!(feature = "sqlite"),
+            Dialect::Postgres => cfg!(feature = "postgres"),
+        }
+    }
+
+    /// 给标识符加引号
+    /// ---
+    /// @parameter  ident   &str
+    /// @return     String
+    /// ---
+    pub fn quote(&self, ident: &str) -> String {
+        match self {
+            Dialect::MySql => format!("`{}`", ident),
+            Dialect::Sqlite | Dialect::Postgres => format!("\"{}\"", ident),
+        }
+    }
+
+    /// 将分组内的值拼接为逗号分隔的字符串
+    /// ---
+    /// @parameter  expr    &str    列或表达式
+    /// @return     String
+    /// ---
+    pub fn group_concat(&self, expr: &str) -> String {
+        match self {
+            Dialect::MySql => format!("GROUP_CONCAT({} ORDER BY {})", expr, expr),
+            Dialect::Sqlite => format!("group_concat({}, ',')", expr),
+            Dialect::Postgres => {
+                format!("string_agg(CAST({} AS TEXT), ',' ORDER BY {})", expr, expr)
+            }
+        }
+    }
+
+    /// 按表中最大 id 重置自增序列，用于插入指定 id 的记录之后。
+    /// 只有 Postgres 需要，MySQL 和 SQLite 会自动调整自增值
+    /// ---
+    /// @parameter  table   &str
+    /// @return     Option<String>
+    /// ---
+    pub fn reset_sequence(&self, table: &str) -> Option<String> {
+        match self {
+            Dialect::MySql | Dialect::Sqlite => None,
+            Dialect::Postgres => Some(format!(
+                "SELECT setval(pg_get_serial_sequence('{}', 'id'), COALESCE(MAX(id), 0) + 1, false) FROM {}",
+                self.quote(table),
+                table
+            )),
+        }
+    }
+
+    /// 将 SQL 改写为当前数据库的写法：
+    /// `?` 占位符在 Postgres 中改为 `$n`，保留字标识符加引号，反引号改为当前数据库的引号
+    /// ---
+    /// @parameter  sql     &str
+    /// @return     String
+    /// ---
+    pub fn rewrite(&self, sql: &str) -> String {
+        let mut out = String::with_capacity(sql.len() + 8);
+        let mut chars = sql.chars().peekable();
+        let mut index = 0;
+        // 上一个非空白字符，用于判断 `t.user` 这类限定名
+        let mut prev = ' ';
+        while let Some(c) = chars.next() {
+            match c {
+                // 字符串常量和已加引号的标识符原样保留
+                '\'' | '"' => {
+                    out.push(c);
+                    for q in chars.by_ref() {
+                        out.push(q);
+                        if q == c {
+                            break;
+                        }
+                    }
+                }
+                '`' => {
+                    let ident: String = chars.by_ref().take_while(|q| *q != '`').collect();
+                    out.push_str(&self.quote(&ident));
+                }
+                '?' if *self == Dialect::Postgres => {
+                    index += 1;
+                    out.push_str(&format!("${}", index));
+                }
+                c if c.is_ascii_alphabetic() || c == '_' => {
+                    let mut word = c.to_string();
+                    while let Some(&n) = chars.peek() {
+                        if !(n.is_ascii_alphanumeric() || n == '_') {
+                            break;
+                        }
+                        word.push(n);
+                        chars.next();
+                    }
+                    let is_call = chars.peek() == Some(&'(');
+                    let reserved = RESERVED.contains(&word.to_lowercase().as_str());
+                    if reserved && prev != '.' && !is_call {
+                        out.push_str(&self.quote(&word.to_lowercase()));
+                    } else {
+                        out.push_str(&word);
+                    }
+                    prev = 'a';
+                    continue;
+                }
+                _ => out.push(c),
+            }
+            if !c.is_whitespace() {
+                prev = c;
+            }
+        }
+        out
+    }
+}
+
+/// 新记录的 id
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct InsertedId {
+    id: i64,
+}
+
+/// 执行 INSERT 并返回新记录的 id（Postgres 使用 `RETURNING id`）
+/// ---
+/// @parameter  executor    &mut E
+/// @parameter  sql         &str        不含结尾分号的 INSERT 语句
+/// @parameter  args        Vec<Bson>
+/// @return     Result<Option<i64>, Error>
+/// ---
+pub async fn insert_returning_id<E: ExecutorMut>(
+    executor: &mut E,
+    sql: &str,
+    args: Vec<Bson>,
+) -> Result<Option<i64>, Error> {
+    if dialect() == Dialect::Postgres {
+        let row: Option<InsertedId> = executor
+            .fetch(&format!("{} RETURNING id", sql), args)
+            .await?;
+        return Ok(row.map(|r| r.id));
+    }
+    Ok(executor.exec(sql, args).await?.last_insert_id)
+}
+
+/// SQL 拦截器：执行前按当前方言改写全部 SQL（包括 rbatis 生成的 SQL）
+#[derive(Debug)]
+pub struct PortableSql;
+
+impl SqlIntercept for PortableSql {
+    fn do_intercept(
+        &self,
+        _rb: &Rbatis,
+        sql: &mut String,
+        _args: &mut Vec<Bson>,
+        _is_prepared_sql: bool,
+    ) -> Result<(), Error> {
+        if let Some(dialect) = DIALECT.get() {
+            *sql = dialect.rewrite(sql);
+        }
+        Ok(())
+    }
+}
+
+/// 设置全局方言
+pub fn init(dialect: Dialect) {
+    let _ = DIALECT.set(dialect);
+}
+
+/// 当前数据库方言
+pub fn dialect() -> Dialect {
+    *DIALECT.get().expect("database dialect is not initialized")
+}
+
+/// 反序列化布尔列：SQLite 没有布尔类型，读出的是 0/1 整数
+/// ---
+/// 用法：`#[serde(default, deserialize_with = "lenient_bool")]`
+/// ---
+pub fn lenient_bool<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<bool>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(crate = "rocket::serde", untagged)]
+    enum BoolOrInt {
+        Bool(bool),
+        Int(i64),
+    }
+    Ok(match Option::<BoolOrInt>::deserialize(deserializer)? {
+        Some(BoolOrInt::Bool(b)) => Some(b),
+        Some(BoolOrInt::Int(i)) => Some(i != 0),
+        None => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_are_numbered_for_postgres() {
+        let sql = "UPDATE article SET title = ? WHERE id = ?";
+        assert_eq!(
+            Dialect::Postgres.rewrite(sql),
+            "UPDATE article SET title = $1 WHERE id = $2"
+        );
+        assert_eq!(Dialect::MySql.rewrite(sql), sql);
+        assert_eq!(Dialect::Sqlite.rewrite(sql), sql);
+    }
+
+    #[test]
+    fn string_literals_are_kept() {
+        assert_eq!(
+            Dialect::Postgres.rewrite("SELECT 'why?', 'it''s user?' FROM article WHERE id = ?"),
+            "SELECT 'why?', 'it''s user?' FROM article WHERE id = $1"
+        );
+    }
+
+    #[test]
+    fn reserved_words_are_quoted() {
+        let sql = "SELECT * FROM user WHERE id = ?";
+        assert_eq!(
+            Dialect::MySql.rewrite(sql),
+            "SELECT * FROM `user` WHERE id = ?"
+        );
+        assert_eq!(
+            Dialect::Postgres.rewrite(sql),
+            "SELECT * FROM \"user\" WHERE id = $1"
+        );
+        assert_eq!(
+            Dialect::Sqlite.rewrite("DELETE FROM User"),
+            "DELETE FROM \"user\""
+        );
+        assert_eq!(
+            Dialect::Postgres.rewrite("SELECT user_id FROM `user`"),
+            "SELECT user_id FROM \"user\""
+        );
+    }
+
+    #[test]
+    fn quoted_qualified_and_called_names_are_kept() {
+        for sql in [
+            "SELECT * FROM \"user\"",
+            "SELECT t.user FROM t",
+            "SELECT t . user FROM t",
+            "SELECT user() FROM dual",
+        ] {
+            assert_eq!(Dialect::Postgres.rewrite(sql), sql);
+        }
+    }
+}
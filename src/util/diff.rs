@@ -0,0 +1,95 @@
+// 文本差异：按行比较两个版本的正文，输出带上下文的差异片段
+use rocket::serde::Serialize;
+use similar::{ChangeTag, TextDiff};
+
+/// 每段差异前后保留的相同行数
+const CONTEXT_LINES: usize = 3;
+
+/// 差异中的一行
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct DiffLine {
+    /// equal、insert 或 delete
+    pub op: &'static str,
+    /// 在旧版本中的行号（从 1 开始），新增的行没有
+    pub old_line: Option<usize>,
+    /// 在新版本中的行号（从 1 开始），删除的行没有
+    pub new_line: Option<usize>,
+    /// 行内容（不含换行符）
+    pub text: String,
+}
+
+/// 一段连续的差异及其上下文
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct DiffHunk {
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+/// 两段文本的差异
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct LineDiff {
+    /// 新增的行数
+    pub insertions: usize,
+    /// 删除的行数
+    pub deletions: usize,
+    pub hunks: Vec<DiffHunk>,
+}
+
+/// 按行比较两段文本
+/// ---
+/// @parameter  old     &str
+/// @parameter  new     &str
+/// @return     LineDiff    文本相同时 hunks 为空
+/// ---
+pub fn line_diff(old: &str, new: &str) -> LineDiff {
+    let diff = TextDiff::from_lines(old, new);
+    let mut insertions = 0;
+    let mut deletions = 0;
+    let mut hunks = Vec::new();
+    for group in diff.grouped_ops(CONTEXT_LINES) {
+        let (first, last) = match (group.first(), group.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => continue,
+        };
+        let mut lines = Vec::new();
+        for op in &group {
+            for change in diff.iter_changes(op) {
+                let op = match change.tag() {
+                    ChangeTag::Equal => "equal",
+                    ChangeTag::Insert => {
+                        insertions += 1;
+                        "insert"
+                    }
+                    ChangeTag::Delete => {
+                        deletions += 1;
+                        "delete"
+                    }
+                };
+                lines.push(DiffLine {
+                    op,
+                    old_line: change.old_index().map(|i| i + 1),
+                    new_line: change.new_index().map(|i| i + 1),
+                    text: change.value().trim_end_matches(['\r', '\n']).to_string(),
+                });
+            }
+        }
+        hunks.push(DiffHunk {
+            old_start: first.old_range().start + 1,
+            old_lines: last.old_range().end - first.old_range().start,
+            new_start: first.new_range().start + 1,
+            new_lines: last.new_range().end - first.new_range().start,
+            lines,
+        });
+    }
+    LineDiff {
+        insertions,
+        deletions,
+        hunks,
+    }
+}
@@ -0,0 +1,148 @@
+// 代码块语法高亮：输出带 `hl-` 前缀 class 的 span，颜色由 `/api/highlight.css` 提供，页面不需要 js
+use std::fmt::Write;
+
+use rocket::figment::Figment;
+use rocket::serde::Deserialize;
+use syntect::highlighting::ThemeSet;
+use syntect::html::{css_for_theme_with_class_style, line_tokens_to_classed_spans, ClassStyle};
+use syntect::parsing::{ParseState, ScopeStack, SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
+
+/// 高亮输出的 class 前缀，HTML 过滤时只放行带此前缀的 class
+pub const CLASS_PREFIX: &str = "hl-";
+
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed {
+    prefix: CLASS_PREFIX,
+};
+
+/// 默认主题
+const DEFAULT_THEME: &str = "InspiredGitHub";
+
+/// 代码高亮参数（Rocket.toml 中的 highlight 配置）
+#[derive(Deserialize, Debug, Clone)]
+#[serde(crate = "rocket::serde", default)]
+pub struct HighlightConfig {
+    /// syntect 内置主题名，如 `InspiredGitHub`、`base16-ocean.dark`、`Solarized (light)`
+    pub theme: String,
+    /// 是否显示行号
+    pub line_numbers: bool,
+}
+
+impl Default for HighlightConfig {
+    fn default() -> Self {
+        HighlightConfig {
+            theme: DEFAULT_THEME.to_string(),
+            line_numbers: false,
+        }
+    }
+}
+
+impl HighlightConfig {
+    /// 读取 Rocket 配置中的 `highlight` 配置段，未配置时使用默认值，主题不存在时返回错误
+    pub fn from_figment(figment: &Figment) -> Result<HighlightConfig, String> {
+        let config: HighlightConfig = match figment.find_value("highlight") {
+            Ok(_) => figment
+                .extract_inner("highlight")
+                .map_err(|e| e.to_string())?,
+            Err(_) => HighlightConfig::default(),
+        };
+        let themes = ThemeSet::load_defaults().themes;
+        if !themes.contains_key(&config.theme) {
+            let names: Vec<&str> = themes.keys().map(String::as_str).collect();
+            return Err(format!(
+                "unknown theme `{}`, expected one of: {}",
+                config.theme,
+                names.join(", ")
+            ));
+        }
+        Ok(config)
+    }
+}
+
+lazy_static! {
+    // 从 Rocket 配置中读取高亮参数（启动时已校验）
+    static ref CONFIG: HighlightConfig = HighlightConfig::from_figment(&rocket::Config::figment())
+        .expect("invalid highlight config");
+    // 内置的语法定义
+    static ref SYNTAXES: SyntaxSet = SyntaxSet::load_defaults_newlines();
+    // 主题对应的样式表
+    static ref CSS: String = {
+        let themes = ThemeSet::load_defaults();
+        let theme = &themes.themes[&CONFIG.theme];
+        let mut css = css_for_theme_with_class_style(theme, CLASS_STYLE).unwrap_or_default();
+        // 行号不随代码复制，也不继承所在 span 的样式
+        css.push_str(concat!(
+            ".hl-ln {\n",
+            " display: inline-block;\n",
+            " min-width: 2em;\n",
+            " margin-right: 1em;\n",
+            " text-align: right;\n",
+            " opacity: 0.5;\n",
+            " font-style: normal;\n",
+            " font-weight: normal;\n",
+            " user-select: none;\n",
+            "}\n",
+        ));
+        css
+    };
+}
+
+/// 与高亮输出配套的样式表
+pub fn css() -> &'static str {
+    &CSS
+}
+
+/// 按代码块标注的语言查找语法，支持语言名（如 `rust`）及扩展名（如 `rs`），找不到时按纯文本处理
+fn find_syntax(lang: Option<&str>) -> &'static SyntaxReference {
+    lang.and_then(|lang| {
+        SYNTAXES
+            .find_syntax_by_token(lang)
+            .or_else(|| SYNTAXES.find_syntax_by_extension(lang))
+    })
+    .unwrap_or_else(|| SYNTAXES.find_syntax_plain_text())
+}
+
+/// 高亮一个代码块，返回 `<pre class="hl-code"><code>...</code></pre>`
+/// ---
+/// @parameter  code    &str
+/// @parameter  lang    Option<&str>    代码块标注的语言
+/// @return     String
+/// ---
+pub fn highlight(code: &str, lang: Option<&str>) -> String {
+    let mut html = String::with_capacity(code.len() * 4);
+    html.push_str(r#"<pre class="hl-code"><code>"#);
+    match classed_spans(code, find_syntax(lang)) {
+        Some(spans) => html.push_str(&spans),
+        // 语法定义解析失败时输出转义后的原文
+        None => html.push_str(
+            &code
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;"),
+        ),
+    }
+    html.push_str("</code></pre>\n");
+    html
+}
+
+/// 逐行解析代码，输出带 class 的 span，开启行号时每行前插入行号
+fn classed_spans(code: &str, syntax: &SyntaxReference) -> Option<String> {
+    let mut state = ParseState::new(syntax);
+    let mut stack = ScopeStack::new();
+    let mut html = String::new();
+    let mut open_spans = 0;
+    for (i, line) in LinesWithEndings::from(code).enumerate() {
+        if CONFIG.line_numbers {
+            let _ = write!(html, r#"<span class="hl-ln">{}</span>"#, i + 1);
+        }
+        let ops = state.parse_line(line, &SYNTAXES).ok()?;
+        let (spans, delta) =
+            line_tokens_to_classed_spans(line, &ops, CLASS_STYLE, &mut stack).ok()?;
+        html.push_str(&spans);
+        open_spans += delta;
+    }
+    for _ in 0..open_spans {
+        html.push_str("</span>");
+    }
+    Some(html)
+}
@@ -0,0 +1,281 @@
+// jwt 签名密钥环：从 Rocket 配置或密钥文件加载，支持多密钥（kid）轮换
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::Local;
+use jwt_simple::prelude::{HS256Key, JWTClaims, MACLike, Token, VerificationOptions};
+use rocket::figment::Figment;
+use rocket::serde::json::serde_json;
+use rocket::serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::util::crypto::random_token;
+
+/// HS256 密钥的最小长度（字节）
+const MIN_SECRET_LEN: usize = 32;
+
+/// 全局密钥环，启动时由 `init` 设置
+static KEYRING: OnceLock<KeyRing> = OnceLock::new();
+
+/// 单个密钥（配置项与密钥文件共用）
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct KeyEntry {
+    /// 密钥 id，写入 jwt 头部的 `kid`
+    pub kid: String,
+    /// base64 编码的密钥
+    pub secret: String,
+    /// 创建时间
+    #[serde(default)]
+    pub created_at: i64,
+}
+
+/// 密钥文件内容
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+#[serde(crate = "rocket::serde")]
+pub struct KeyFile {
+    /// 当前用于签名的密钥 id
+    pub active: String,
+    /// 全部仍可用于校验的密钥
+    pub keys: Vec<KeyEntry>,
+}
+
+/// Rocket.toml 中的 `jwt` 配置段
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(crate = "rocket::serde", default)]
+pub struct JwtConfig {
+    /// 密钥文件路径，文件不存在时自动生成
+    pub key_file: Option<PathBuf>,
+    /// 直接写在配置中的密钥
+    pub keys: Vec<KeyEntry>,
+    /// 用于签名的密钥 id，优先于密钥文件中的 `active`
+    pub active_kid: Option<String>,
+}
+
+/// 密钥加载错误
+#[derive(Debug)]
+pub enum KeyError {
+    Config(String),
+    Io(PathBuf, std::io::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyError::Config(e) => write!(f, "invalid jwt config: {}", e),
+            KeyError::Io(path, e) => write!(f, "jwt key file {}: {}", path.display(), e),
+            KeyError::Invalid(e) => write!(f, "invalid jwt key: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for KeyError {}
+
+/// 密钥环
+pub struct KeyRing {
+    active: String,
+    keys: HashMap<String, HS256Key>,
+}
+
+/// 写入仅所有者可读写的文件：创建时即为 0600，已存在的文件先收紧权限再写入
+pub fn write_private(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    file.write_all(contents)
+}
+
+impl KeyFile {
+    /// 读取密钥文件
+    pub fn read(path: &Path) -> Result<KeyFile, KeyError> {
+        let raw = fs::read_to_string(path).map_err(|e| KeyError::Io(path.to_path_buf(), e))?;
+        serde_json::from_str(&raw).map_err(|e| KeyError::Invalid(e.to_string()))
+    }
+
+    /// 写入密钥文件（仅所有者可读写）
+    pub fn write(&self, path: &Path) -> Result<(), KeyError> {
+        let raw =
+            serde_json::to_string_pretty(self).map_err(|e| KeyError::Invalid(e.to_string()))?;
+        write_private(path, raw.as_bytes()).map_err(|e| KeyError::Io(path.to_path_buf(), e))
+    }
+
+    /// 生成新密钥并设为签名密钥，旧密钥保留用于校验已签发的 token
+    pub fn rotate(&mut self) -> &KeyEntry {
+        let entry = KeyEntry::generate();
+        self.active = entry.kid.clone();
+        self.keys.push(entry);
+        &self.keys[self.keys.len() - 1]
+    }
+
+    /// 生成只包含一个新密钥的密钥文件
+    pub fn generate() -> KeyFile {
+        let entry = KeyEntry::generate();
+        KeyFile {
+            active: entry.kid.clone(),
+            keys: vec![entry],
+        }
+    }
+}
+
+impl JwtConfig {
+    /// 读取 Rocket 配置中的 `jwt` 配置段
+    pub fn from_figment(figment: &Figment) -> Result<JwtConfig, KeyError> {
+        match figment.find_value("jwt") {
+            Ok(_) => figment
+                .extract_inner("jwt")
+                .map_err(|e| KeyError::Config(e.to_string())),
+            Err(_) => Ok(JwtConfig::default()),
+        }
+    }
+}
+
+impl KeyEntry {
+    /// 生成新的随机密钥，kid 取当前时间加随机后缀（同一秒内生成的 kid 也不重复）
+    pub fn generate() -> KeyEntry {
+        let now = Local::now();
+        KeyEntry {
+            kid: format!("{}-{}", now.format("%Y%m%d%H%M%S"), random_token(6)),
+            secret: BASE64.encode(HS256Key::generate().to_bytes()),
+            created_at: now.timestamp(),
+        }
+    }
+}
+
+impl KeyRing {
+    /// 从 Rocket 配置加载密钥环
+    /// ---
+    /// @parameter  figment     &Figment
+    /// @return     Result<KeyRing, KeyError>
+    /// ---
+    pub fn from_figment(figment: &Figment) -> Result<KeyRing, KeyError> {
+        let config = JwtConfig::from_figment(figment)?;
+
+        let mut entries = config.keys.clone();
+        let mut active = config.active_kid.clone();
+
+        if let Some(path) = &config.key_file {
+            let file = if path.exists() {
+                KeyFile::read(path)?
+            } else {
+                let file = KeyFile::generate();
+                file.write(path)?;
+                log::warn!("jwt 密钥文件 {} 不存在，已生成新密钥", path.display());
+                file
+            };
+            if active.is_none() {
+                active = Some(file.active.clone());
+            }
+            entries.extend(file.keys);
+        }
+
+        if entries.is_empty() {
+            // 未配置任何密钥：使用临时密钥，重启后已签发的 token 全部失效
+            log::warn!("未配置 jwt 签名密钥，使用临时密钥");
+            let entry = KeyEntry::generate();
+            active = Some(entry.kid.clone());
+            entries.push(entry);
+        }
+
+        let active = match active {
+            Some(a) => a,
+            None if entries.len() == 1 => entries[0].kid.clone(),
+            None => return Err(KeyError::Config("active_kid is required".to_string())),
+        };
+
+        let mut keys = HashMap::new();
+        for entry in entries {
+            // 配置与密钥文件中的 kid 重复时无法确定用哪个密钥校验
+            if keys.contains_key(&entry.kid) {
+                return Err(KeyError::Config(format!("duplicate key id {}", entry.kid)));
+            }
+            let raw = BASE64
+                .decode(entry.secret.trim())
+                .map_err(|e| KeyError::Invalid(format!("{}: {}", entry.kid, e)))?;
+            if raw.len() < MIN_SECRET_LEN {
+                return Err(KeyError::Invalid(format!(
+                    "{}: secret must be at least {} bytes",
+                    entry.kid, MIN_SECRET_LEN
+                )));
+            }
+            let key = HS256Key::from_bytes(&raw).with_key_id(&entry.kid);
+            keys.insert(entry.kid, key);
+        }
+
+        if !keys.contains_key(&active) {
+            return Err(KeyError::Config(format!("active key {} not found", active)));
+        }
+        Ok(KeyRing { active, keys })
+    }
+
+    /// 使用当前密钥签名
+    pub fn sign<C: Serialize + DeserializeOwned>(
+        &self,
+        claims: JWTClaims<C>,
+    ) -> Result<String, jwt_simple::Error> {
+        self.keys[&self.active].authenticate(claims)
+    }
+
+    /// 按 token 头部的 kid 选择密钥校验
+    pub fn verify<C: Serialize + DeserializeOwned>(
+        &self,
+        token: &str,
+    ) -> Result<JWTClaims<C>, jwt_simple::Error> {
+        let metadata = Token::decode_metadata(token)?;
+        let kid = metadata
+            .key_id()
+            .ok_or_else(|| jwt_simple::Error::msg("missing kid"))?;
+        let key = self
+            .keys
+            .get(kid)
+            .ok_or_else(|| jwt_simple::Error::msg("unknown kid"))?;
+        let options = VerificationOptions {
+            required_key_id: Some(kid.to_string()),
+            ..Default::default()
+        };
+        key.verify_token::<C>(token, Some(options))
+    }
+}
+
+/// 设置全局密钥环
+pub fn init(ring: KeyRing) {
+    let _ = KEYRING.set(ring);
+}
+
+/// 获取全局密钥环
+pub fn keyring() -> &'static KeyRing {
+    KEYRING.get().expect("jwt keyring is not initialized")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_kids_are_unique() {
+        assert_ne!(KeyEntry::generate().kid, KeyEntry::generate().kid);
+    }
+
+    #[test]
+    fn duplicate_kids_are_rejected() {
+        let entry = KeyEntry::generate();
+        let figment = Figment::new()
+            .merge(("jwt.active_kid", entry.kid.clone()))
+            .merge(("jwt.keys", vec![entry.clone(), entry]));
+        match KeyRing::from_figment(&figment) {
+            Err(KeyError::Config(e)) => assert!(e.starts_with("duplicate key id")),
+            _ => panic!("duplicate kid accepted"),
+        }
+    }
+}
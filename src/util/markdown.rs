@@ -0,0 +1,425 @@
+// Markdown 渲染：文章正文（GFM 表格、任务列表、脚注、删除线）转为 HTML，标题带锚点 id，代码块在服务端高亮，输出经白名单过滤
+// 同时生成标题目录、字数、预计阅读时间及摘要
+use std::collections::HashSet;
+
+use ammonia::Builder;
+use pulldown_cmark::{html, CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use rbatis::Error;
+use rocket::serde::de::{self, Deserializer};
+use rocket::serde::json::serde_json;
+use rocket::serde::ser::Serializer;
+use rocket::serde::{Deserialize, Serialize};
+use rocket::tokio::task;
+
+use crate::util::highlight::{self, CLASS_PREFIX};
+use crate::util::slug::slugify;
+
+lazy_static! {
+    // HTML 白名单：在 ammonia 默认规则（去除脚本、事件属性及危险链接）的基础上放行渲染结果需要的标签及属性
+    static ref SANITIZER: Builder<'static> = {
+        let mut builder = Builder::default();
+        builder
+            // 标题及脚注的锚点
+            .add_tag_attributes("h1", &["id"])
+            .add_tag_attributes("h2", &["id"])
+            .add_tag_attributes("h3", &["id"])
+            .add_tag_attributes("h4", &["id"])
+            .add_tag_attributes("h5", &["id"])
+            .add_tag_attributes("h6", &["id"])
+            .add_tag_attributes("div", &["id"])
+            .add_allowed_classes("div", &["footnote-definition"])
+            .add_allowed_classes("sup", &["footnote-reference", "footnote-definition-label"])
+            // 正文中的 id 一律加前缀，避免覆盖页面上的其他元素（DOM clobbering）
+            .id_prefix(Some(ID_PREFIX))
+            // 任务列表的复选框，只允许只读的 checkbox
+            .add_tags(&["input"])
+            .add_tag_attributes("input", &["checked"])
+            .set_tag_attribute_value("input", "type", "checkbox")
+            .set_tag_attribute_value("input", "disabled", "")
+            // 表格列的对齐方式
+            .add_tag_attributes("th", &["style"])
+            .add_tag_attributes("td", &["style"])
+            .filter_style_properties(["text-align"].into_iter().collect())
+            // 代码高亮的 class，只保留带 `hl-` 前缀的
+            .add_tag_attributes("pre", &["class"])
+            .add_tag_attributes("span", &["class"])
+            .attribute_filter(|element, attribute, value| match (element, attribute) {
+                ("pre" | "span", "class") => {
+                    let classes: Vec<&str> = value
+                        .split_ascii_whitespace()
+                        .filter(|c| c.starts_with(CLASS_PREFIX))
+                        .collect();
+                    Some(classes.join(" ").into())
+                }
+                // 页内链接（脚注及指向标题的链接）指向加了前缀的 id
+                ("a", "href") => match value.strip_prefix('#') {
+                    Some(id) if !id.starts_with(ID_PREFIX) => {
+                        Some(format!("#{}{}", ID_PREFIX, id).into())
+                    }
+                    _ => Some(value.into()),
+                },
+                _ => Some(value.into()),
+            });
+        builder
+    };
+}
+
+/// 正文中 id 的前缀
+pub const ID_PREFIX: &str = "user-content-";
+
+/// 每分钟阅读的单词数（英文等以空格分词的文字）
+const WORDS_PER_MINUTE: u32 = 200;
+
+/// 每分钟阅读的字数（中文、日文）
+const CJK_CHARS_PER_MINUTE: u32 = 300;
+
+/// 自动摘要的最大字符数
+const EXCERPT_CHARS: usize = 200;
+
+/// 目录中的一个标题
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(crate = "rocket::serde")]
+pub struct TocEntry {
+    /// 标题级别 1-6
+    pub level: u8,
+    /// 锚点 id（带 `user-content-` 前缀），与 content_html 中标题的 id 相同
+    pub id: String,
+    pub text: String,
+    /// 下一级标题
+    #[serde(default)]
+    pub children: Vec<TocEntry>,
+}
+
+/// 文章目录，按标题级别嵌套
+/// 数据库中保存为 JSON 字符串，接口及导出文件中为数组
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Toc(pub Vec<TocEntry>);
+
+impl Toc {
+    /// 保存到数据库的 JSON 字符串
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.0).unwrap_or_default()
+    }
+}
+
+impl Serialize for Toc {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Toc {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(crate = "rocket::serde", untagged)]
+        enum Repr {
+            Json(String),
+            Entries(Vec<TocEntry>),
+        }
+        match Repr::deserialize(deserializer)? {
+            Repr::Json(json) => serde_json::from_str(&json)
+                .map(Toc)
+                .map_err(de::Error::custom),
+            Repr::Entries(entries) => Ok(Toc(entries)),
+        }
+    }
+}
+
+/// 渲染结果
+#[derive(Clone, Debug, Default)]
+pub struct Rendered {
+    /// 过滤后的 HTML
+    pub html: String,
+    pub toc: Toc,
+    /// 字数：中文、日文每个字计 1，其他文字按单词计
+    pub word_count: u32,
+    /// 预计阅读时间（分钟），有内容时至少 1 分钟
+    pub reading_minutes: u32,
+    /// 由正文生成的纯文本摘要
+    pub excerpt: String,
+}
+
+/// 渲染时启用的 Markdown 扩展
+fn options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+}
+
+/// 渲染 Markdown 正文：过滤后的 HTML、目录、字数、阅读时间及摘要
+/// ---
+/// @parameter  source  &str
+/// @return     Rendered
+/// ---
+pub fn render(source: &str) -> Rendered {
+    let (events, headings) = with_heading_ids(Parser::new_ext(source, options()).collect());
+    let counter = count_words(&events);
+    let excerpt = excerpt(&events);
+    let events = with_highlighted_code(events);
+    let mut raw = String::with_capacity(source.len() * 3 / 2);
+    html::push_html(&mut raw, events.into_iter());
+    Rendered {
+        html: SANITIZER.clean(&raw).to_string(),
+        toc: Toc(nest(headings)),
+        word_count: counter.words + counter.cjk_chars,
+        reading_minutes: counter.minutes(),
+        excerpt,
+    }
+}
+
+/// 在阻塞线程池中渲染：解析、过滤及代码高亮都是同步计算，长文章会长时间占用异步任务线程
+/// ---
+/// @parameter  source  &str
+/// @return     Result<Rendered, Error>
+/// ---
+pub async fn render_blocking(source: &str) -> Result<Rendered, Error> {
+    let source = source.to_string();
+    task::spawn_blocking(move || render(&source))
+        .await
+        .map_err(|e| Error::E(e.to_string()))
+}
+
+/// 为每个标题设置锚点 id：由标题文字生成并加上 `user-content-` 前缀，重复时追加 `-2`、`-3`……
+/// 同时按出现顺序返回全部标题
+fn with_heading_ids(mut events: Vec<Event<'_>>) -> (Vec<Event<'_>>, Vec<TocEntry>) {
+    let mut used = HashSet::new();
+    let mut headings = Vec::new();
+    let mut i = 0;
+    while i < events.len() {
+        if !matches!(events[i], Event::Start(Tag::Heading { .. })) {
+            i += 1;
+            continue;
+        }
+        let mut text = String::new();
+        let mut end = i + 1;
+        while end < events.len() && !matches!(events[end], Event::End(TagEnd::Heading(_))) {
+            if let Event::Text(t) | Event::Code(t) = &events[end] {
+                text.push_str(t);
+            }
+            end += 1;
+        }
+        let anchor = format!("{}{}", ID_PREFIX, unique_anchor(&text, &mut used));
+        if let Event::Start(Tag::Heading { level, id, .. }) = &mut events[i] {
+            *id = Some(anchor.clone().into());
+            headings.push(TocEntry {
+                level: *level as u8,
+                id: anchor,
+                text: text.trim().to_string(),
+                children: Vec::new(),
+            });
+        }
+        i = end;
+    }
+    (events, headings)
+}
+
+/// 按级别嵌套标题：每个标题放在它前面最近的更高级标题下
+fn nest(headings: Vec<TocEntry>) -> Vec<TocEntry> {
+    fn insert(siblings: &mut Vec<TocEntry>, entry: TocEntry) {
+        match siblings.last_mut() {
+            Some(last) if last.level < entry.level => insert(&mut last.children, entry),
+            _ => siblings.push(entry),
+        }
+    }
+    let mut toc = Vec::new();
+    for entry in headings {
+        insert(&mut toc, entry);
+    }
+    toc
+}
+
+/// 是否为按字计数的文字（汉字、日文假名）；韩文以空格分词，按单词计
+pub fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'      // 平假名、片假名
+        | '\u{3400}'..='\u{4DBF}'    // 汉字扩展 A
+        | '\u{4E00}'..='\u{9FFF}'    // 汉字
+        | '\u{F900}'..='\u{FAFF}'    // 兼容汉字
+        | '\u{20000}'..='\u{2FA1F}'  // 汉字扩展 B 及以后
+    )
+}
+
+/// 是否为中文、日文标点（全角标点）
+fn is_cjk_punctuation(c: char) -> bool {
+    matches!(c, '\u{3000}'..='\u{303F}' | '\u{FF00}'..='\u{FFEF}')
+}
+
+/// 正文字数统计
+#[derive(Default)]
+struct WordCounter {
+    /// 以空格分词的单词数
+    words: u32,
+    /// 汉字及假名数
+    cjk_chars: u32,
+}
+
+impl WordCounter {
+    fn add(&mut self, text: &str) {
+        let mut in_word = false;
+        for c in text.chars() {
+            if is_cjk(c) {
+                self.cjk_chars += 1;
+                in_word = false;
+            } else if c.is_alphanumeric() || (in_word && (c == '\'' || c == '’')) {
+                if !in_word {
+                    self.words += 1;
+                }
+                in_word = true;
+            } else {
+                in_word = false;
+            }
+        }
+    }
+
+    /// 预计阅读时间（分钟），向上取整
+    fn minutes(&self) -> u32 {
+        if self.words == 0 && self.cjk_chars == 0 {
+            return 0;
+        }
+        let seconds =
+            self.words * 60 / WORDS_PER_MINUTE + self.cjk_chars * 60 / CJK_CHARS_PER_MINUTE;
+        seconds.div_ceil(60).max(1)
+    }
+}
+
+/// 统计正文（包括代码）的字数，不包括 HTML 标签
+fn count_words(events: &[Event<'_>]) -> WordCounter {
+    let mut counter = WordCounter::default();
+    for event in events {
+        if let Event::Text(text) | Event::Code(text) = event {
+            counter.add(text);
+        }
+    }
+    counter
+}
+
+/// 生成纯文本摘要：有 `<!-- more -->` 时取其之前的全部内容，
+/// 否则取前 200 个字符，尽量在句末截断，其次在单词之间截断并追加省略号
+/// 不包括代码块、图片、脚注及 HTML
+fn excerpt(events: &[Event<'_>]) -> String {
+    let mut text = String::new();
+    // 处于代码块、图片或脚注内的层数
+    let mut skipped = 0;
+    for event in events {
+        match event {
+            Event::Html(html) | Event::InlineHtml(html) if is_more_marker(html) => {
+                return collapse_whitespace(&text);
+            }
+            Event::Start(Tag::CodeBlock(_) | Tag::Image { .. } | Tag::FootnoteDefinition(_)) => {
+                skipped += 1
+            }
+            Event::End(TagEnd::CodeBlock | TagEnd::Image | TagEnd::FootnoteDefinition) => {
+                skipped -= 1
+            }
+            Event::Text(t) | Event::Code(t) if skipped == 0 => text.push_str(t),
+            // 块之间及换行处用空格分隔
+            Event::SoftBreak
+            | Event::HardBreak
+            | Event::End(
+                TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::Item | TagEnd::TableCell,
+            ) => text.push(' '),
+            _ => {}
+        }
+    }
+    truncate(&collapse_whitespace(&text), EXCERPT_CHARS)
+}
+
+/// 是否为摘要分隔标记 `<!-- more -->`（不区分大小写及空格）
+fn is_more_marker(html: &str) -> bool {
+    html.trim()
+        .strip_prefix("<!--")
+        .and_then(|s| s.strip_suffix("-->"))
+        .is_some_and(|s| s.trim().eq_ignore_ascii_case("more"))
+}
+
+/// 合并连续空白为一个空格，中文、日文之间的换行不保留空格
+fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for word in text.split_whitespace() {
+        let joined = match (out.chars().last(), word.chars().next()) {
+            (Some(a), Some(b)) => {
+                (is_cjk(a) || is_cjk_punctuation(a)) && (is_cjk(b) || is_cjk_punctuation(b))
+            }
+            _ => true,
+        };
+        if !joined {
+            out.push(' ');
+        }
+        out.push_str(word);
+    }
+    out
+}
+
+/// 截断到最多 max 个字符：优先在后半段的句末截断，其次在单词之间（中文、日文每个字之间均可）截断
+fn truncate(text: &str, max: usize) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() <= max {
+        return text.to_string();
+    }
+    // 第 i 个字符之前是否为句末
+    let sentence_end = |i: usize| match chars[i - 1] {
+        '。' | '！' | '？' | '…' => true,
+        '.' | '!' | '?' => chars[i].is_whitespace(),
+        _ => false,
+    };
+    // 第 i 个字符之前是否为单词边界
+    let word_boundary = |i: usize| {
+        let (a, b) = (chars[i - 1], chars[i]);
+        a.is_whitespace() || b.is_whitespace() || is_cjk(a) || is_cjk(b) || is_cjk_punctuation(a)
+    };
+    if let Some(end) = (max / 2..=max).rev().find(|&i| sentence_end(i)) {
+        return chars[..end].iter().collect();
+    }
+    let end = (1..=max).rev().find(|&i| word_boundary(i)).unwrap_or(max);
+    let mut excerpt: String = chars[..end].iter().collect();
+    excerpt.truncate(excerpt.trim_end().len());
+    excerpt.push('…');
+    excerpt
+}
+
+/// 将代码块替换为高亮后的 HTML（代码块标注的第一个单词为语言）
+fn with_highlighted_code(events: Vec<Event<'_>>) -> Vec<Event<'_>> {
+    let mut out = Vec::with_capacity(events.len());
+    // 正在读取的代码块的语言及代码
+    let mut block: Option<(Option<String>, String)> = None;
+    for event in events {
+        match (event, &mut block) {
+            (Event::Start(Tag::CodeBlock(kind)), _) => {
+                let lang = match kind {
+                    CodeBlockKind::Fenced(info) => {
+                        info.split_whitespace().next().map(str::to_string)
+                    }
+                    CodeBlockKind::Indented => None,
+                };
+                block = Some((lang, String::new()));
+            }
+            (Event::Text(text), Some((_, code))) => code.push_str(&text),
+            (Event::End(TagEnd::CodeBlock), _) => {
+                if let Some((lang, code)) = block.take() {
+                    out.push(Event::Html(
+                        highlight::highlight(&code, lang.as_deref()).into(),
+                    ));
+                }
+            }
+            (event, _) => out.push(event),
+        }
+    }
+    out
+}
+
+/// 由标题文字生成未使用的锚点 id，无法转写的标题使用 `section`
+fn unique_anchor(text: &str, used: &mut HashSet<String>) -> String {
+    let mut base = slugify(text);
+    if base.is_empty() {
+        base = "section".to_string();
+    }
+    let mut anchor = base.clone();
+    let mut n = 1;
+    while used.contains(&anchor) {
+        n += 1;
+        anchor = format!("{}-{}", base, n);
+    }
+    used.insert(anchor.clone());
+    anchor
+}
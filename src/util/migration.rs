@@ -0,0 +1,391 @@
+// 数据库迁移：按方言嵌入 migrations/<dialect>/NNNN_name.{up,down}.sql，记录在 schema_migrations 表中
+use std::fmt;
+
+use chrono::Local;
+use rbatis::executor::{Executor, ExecutorMut};
+use rbatis::rbatis::Rbatis;
+use rbson::bson;
+use rocket::serde::Deserialize;
+
+use crate::util::crypto::sha256_hex;
+use crate::util::dialect::{dialect, Dialect};
+
+/// 一个版本的迁移脚本
+#[derive(Debug)]
+pub struct Migration {
+    /// 文件名，如 `0001_initial_schema`
+    pub name: &'static str,
+    pub up: &'static str,
+    pub down: &'static str,
+}
+
+impl Migration {
+    /// 版本号，取自文件名开头的数字
+    pub fn version(&self) -> i64 {
+        self.name
+            .split('_')
+            .next()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_default()
+    }
+
+    /// 升级脚本的校验和，已执行的脚本被修改时用于发现差异
+    pub fn checksum(&self) -> String {
+        sha256_hex(self.up)
+    }
+}
+
+/// 按方言嵌入迁移脚本，文件名须按版本号递增排列
+macro_rules! migrations {
+    ($dialect:literal: $($name:literal),* $(,)?) => {
+        &[$(Migration {
+            name: $name,
+            up: include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/migrations/", $dialect, "/", $name, ".up.sql")),
+            down: include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/migrations/", $dialect, "/", $name, ".down.sql")),
+        }),*]
+    };
+}
+
+const MYSQL: &[Migration] = migrations!("mysql":
+    "0001_initial_schema",
+    "0002_article_status",
+    "0003_article_revision",
+    "0004_slugs",
+    "0005_content_html",
+    "0006_article_outline",
+    "0007_article_excerpt",
+);
+
+const SQLITE: &[Migration] = migrations!("sqlite":
+    "0001_initial_schema",
+    "0002_article_status",
+    "0003_article_revision",
+    "0004_slugs",
+    "0005_content_html",
+    "0006_article_outline",
+    "0007_article_excerpt",
+);
+
+const POSTGRES: &[Migration] = migrations!("postgres":
+    "0001_initial_schema",
+    "0002_article_status",
+    "0003_article_revision",
+    "0004_slugs",
+    "0005_content_html",
+    "0006_article_outline",
+    "0007_article_excerpt",
+);
+
+/// 当前数据库方言对应的全部迁移
+pub fn migrations() -> &'static [Migration] {
+    match dialect() {
+        Dialect::MySql => MYSQL,
+        Dialect::Sqlite => SQLITE,
+        Dialect::Postgres => POSTGRES,
+    }
+}
+
+/// schema_migrations 表中的记录
+#[derive(Deserialize, Clone, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub name: String,
+    pub checksum: String,
+    pub applied_at: i64,
+}
+
+/// 迁移状态
+#[derive(Debug)]
+pub struct MigrationStatus {
+    pub name: &'static str,
+    /// 执行时间，未执行时为 None
+    pub applied_at: Option<i64>,
+}
+
+/// 迁移错误
+#[derive(Debug)]
+pub enum MigrationError {
+    Db(rbatis::Error),
+    /// 已执行的脚本内容与当前版本不一致
+    ChecksumMismatch {
+        version: i64,
+        name: String,
+    },
+    /// 数据库中存在当前程序不认识的版本（通常是用新版本程序迁移后又回退了程序）
+    UnknownVersion {
+        version: i64,
+        name: String,
+    },
+    /// 某个版本执行失败
+    Failed {
+        name: &'static str,
+        error: rbatis::Error,
+    },
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::Db(e) => write!(f, "migration failed: {}", e),
+            MigrationError::ChecksumMismatch { version, name } => write!(
+                f,
+                "migration {} ({}) was modified after it was applied",
+                version, name
+            ),
+            MigrationError::UnknownVersion { version, name } => write!(
+                f,
+                "database has migration {} ({}) which is unknown to this build",
+                version, name
+            ),
+            MigrationError::Failed { name, error } => {
+                write!(f, "migration {} failed: {}", name, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+impl From<rbatis::Error> for MigrationError {
+    fn from(e: rbatis::Error) -> Self {
+        MigrationError::Db(e)
+    }
+}
+
+/// 将脚本拆分为单条语句（忽略 `--` 注释行）
+fn statements(script: &str) -> Vec<String> {
+    let script: String = script
+        .lines()
+        .filter(|l| !l.trim_start().starts_with("--"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    script
+        .split(';')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// 创建 schema_migrations 表并读取已执行的版本
+async fn applied(rb: &Rbatis) -> Result<Vec<AppliedMigration>, MigrationError> {
+    rb.exec(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (version BIGINT NOT NULL PRIMARY KEY, name VARCHAR(255) NOT NULL, checksum VARCHAR(64) NOT NULL, applied_at BIGINT NOT NULL)",
+        vec![],
+    )
+    .await?;
+    let rows = rb
+        .fetch(
+            "SELECT version, name, checksum, applied_at FROM schema_migrations ORDER BY version",
+            vec![],
+        )
+        .await?;
+    Ok(rows)
+}
+
+/// 校验已执行脚本的校验和
+/// ---
+/// @parameter  known       &[Migration]            当前程序的迁移
+/// @parameter  applied     &[AppliedMigration]     数据库中已执行的迁移
+/// @return     Result<(), MigrationError>
+/// ---
+fn verify(known: &[Migration], applied: &[AppliedMigration]) -> Result<(), MigrationError> {
+    for record in applied {
+        match known.iter().find(|m| m.version() == record.version) {
+            Some(m) if m.checksum() == record.checksum => {}
+            Some(_) => {
+                return Err(MigrationError::ChecksumMismatch {
+                    version: record.version,
+                    name: record.name.clone(),
+                })
+            }
+            None => {
+                return Err(MigrationError::UnknownVersion {
+                    version: record.version,
+                    name: record.name.clone(),
+                })
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 在一个事务中执行脚本并更新 schema_migrations（MySQL 的 DDL 会隐式提交，无法整体回滚）
+async fn run(rb: &Rbatis, migration: &'static Migration, up: bool) -> Result<(), MigrationError> {
+    let failed = |error| MigrationError::Failed {
+        name: migration.name,
+        error,
+    };
+    let mut tx = rb.acquire_begin().await?.defer_async(|mut tx1| async move {
+        if !tx1.is_done() {
+            let _ = tx1.rollback().await;
+        }
+    });
+    let script = if up { migration.up } else { migration.down };
+    for sql in statements(script) {
+        tx.exec(&sql, vec![]).await.map_err(failed)?;
+    }
+    if up {
+        tx.exec(
+            "INSERT INTO schema_migrations (version, name, checksum, applied_at) VALUES (?, ?, ?, ?)",
+            vec![
+                bson!(migration.version()),
+                bson!(migration.name),
+                bson!(migration.checksum()),
+                bson!(Local::now().timestamp()),
+            ],
+        )
+        .await
+        .map_err(failed)?;
+    } else {
+        tx.exec(
+            "DELETE FROM schema_migrations WHERE version = ?",
+            vec![bson!(migration.version())],
+        )
+        .await
+        .map_err(failed)?;
+    }
+    tx.commit().await.map_err(failed)?;
+    Ok(())
+}
+
+/// 执行全部未执行的迁移
+/// ---
+/// @parameter  rb      &Rbatis
+/// @return     Result<Vec<&Migration>, MigrationError>    本次执行的迁移
+/// ---
+pub async fn migrate(rb: &Rbatis) -> Result<Vec<&'static Migration>, MigrationError> {
+    let applied = applied(rb).await?;
+    verify(migrations(), &applied)?;
+    let mut done = vec![];
+    for migration in migrations() {
+        if applied.iter().any(|a| a.version == migration.version()) {
+            continue;
+        }
+        run(rb, migration, true).await?;
+        log::info!("已执行数据库迁移 {}", migration.name);
+        done.push(migration);
+    }
+    Ok(done)
+}
+
+/// 回滚最近执行的 steps 个迁移
+/// ---
+/// @parameter  rb      &Rbatis
+/// @parameter  steps   usize
+/// @return     Result<Vec<&Migration>, MigrationError>    本次回滚的迁移
+/// ---
+pub async fn rollback(
+    rb: &Rbatis,
+    steps: usize,
+) -> Result<Vec<&'static Migration>, MigrationError> {
+    let applied = applied(rb).await?;
+    verify(migrations(), &applied)?;
+    let mut done = vec![];
+    for record in applied.iter().rev().take(steps) {
+        // verify 已确认每个已执行的版本都存在
+        let migration = migrations()
+            .iter()
+            .find(|m| m.version() == record.version)
+            .expect("verified migration");
+        run(rb, migration, false).await?;
+        log::info!("已回滚数据库迁移 {}", migration.name);
+        done.push(migration);
+    }
+    Ok(done)
+}
+
+/// 查询每个迁移的执行状态
+/// ---
+/// @parameter  rb      &Rbatis
+/// @return     Result<Vec<MigrationStatus>, MigrationError>
+/// ---
+pub async fn status(rb: &Rbatis) -> Result<Vec<MigrationStatus>, MigrationError> {
+    let applied = applied(rb).await?;
+    verify(migrations(), &applied)?;
+    Ok(migrations()
+        .iter()
+        .map(|m| MigrationStatus {
+            name: m.name,
+            applied_at: applied
+                .iter()
+                .find(|a| a.version == m.version())
+                .map(|a| a.applied_at),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 与 migrations() 相同的迁移对应的执行记录
+    fn applied_all(known: &[Migration]) -> Vec<AppliedMigration> {
+        known
+            .iter()
+            .map(|m| AppliedMigration {
+                version: m.version(),
+                name: m.name.to_string(),
+                checksum: m.checksum(),
+                applied_at: 0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn statements_skip_comments_and_empty_parts() {
+        let script =
+            "-- 文章表\nCREATE TABLE a (id INT);\n\n  -- 索引\nCREATE INDEX i ON a (id);\n;\n";
+        assert_eq!(
+            statements(script),
+            vec!["CREATE TABLE a (id INT)", "CREATE INDEX i ON a (id)"]
+        );
+    }
+
+    #[test]
+    fn every_dialect_has_the_same_versions() {
+        let versions = |known: &[Migration]| known.iter().map(|m| m.version()).collect::<Vec<_>>();
+        let expected: Vec<i64> = (1..=MYSQL.len() as i64).collect();
+        assert_eq!(versions(MYSQL), expected);
+        assert_eq!(versions(SQLITE), expected);
+        assert_eq!(versions(POSTGRES), expected);
+        for m in MYSQL.iter().chain(SQLITE).chain(POSTGRES) {
+            assert!(!statements(m.up).is_empty(), "{} has no statements", m.name);
+        }
+    }
+
+    #[test]
+    fn verify_accepts_applied_prefix() {
+        let applied = applied_all(SQLITE);
+        assert!(verify(SQLITE, &applied).is_ok());
+        assert!(verify(SQLITE, &applied[..2]).is_ok());
+        assert!(verify(SQLITE, &[]).is_ok());
+    }
+
+    #[test]
+    fn verify_rejects_modified_scripts() {
+        let mut applied = applied_all(SQLITE);
+        applied[1].checksum = sha256_hex("CREATE TABLE edited (id INT)");
+        match verify(SQLITE, &applied) {
+            Err(MigrationError::ChecksumMismatch { version, name }) => {
+                assert_eq!((version, name.as_str()), (2, SQLITE[1].name));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn verify_rejects_unknown_versions() {
+        let mut applied = applied_all(SQLITE);
+        applied.push(AppliedMigration {
+            version: 9999,
+            name: "9999_from_the_future".to_string(),
+            checksum: String::new(),
+            applied_at: 0,
+        });
+        assert!(matches!(
+            verify(SQLITE, &applied),
+            Err(MigrationError::UnknownVersion { version: 9999, .. })
+        ));
+    }
+}
@@ -1 +1,17 @@
+pub mod crypto;
+pub mod database;
+pub mod dialect;
+pub mod diff;
+pub mod highlight;
+pub mod keyring;
+pub mod markdown;
+pub mod migration;
+pub mod password;
+pub mod permission;
+pub mod scheduler;
+pub mod slug;
+pub mod throttle;
 pub mod token;
+pub mod totp;
+pub mod transaction;
+pub mod validation;
@@ -0,0 +1,212 @@
+// 密码哈希（Argon2id）及旧版 md5 密码校验
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{self, PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use md5::{Digest, Md5};
+use rbatis::Error;
+use rocket::figment::Figment;
+use rocket::serde::Deserialize;
+use rocket::tokio::task;
+
+use crate::util::crypto::constant_time_eq;
+
+/// Argon2id 哈希参数，对应 Rocket.toml 中的 `password_hash` 配置段
+#[derive(Deserialize, Clone, Debug)]
+#[serde(crate = "rocket::serde", default)]
+pub struct HashConfig {
+    /// 内存开销（KiB）
+    pub memory_kib: u32,
+    /// 迭代次数
+    pub iterations: u32,
+    /// 并行度
+    pub parallelism: u32,
+}
+
+impl Default for HashConfig {
+    fn default() -> Self {
+        // OWASP 推荐的 Argon2id 最低配置
+        HashConfig {
+            memory_kib: 19456,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+impl HashConfig {
+    /// 读取 Rocket 配置中的 `password_hash` 配置段，未配置时使用默认值，参数无效时返回错误
+    pub fn from_figment(figment: &Figment) -> Result<HashConfig, String> {
+        let config: HashConfig = match figment.find_value("password_hash") {
+            Ok(_) => figment
+                .extract_inner("password_hash")
+                .map_err(|e| e.to_string())?,
+            Err(_) => HashConfig::default(),
+        };
+        config.params().map_err(|e| e.to_string())?;
+        Ok(config)
+    }
+
+    /// 转为 Argon2 参数
+    fn params(&self) -> Result<Params, password_hash::Error> {
+        Ok(Params::new(
+            self.memory_kib,
+            self.iterations,
+            self.parallelism,
+            None,
+        )?)
+    }
+}
+
+lazy_static! {
+    // 从 Rocket 配置中读取哈希参数（启动时已校验）
+    static ref CONFIG: HashConfig = HashConfig::from_figment(&rocket::Config::figment())
+        .expect("invalid password_hash config");
+    // 用户不存在时用于对齐校验耗时的哈希
+    static ref DUMMY_HASH: String = hash("lsp-blog").unwrap_or_default();
+}
+
+/// 密码校验结果
+#[derive(Debug, PartialEq, Eq)]
+pub enum Verified {
+    /// 密码正确
+    Valid,
+    /// 密码正确，但存储的哈希为旧格式或参数已过时，需要重新哈希
+    NeedsRehash,
+    /// 密码错误
+    Invalid,
+}
+
+/// 按当前配置构造 Argon2id 哈希器
+fn hasher() -> Result<Argon2<'static>, password_hash::Error> {
+    Ok(Argon2::new(
+        Algorithm::Argon2id,
+        Version::V0x13,
+        CONFIG.params()?,
+    ))
+}
+
+/// 生成密码哈希（PHC 格式，随机盐），在阻塞线程池中计算
+/// ---
+/// @parameter  password        &str
+/// @return     Result<String, Error>
+/// ---
+pub async fn hash_password(password: &str) -> Result<String, Error> {
+    let password = password.to_string();
+    task::spawn_blocking(move || hash(&password))
+        .await
+        .map_err(|e| Error::E(e.to_string()))?
+        .map_err(|e| Error::E(e.to_string()))
+}
+
+/// 校验密码，在阻塞线程池中计算
+/// ---
+/// @parameter  password        &str
+/// @parameter  stored          &str        数据库中保存的哈希
+/// @return     Verified
+/// ---
+pub async fn verify_password(password: &str, stored: &str) -> Verified {
+    let (password, stored) = (password.to_string(), stored.to_string());
+    task::spawn_blocking(move || verify(&password, &stored))
+        .await
+        .unwrap_or(Verified::Invalid)
+}
+
+/// 用户不存在时执行一次无意义的校验，避免通过响应时间枚举用户名
+pub async fn dummy_verify(password: &str) {
+    let _ = verify_password(password, &DUMMY_HASH).await;
+}
+
+/// 生成密码哈希（Argon2id 计算耗时，不能直接在异步任务中调用）
+fn hash(password: &str) -> Result<String, password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = hasher()?.hash_password(password.as_bytes(), &salt)?;
+    Ok(hash.to_string())
+}
+
+/// 校验密码（同 hash，在阻塞线程池中调用）
+fn verify(password: &str, stored: &str) -> Verified {
+    if is_legacy_md5(stored) {
+        if constant_time_eq(md5(password).as_bytes(), stored.to_lowercase().as_bytes()) {
+            return Verified::NeedsRehash;
+        }
+        return Verified::Invalid;
+    }
+
+    let parsed = match PasswordHash::new(stored) {
+        Ok(h) => h,
+        Err(_) => return Verified::Invalid,
+    };
+    // 按哈希中记录的算法及参数校验，与当前配置无关
+    if Argon2::default()
+        .verify_password(password.as_bytes(), &parsed)
+        .is_err()
+    {
+        return Verified::Invalid;
+    }
+    if needs_rehash(&parsed) {
+        return Verified::NeedsRehash;
+    }
+    Verified::Valid
+}
+
+/// 哈希算法或参数与当前配置不一致时需要重新哈希
+fn needs_rehash(hash: &PasswordHash) -> bool {
+    if hash.algorithm != Algorithm::Argon2id.ident() {
+        return true;
+    }
+    match Params::try_from(hash) {
+        Ok(p) => {
+            p.m_cost() != CONFIG.memory_kib
+                || p.t_cost() != CONFIG.iterations
+                || p.p_cost() != CONFIG.parallelism
+        }
+        Err(_) => true,
+    }
+}
+
+/// 旧版密码为 32 位十六进制的无盐 md5 摘要
+fn is_legacy_md5(stored: &str) -> bool {
+    stored.len() == 32 && stored.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// md5 摘要函数（仅用于校验旧密码）
+fn md5(input: &str) -> String {
+    let mut md5 = Md5::new();
+    md5.update(input.as_bytes());
+    format!("{:x}", md5.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_params_are_rejected() {
+        let config = HashConfig::from_figment(&Figment::new()).unwrap();
+        assert_eq!(config.memory_kib, HashConfig::default().memory_kib);
+
+        let figment = Figment::new().merge(("password_hash.iterations", 0));
+        assert!(HashConfig::from_figment(&figment).is_err());
+        let figment = Figment::new().merge(("password_hash.iterations", "two"));
+        assert!(HashConfig::from_figment(&figment).is_err());
+        let figment = Figment::new().merge(("password_hash.iterations", 3));
+        assert_eq!(HashConfig::from_figment(&figment).unwrap().iterations, 3);
+    }
+
+    #[test]
+    fn hashes_verify_with_their_own_params() {
+        let params = Params::new(8, 1, 1, None).unwrap();
+        let salt = SaltString::generate(&mut OsRng);
+        let stored = Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password(b"secret", &salt)
+            .unwrap()
+            .to_string();
+        assert_eq!(verify("secret", &stored), Verified::NeedsRehash);
+        assert_eq!(verify("wrong", &stored), Verified::Invalid);
+
+        let stored = hash("secret").unwrap();
+        assert_eq!(verify("secret", &stored), Verified::Valid);
+        assert_eq!(verify(&md5("secret"), &stored), Verified::Invalid);
+        assert_eq!(verify("secret", &md5("secret")), Verified::NeedsRehash);
+    }
+}
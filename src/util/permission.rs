@@ -0,0 +1,169 @@
+// 角色、权限矩阵及权限请求守卫
+use std::marker::PhantomData;
+
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome};
+use rocket::serde::{Deserialize, Serialize};
+
+use crate::util::token::{AuthUser, Token};
+
+/// 用户角色
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
+pub enum Role {
+    /// 管理员：全部权限
+    Admin,
+    /// 编辑：管理分类、标签及全部文章
+    Editor,
+    /// 作者：发布并管理自己的文章
+    Author,
+    /// 投稿者：撰写并修改自己的文章，只能保存为草稿，不能删除
+    Contributor,
+}
+
+/// 权限
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Permission {
+    /// 管理用户
+    ManageUsers,
+    /// 管理分类和标签
+    ManageTaxonomy,
+    /// 新增文章
+    CreateArticle,
+    /// 发布文章（包括定时发布）
+    PublishArticle,
+    /// 修改自己的文章
+    EditOwnArticle,
+    /// 修改任意文章
+    EditAnyArticle,
+    /// 删除自己的文章
+    DeleteOwnArticle,
+    /// 删除任意文章
+    DeleteAnyArticle,
+    /// 指定或修改文章、分类、标签的 slug
+    EditSlug,
+}
+
+impl Role {
+    /// 解析数据库中保存的角色，无法识别时返回 None
+    pub fn parse(role: &str) -> Option<Role> {
+        match role {
+            "admin" => Some(Role::Admin),
+            "editor" => Some(Role::Editor),
+            "author" => Some(Role::Author),
+            "contributor" => Some(Role::Contributor),
+            _ => None,
+        }
+    }
+
+    /// 数据库中保存的角色名
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Editor => "editor",
+            Role::Author => "author",
+            Role::Contributor => "contributor",
+        }
+    }
+
+    /// 权限矩阵
+    pub fn can(&self, permission: Permission) -> bool {
+        use Permission::*;
+        match self {
+            Role::Admin => true,
+            Role::Editor => matches!(
+                permission,
+                ManageTaxonomy
+                    | CreateArticle
+                    | PublishArticle
+                    | EditOwnArticle
+                    | EditAnyArticle
+                    | DeleteOwnArticle
+                    | DeleteAnyArticle
+            ),
+            Role::Author => matches!(
+                permission,
+                CreateArticle | PublishArticle | EditOwnArticle | DeleteOwnArticle
+            ),
+            Role::Contributor => matches!(permission, CreateArticle | EditOwnArticle),
+        }
+    }
+}
+
+impl AuthUser {
+    /// 是否可以修改作者为 author_id 的文章
+    pub fn can_edit_article(&self, author_id: Option<u32>) -> bool {
+        self.role.can(Permission::EditAnyArticle)
+            || (self.role.can(Permission::EditOwnArticle) && author_id == Some(self.id))
+    }
+
+    /// 是否可以删除作者为 author_id 的文章
+    pub fn can_delete_article(&self, author_id: Option<u32>) -> bool {
+        self.role.can(Permission::DeleteAnyArticle)
+            || (self.role.can(Permission::DeleteOwnArticle) && author_id == Some(self.id))
+    }
+}
+
+/// 权限标记类型，用作 `Authorized<P>` 的类型参数
+pub trait Policy {
+    const PERMISSION: Permission;
+}
+
+/// 权限标记类型
+pub mod perm {
+    use super::{Permission, Policy};
+
+    pub struct ManageUsers;
+    pub struct ManageTaxonomy;
+    pub struct CreateArticle;
+    pub struct EditOwnArticle;
+    pub struct DeleteOwnArticle;
+
+    impl Policy for ManageUsers {
+        const PERMISSION: Permission = Permission::ManageUsers;
+    }
+    impl Policy for ManageTaxonomy {
+        const PERMISSION: Permission = Permission::ManageTaxonomy;
+    }
+    impl Policy for CreateArticle {
+        const PERMISSION: Permission = Permission::CreateArticle;
+    }
+    impl Policy for EditOwnArticle {
+        const PERMISSION: Permission = Permission::EditOwnArticle;
+    }
+    impl Policy for DeleteOwnArticle {
+        const PERMISSION: Permission = Permission::DeleteOwnArticle;
+    }
+}
+
+// 权限请求守卫：先校验 token，再检查当前角色是否拥有权限 P
+pub struct Authorized<P: Policy> {
+    pub token: Token,
+    _policy: PhantomData<P>,
+}
+
+impl<P: Policy> Authorized<P> {
+    /// 当前登录用户
+    pub fn user(&self) -> &AuthUser {
+        &self.token.user
+    }
+}
+
+#[rocket::async_trait]
+impl<'r, P: Policy> FromRequest<'r> for Authorized<P> {
+    type Error = ();
+    async fn from_request(request: &'r rocket::Request<'_>) -> Outcome<Self, Self::Error> {
+        let token = match request.guard::<Token>().await {
+            Outcome::Success(t) => t,
+            Outcome::Error(e) => return Outcome::Error(e),
+            Outcome::Forward(s) => return Outcome::Forward(s),
+        };
+        if token.user.role.can(P::PERMISSION) {
+            return Outcome::Success(Authorized {
+                token,
+                _policy: PhantomData,
+            });
+        }
+        Outcome::Error((Status::Forbidden, ()))
+    }
+}
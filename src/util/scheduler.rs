@@ -0,0 +1,75 @@
+// 定时发布：后台任务定期把到达发布时间的定时文章改为已发布
+use std::time::Duration;
+
+use chrono::Local;
+use rocket::fairing::AdHoc;
+use rocket::figment::Figment;
+use rocket::serde::Deserialize;
+
+use crate::repository::Repositories;
+
+/// Rocket.toml 中的 `scheduler` 配置段
+#[derive(Deserialize, Clone, Debug)]
+#[serde(crate = "rocket::serde", default)]
+pub struct SchedulerConfig {
+    /// 检查定时文章的间隔（秒），为 0 时不启动后台任务
+    pub publish_interval_secs: u64,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        SchedulerConfig {
+            publish_interval_secs: 60,
+        }
+    }
+}
+
+impl SchedulerConfig {
+    /// 读取 Rocket 配置中的 `scheduler` 配置段，未配置时使用默认值
+    pub fn from_figment(figment: &Figment) -> Result<SchedulerConfig, String> {
+        match figment.find_value("scheduler") {
+            Ok(_) => figment
+                .extract_inner("scheduler")
+                .map_err(|e| e.to_string()),
+            Err(_) => Ok(SchedulerConfig::default()),
+        }
+    }
+}
+
+/// 发布到达发布时间的定时文章
+/// ---
+/// @parameter  repos       &Repositories
+/// @return     Result<u64, rbatis::Error>     发布的文章数量
+/// ---
+pub async fn publish_due(repos: &Repositories) -> Result<u64, rbatis::Error> {
+    let published = repos.articles.publish_due(Local::now().timestamp()).await?;
+    if published > 0 {
+        log::info!("定时发布了 {} 篇文章", published);
+    }
+    Ok(published)
+}
+
+/// 服务启动后运行定时发布任务
+pub fn fairing(config: SchedulerConfig) -> AdHoc {
+    AdHoc::on_liftoff("Scheduled Publishing", move |rocket| {
+        Box::pin(async move {
+            if config.publish_interval_secs == 0 {
+                return;
+            }
+            let repos = match rocket.state::<Repositories>() {
+                Some(repos) => repos.clone(),
+                None => return,
+            };
+            let period = Duration::from_secs(config.publish_interval_secs);
+            rocket::tokio::spawn(async move {
+                let mut interval = rocket::tokio::time::interval(period);
+                loop {
+                    interval.tick().await;
+                    if let Err(e) = publish_due(&repos).await {
+                        log::error!("定时发布失败: {}", e);
+                    }
+                }
+            });
+        })
+    })
+}
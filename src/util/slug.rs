@@ -0,0 +1,203 @@
+// 网址 slug：由标题或名称生成，中文、日文等转写为拉丁字母，只包含小写字母、数字及连字符
+use rbatis::Error;
+use validator::ValidationError;
+
+use crate::model::slug_history::SlugKind;
+use crate::repository::Repositories;
+use crate::response::error::{AppError, FieldError};
+use crate::util::permission::Permission;
+use crate::util::token::AuthUser;
+use crate::util::validation;
+
+/// slug 的最大长度
+pub const MAX_SLUG_LEN: usize = 80;
+
+/// 生成 slug 时标题部分的最大长度，留出 `-2` 等后缀的长度
+const MAX_BASE_LEN: usize = MAX_SLUG_LEN - 8;
+
+/// 将标题转为 slug，无法转写出字母或数字时返回空字符串
+/// ---
+/// @parameter  text    &str        如 `Rust 入门` 转为 `rust-ru-men`
+/// @return     String
+/// ---
+pub fn slugify(text: &str) -> String {
+    let slug = ::slug::slugify(text);
+    if slug.len() <= MAX_BASE_LEN {
+        return slug;
+    }
+    // 过长时在单词边界截断
+    let cut = &slug[..MAX_BASE_LEN];
+    match cut.rfind('-') {
+        Some(i) if i > 0 => cut[..i].to_string(),
+        _ => cut.to_string(),
+    }
+}
+
+/// 是否为合法的 slug：小写字母、数字及单个连字符，不以连字符开头或结尾
+pub fn is_valid(slug: &str) -> bool {
+    !slug.is_empty()
+        && slug.len() <= MAX_SLUG_LEN
+        && slug
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+        && !slug.starts_with('-')
+        && !slug.ends_with('-')
+        && !slug.contains("--")
+}
+
+/// 校验提交的 slug 格式
+pub fn slug_format(slug: &str) -> Result<(), ValidationError> {
+    if !is_valid(slug) {
+        return Err(validation::invalid(
+            "invalid_format",
+            "Use lowercase letters, digits and single hyphens, at most 80 characters.",
+        ));
+    }
+    Ok(())
+}
+
+/// slug 是否已被其他数据使用（包括其他数据的旧 slug，以免旧地址的重定向失效）
+/// ---
+/// @parameter  repos   &Repositories
+/// @parameter  kind    SlugKind
+/// @parameter  slug    &str
+/// @parameter  id      Option<u32>     更新时为数据自身的 id
+/// @return     Result<bool, Error>
+/// ---
+pub async fn is_taken(
+    repos: &Repositories,
+    kind: SlugKind,
+    slug: &str,
+    id: Option<u32>,
+) -> Result<bool, Error> {
+    let owner = match kind {
+        SlugKind::Article => repos.articles.find_by_slug(slug).await?.and_then(|a| a.id),
+        SlugKind::Category => repos
+            .categories
+            .find_by_slug(slug)
+            .await?
+            .and_then(|c| c.id),
+        SlugKind::Tag => repos.tags.find_by_slug(slug).await?.and_then(|t| t.id),
+    };
+    if owner.is_some() && owner != id {
+        return Ok(true);
+    }
+    let target = repos.slugs.find_target(kind, slug).await?;
+    Ok(target.is_some() && target != id)
+}
+
+/// 由标题生成未被使用的 slug：依次尝试 `slug`、`slug-2`、`slug-3`……
+/// 标题无法转写时以类型名（如 `article`）为基础
+/// ---
+/// @parameter  repos   &Repositories
+/// @parameter  kind    SlugKind
+/// @parameter  text    &str
+/// @parameter  id      Option<u32>     更新时为数据自身的 id
+/// @return     Result<String, Error>
+/// ---
+pub async fn generate(
+    repos: &Repositories,
+    kind: SlugKind,
+    text: &str,
+    id: Option<u32>,
+) -> Result<String, Error> {
+    let mut base = slugify(text);
+    if base.is_empty() {
+        base = kind.as_str().to_string();
+    }
+    let mut candidate = base.clone();
+    let mut n = 1;
+    while is_taken(repos, kind, &candidate, id).await? {
+        n += 1;
+        candidate = format!("{}-{}", base, n);
+    }
+    Ok(candidate)
+}
+
+/// 提交的 slug 已被使用时追加字段错误（格式错误时不再查库）
+/// ---
+/// @parameter  repos       &Repositories
+/// @parameter  kind        SlugKind
+/// @parameter  slug        Option<&str>
+/// @parameter  id          Option<u32>     更新时为数据自身的 id
+/// @parameter  errors      &mut Vec<FieldError>
+/// @return     Result<(), Error>
+/// ---
+pub async fn check_taken(
+    repos: &Repositories,
+    kind: SlugKind,
+    slug: Option<&str>,
+    id: Option<u32>,
+    errors: &mut Vec<FieldError>,
+) -> Result<(), Error> {
+    let slug = match slug {
+        Some(slug) if !validation::has_error(errors, "slug") => slug,
+        _ => return Ok(()),
+    };
+    if is_taken(repos, kind, slug, id).await? {
+        errors.push(FieldError::new("slug", "taken", "Slug is already in use."));
+    }
+    Ok(())
+}
+
+/// 保存时使用的 slug
+/// ---
+/// 提交了 slug 时使用提交的 slug，与原 slug 不同时需要 EditSlug 权限；
+/// 未提交时保留原 slug，没有原 slug 时由标题生成
+/// ---
+/// @parameter  repos       &Repositories
+/// @parameter  kind        SlugKind
+/// @parameter  user        &AuthUser
+/// @parameter  submitted   Option<&str>
+/// @parameter  current     Option<(u32, Option<&str>)>     更新时为数据的 id 及原 slug
+/// @parameter  title       &str
+/// @return     Result<String, AppError>
+/// ---
+pub async fn resolve(
+    repos: &Repositories,
+    kind: SlugKind,
+    user: &AuthUser,
+    submitted: Option<&str>,
+    current: Option<(u32, Option<&str>)>,
+    title: &str,
+) -> Result<String, AppError> {
+    let id = current.map(|(id, _)| id);
+    let current_slug = current.and_then(|(_, slug)| slug);
+    match submitted {
+        Some(slug) if Some(slug) != current_slug && !user.role.can(Permission::EditSlug) => {
+            Err(AppError::forbidden())
+        }
+        Some(slug) => Ok(slug.to_string()),
+        None => match current_slug {
+            Some(slug) => Ok(slug.to_string()),
+            None => Ok(generate(repos, kind, title, id).await?),
+        },
+    }
+}
+
+/// 旧 slug 对应数据的当前 slug，用于重定向（文章只重定向到已发布的文章）
+/// ---
+/// @parameter  repos       &Repositories
+/// @parameter  kind        SlugKind
+/// @parameter  old_slug    &str
+/// @return     Result<Option<String>, Error>
+/// ---
+pub async fn redirect_target(
+    repos: &Repositories,
+    kind: SlugKind,
+    old_slug: &str,
+) -> Result<Option<String>, Error> {
+    let id = match repos.slugs.find_target(kind, old_slug).await? {
+        Some(id) => id,
+        None => return Ok(None),
+    };
+    Ok(match kind {
+        SlugKind::Article => repos
+            .articles
+            .find_published_by_id(id)
+            .await?
+            .and_then(|a| a.slug),
+        SlugKind::Category => repos.categories.find_by_id(id).await?.and_then(|c| c.slug),
+        SlugKind::Tag => repos.tags.find_by_id(id).await?.and_then(|t| t.slug),
+    })
+}
@@ -0,0 +1,435 @@
+// 登录失败限流：按 IP 和用户名统计失败次数，指数退避并在多次失败后临时锁定账号
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+
+use chrono::Local;
+use rocket::figment::Figment;
+use rocket::serde::{Deserialize, Serialize};
+
+/// 限流参数，对应 Rocket.toml 中的 `login_throttle` 配置段
+#[derive(Deserialize, Clone, Debug)]
+#[serde(crate = "rocket::serde", default)]
+pub struct ThrottleConfig {
+    /// 同一用户名连续失败多少次后锁定账号
+    pub max_failures: u32,
+    /// 同一 IP 连续失败多少次后锁定该 IP
+    pub ip_max_failures: u32,
+    /// 退避基础时长（秒），第 n 次失败后需等待 base * 2^(n-1) 秒
+    pub base_delay_secs: i64,
+    /// 退避时长上限（秒）
+    pub max_delay_secs: i64,
+    /// 锁定时长（秒）
+    pub lockout_secs: i64,
+    /// 超过该时长没有失败则重新计数（秒）
+    pub reset_after_secs: i64,
+    /// 最多保留的失败记录数，超出时丢弃最早失败且不在限制中的记录
+    pub max_entries: usize,
+}
+
+impl Default for ThrottleConfig {
+    fn default() -> Self {
+        ThrottleConfig {
+            max_failures: 5,
+            ip_max_failures: 20,
+            base_delay_secs: 1,
+            max_delay_secs: 300,
+            lockout_secs: 900,
+            reset_after_secs: 3600,
+            max_entries: 10000,
+        }
+    }
+}
+
+impl ThrottleConfig {
+    /// 读取 Rocket 配置中的 `login_throttle` 配置段，未配置时使用默认值
+    pub fn from_figment(figment: &Figment) -> Result<ThrottleConfig, String> {
+        match figment.find_value("login_throttle") {
+            Ok(_) => figment
+                .extract_inner("login_throttle")
+                .map_err(|e| e.to_string()),
+            Err(_) => Ok(ThrottleConfig::default()),
+        }
+    }
+}
+
+/// 单个用户名或 IP 的失败记录
+#[derive(Clone, Debug)]
+struct Entry {
+    failures: u32,
+    last_failure: i64,
+    blocked_until: i64,
+    locked: bool,
+}
+
+/// 当前被限制的用户名或 IP
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct LockoutVo {
+    /// `user:<用户名>` 或 `ip:<地址>`
+    pub key: String,
+    pub failures: u32,
+    pub last_failure: i64,
+    pub blocked_until: i64,
+    /// 是否已达到锁定阈值（否则只是退避等待）
+    pub locked: bool,
+}
+
+/// 登录限流器，作为 Rocket 托管状态使用
+pub struct LoginThrottle {
+    config: ThrottleConfig,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+/// 用户名对应的键
+pub fn user_key(username: &str) -> String {
+    format!("user:{}", username.to_lowercase())
+}
+
+/// IP 对应的键
+pub fn ip_key(ip: &IpAddr) -> String {
+    format!("ip:{}", ip)
+}
+
+impl LoginThrottle {
+    pub fn new(config: ThrottleConfig) -> Self {
+        LoginThrottle {
+            config,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 本次登录涉及的键
+    fn keys(username: &str, ip: Option<IpAddr>) -> Vec<String> {
+        let mut keys = vec![user_key(username)];
+        if let Some(ip) = ip {
+            keys.push(ip_key(&ip));
+        }
+        keys
+    }
+
+    /// 检查是否允许尝试登录
+    /// ---
+    /// @return     Result<(), i64>     不允许时返回需要等待的秒数
+    /// ---
+    pub fn check(&self, username: &str, ip: Option<IpAddr>) -> Result<(), i64> {
+        let now = Local::now().timestamp();
+        let entries = self.entries.lock().unwrap();
+        let keys = Self::keys(username, ip);
+        let wait = keys
+            .iter()
+            .filter_map(|k| entries.get(k))
+            .map(|e| e.blocked_until - now)
+            .max()
+            .unwrap_or(0);
+        if wait > 0 {
+            return Err(wait);
+        }
+        // 记录已满且都在限制中时拒绝新的用户名或 IP，不能为其丢弃仍在锁定中的记录
+        if keys.iter().any(|k| !entries.contains_key(k)) {
+            if let Some(wait) = Self::full_until(&entries, &self.config, now) {
+                return Err(wait);
+            }
+        }
+        Ok(())
+    }
+
+    /// 记录数已达上限且全部在限制中时，返回最早解除限制的等待秒数
+    fn full_until(
+        entries: &HashMap<String, Entry>,
+        config: &ThrottleConfig,
+        now: i64,
+    ) -> Option<i64> {
+        if entries.len() < config.max_entries.max(1) {
+            return None;
+        }
+        let mut earliest: Option<i64> = None;
+        for e in entries.values() {
+            if e.blocked_until <= now {
+                return None;
+            }
+            earliest = Some(earliest.map_or(e.blocked_until, |t| t.min(e.blocked_until)));
+        }
+        earliest.map(|t| t - now)
+    }
+
+    /// 记录一次失败
+    /// ---
+    /// @return     bool    本次失败是否触发了锁定
+    /// ---
+    pub fn record_failure(&self, username: &str, ip: Option<IpAddr>) -> bool {
+        let now = Local::now().timestamp();
+        let config = &self.config;
+        let mut entries = self.entries.lock().unwrap();
+
+        let mut locked = false;
+        for key in Self::keys(username, ip) {
+            // 没有空间时不记录（check 已拒绝新的键，只有并发的请求会走到这里）
+            if !entries.contains_key(&key) && !Self::prune(&mut entries, config, now) {
+                continue;
+            }
+            let threshold = if key.starts_with("ip:") {
+                config.ip_max_failures
+            } else {
+                config.max_failures
+            };
+            let entry = entries.entry(key).or_insert(Entry {
+                failures: 0,
+                last_failure: now,
+                blocked_until: 0,
+                locked: false,
+            });
+            if entry.locked && entry.blocked_until <= now {
+                // 锁定期已过，重新计数
+                entry.failures = 0;
+                entry.locked = false;
+            }
+            entry.failures += 1;
+            entry.last_failure = now;
+
+            if entry.failures >= threshold {
+                entry.blocked_until = now + config.lockout_secs;
+                locked |= !entry.locked;
+                entry.locked = true;
+            } else {
+                let exp = entry.failures.saturating_sub(1).min(30);
+                let delay = config
+                    .base_delay_secs
+                    .saturating_mul(1 << exp)
+                    .min(config.max_delay_secs);
+                entry.blocked_until = now + delay;
+            }
+        }
+        locked
+    }
+
+    /// 新增记录前清理过期的记录，记录数仍达到上限时丢弃最早失败且不在限制中的记录
+    /// ---
+    /// 限制中的记录不会被丢弃，否则用大量用户名即可挤掉被锁定的账号继续猜测密码
+    /// @return     bool    是否有空间新增记录
+    /// ---
+    fn prune(entries: &mut HashMap<String, Entry>, config: &ThrottleConfig, now: i64) -> bool {
+        entries.retain(|_, e| {
+            now - e.last_failure <= config.reset_after_secs || e.blocked_until > now
+        });
+        while entries.len() >= config.max_entries.max(1) {
+            let oldest = entries
+                .iter()
+                .filter(|(_, e)| e.blocked_until <= now)
+                .min_by_key(|(_, e)| e.last_failure)
+                .map(|(k, _)| k.clone());
+            match oldest {
+                Some(key) => entries.remove(&key),
+                None => return false,
+            };
+        }
+        true
+    }
+
+    /// 登录成功后清除用户名的失败记录
+    /// ---
+    /// IP 的失败记录保留：否则同一 IP 登录自己的账号即可清除猜测其他账号的失败次数
+    /// ---
+    pub fn record_success(&self, username: &str) {
+        self.entries.lock().unwrap().remove(&user_key(username));
+    }
+
+    /// 当前仍在限制中的用户名和 IP
+    pub fn list(&self) -> Vec<LockoutVo> {
+        let now = Local::now().timestamp();
+        let entries = self.entries.lock().unwrap();
+        let mut list: Vec<LockoutVo> = entries
+            .iter()
+            .filter(|(_, e)| e.blocked_until > now)
+            .map(|(k, e)| LockoutVo {
+                key: k.clone(),
+                failures: e.failures,
+                last_failure: e.last_failure,
+                blocked_until: e.blocked_until,
+                locked: e.locked,
+            })
+            .collect();
+        list.sort_by_key(|l| std::cmp::Reverse(l.blocked_until));
+        list
+    }
+
+    /// 解除限制
+    /// ---
+    /// @parameter  key     &str    `user:<用户名>` 或 `ip:<地址>`
+    /// @return     bool    是否存在该记录
+    /// ---
+    pub fn clear(&self, key: &str) -> bool {
+        self.entries.lock().unwrap().remove(key).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip() -> Option<IpAddr> {
+        Some("192.0.2.1".parse().unwrap())
+    }
+
+    /// 记录的等待时长（秒）
+    fn delay(throttle: &LoginThrottle, key: &str) -> i64 {
+        let entries = throttle.entries.lock().unwrap();
+        let entry = &entries[key];
+        entry.blocked_until - entry.last_failure
+    }
+
+    /// 把全部记录提前 secs 秒，模拟时间流逝
+    fn rewind(throttle: &LoginThrottle, secs: i64) {
+        for e in throttle.entries.lock().unwrap().values_mut() {
+            e.last_failure -= secs;
+            e.blocked_until -= secs;
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let throttle = LoginThrottle::new(ThrottleConfig {
+            max_failures: 10,
+            base_delay_secs: 2,
+            max_delay_secs: 10,
+            ..ThrottleConfig::default()
+        });
+        let mut delays = vec![];
+        for _ in 0..5 {
+            assert!(!throttle.record_failure("alice", None));
+            delays.push(delay(&throttle, &user_key("alice")));
+        }
+        assert_eq!(delays, [2, 4, 8, 10, 10]);
+        assert!(throttle.check("alice", None).is_err());
+        assert!(throttle.check("bob", None).is_ok());
+
+        rewind(&throttle, 10);
+        assert!(throttle.check("alice", None).is_ok());
+    }
+
+    #[test]
+    fn lockout_lasts_for_the_window_then_counting_restarts() {
+        let throttle = LoginThrottle::new(ThrottleConfig {
+            max_failures: 3,
+            lockout_secs: 900,
+            ..ThrottleConfig::default()
+        });
+        let locked: Vec<bool> = (0..4)
+            .map(|_| throttle.record_failure("Alice", None))
+            .collect();
+        // 只有触发锁定的那次失败返回 true
+        assert_eq!(locked, [false, false, true, false]);
+        assert_eq!(delay(&throttle, &user_key("alice")), 900);
+        let retry_after = throttle.check("alice", None).unwrap_err();
+        assert!(retry_after > 0 && retry_after <= 900);
+        assert!(throttle.list()[0].locked);
+
+        rewind(&throttle, 900);
+        assert!(throttle.check("alice", None).is_ok());
+        assert!(!throttle.record_failure("alice", None));
+        let entries = throttle.entries.lock().unwrap();
+        assert_eq!(entries[&user_key("alice")].failures, 1);
+        assert!(!entries[&user_key("alice")].locked);
+    }
+
+    #[test]
+    fn ip_lockout_uses_its_own_threshold() {
+        let throttle = LoginThrottle::new(ThrottleConfig {
+            max_failures: 10,
+            ip_max_failures: 2,
+            ..ThrottleConfig::default()
+        });
+        assert!(!throttle.record_failure("alice", ip()));
+        assert!(throttle.record_failure("bob", ip()));
+        let list = throttle.list();
+        let ip_entry = list.iter().find(|l| l.key.starts_with("ip:")).unwrap();
+        assert!(ip_entry.locked);
+        assert!(list
+            .iter()
+            .filter(|l| l.key.starts_with("user:"))
+            .all(|l| !l.locked));
+    }
+
+    #[test]
+    fn counters_reset_after_success_clear_or_a_quiet_period() {
+        let throttle = LoginThrottle::new(ThrottleConfig::default());
+        throttle.record_failure("alice", None);
+        throttle.record_success("alice");
+        assert!(throttle.check("alice", None).is_ok());
+        assert!(throttle.list().is_empty());
+
+        throttle.record_failure("alice", ip());
+        assert!(throttle.clear(&user_key("alice")));
+        assert!(!throttle.clear(&user_key("alice")));
+        assert!(throttle.clear(&ip_key(&ip().unwrap())));
+
+        throttle.record_failure("alice", None);
+        throttle.record_failure("alice", None);
+        rewind(&throttle, 3601);
+        throttle.record_failure("bob", None);
+        let entries = throttle.entries.lock().unwrap();
+        assert!(!entries.contains_key(&user_key("alice")));
+    }
+
+    #[test]
+    fn success_keeps_the_ip_counter() {
+        let throttle = LoginThrottle::new(ThrottleConfig::default());
+        throttle.record_failure("alice", ip());
+        throttle.record_success("alice");
+
+        let keys: Vec<String> = throttle.list().into_iter().map(|l| l.key).collect();
+        assert_eq!(keys, vec![ip_key(&ip().unwrap())]);
+        assert!(throttle.check("bob", ip()).is_err());
+    }
+
+    #[test]
+    fn entries_are_bounded() {
+        let throttle = LoginThrottle::new(ThrottleConfig {
+            max_entries: 3,
+            ..ThrottleConfig::default()
+        });
+        for name in ["a", "b", "c", "d", "e"] {
+            throttle.record_failure(name, None);
+        }
+        assert_eq!(throttle.entries.lock().unwrap().len(), 3);
+
+        // 过期的记录在新增记录时被清理
+        rewind(&throttle, 7200);
+        throttle.record_failure("f", None);
+        let keys: Vec<String> = throttle.entries.lock().unwrap().keys().cloned().collect();
+        assert_eq!(keys, vec![user_key("f")]);
+
+        // 锁定中的记录不会被新的用户名挤掉
+        let throttle = LoginThrottle::new(ThrottleConfig {
+            max_entries: 2,
+            max_failures: 2,
+            base_delay_secs: 60,
+            ..ThrottleConfig::default()
+        });
+        throttle.record_failure("victim", None);
+        assert!(throttle.record_failure("victim", None));
+        throttle.record_failure("x", None);
+
+        // 记录已满且都在限制中：拒绝新的用户名，等到最早的退避结束
+        let wait = throttle.check("y", None).unwrap_err();
+        assert!(wait > 0 && wait <= 60);
+        throttle.record_failure("y", None);
+        assert!(!throttle
+            .entries
+            .lock()
+            .unwrap()
+            .contains_key(&user_key("y")));
+
+        // 退避结束的记录可以被挤掉，锁定中的记录保留
+        rewind(&throttle, 61);
+        assert!(throttle.check("y", None).is_ok());
+        throttle.record_failure("y", None);
+        {
+            let entries = throttle.entries.lock().unwrap();
+            assert!(entries.contains_key(&user_key("victim")));
+            assert!(entries.contains_key(&user_key("y")));
+            assert!(!entries.contains_key(&user_key("x")));
+        }
+        assert!(throttle.check("victim", None).is_err());
+    }
+}
@@ -1,22 +1,158 @@
-use crate::KEY;
+use crate::model::user::User;
+use crate::repository::Repositories;
+use crate::util::keyring::keyring;
+use crate::util::permission::Role;
+use chrono::Local;
 use jwt_simple::prelude::Duration;
-use jwt_simple::prelude::{Claims, MACLike, NoCustomClaims};
+use jwt_simple::prelude::{Claims, JWTClaims};
+use rand::RngCore;
+use rocket::figment::Figment;
 use rocket::http::Status;
 use rocket::request::{FromRequest, Outcome};
+use rocket::serde::{Deserialize, Serialize};
+
+/// 令牌有效期配置，对应 Rocket.toml 中的 `jwt` 配置段
+#[derive(Deserialize, Clone, Debug)]
+#[serde(crate = "rocket::serde", default)]
+pub struct TokenConfig {
+    /// 访问令牌有效期（秒）
+    pub access_ttl_secs: u64,
+    /// 刷新令牌有效期（秒）
+    pub refresh_ttl_secs: u64,
+}
+
+impl Default for TokenConfig {
+    fn default() -> Self {
+        TokenConfig {
+            access_ttl_secs: 30 * 60,
+            refresh_ttl_secs: 14 * 24 * 60 * 60,
+        }
+    }
+}
+
+impl TokenConfig {
+    /// 读取 Rocket 配置中 `jwt` 配置段的有效期，未配置时使用默认值，有效期为 0 时返回错误
+    pub fn from_figment(figment: &Figment) -> Result<TokenConfig, String> {
+        let config: TokenConfig = match figment.find_value("jwt") {
+            Ok(_) => figment.extract_inner("jwt").map_err(|e| e.to_string())?,
+            Err(_) => TokenConfig::default(),
+        };
+        if config.access_ttl_secs == 0 || config.refresh_ttl_secs == 0 {
+            return Err("access_ttl_secs and refresh_ttl_secs must be at least 1".to_string());
+        }
+        Ok(config)
+    }
+}
+
+lazy_static! {
+    // 从 Rocket 配置中读取令牌有效期（启动时已校验）
+    pub static ref TOKEN_CONFIG: TokenConfig = TokenConfig::from_figment(&rocket::Config::figment())
+        .expect("invalid jwt config");
+}
+
+/// 令牌中的自定义声明
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct AuthClaims {
+    /// 用户 id
+    pub uid: u32,
+    /// 签发时的用户角色
+    pub role: String,
+    /// 签发时间（毫秒），`iat` 只精确到秒，无法区分吊销全部会话的同一秒内签发的令牌
+    pub iat_ms: i64,
+}
 
 /// 生成token函数
 /// ---
-/// @parameter      username        &str
+/// @parameter      user            &User
 /// @return         token           String
 /// ---
-pub fn create_token(username: &str) -> String {
+pub fn create_token(user: &User) -> String {
+    // 随机令牌 id，用于吊销
+    let mut jti = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut jti);
+    let jti: String = jti.iter().map(|b| format!("{:02x}", b)).collect();
+
+    let custom = AuthClaims {
+        uid: user.id.unwrap_or_default(),
+        role: user.role.clone().unwrap_or_default(),
+        iat_ms: Local::now().timestamp_millis(),
+    };
     // 创建令牌
-    let claims = Claims::create(Duration::from_hours(2)).with_issuer(username);
-    KEY.authenticate(claims).unwrap()
+    let ttl = Duration::from_secs(TOKEN_CONFIG.access_ttl_secs);
+    let claims = Claims::with_custom_claims(custom, ttl)
+        .with_issuer(user.username.clone().unwrap_or_default())
+        .with_jwt_id(jti);
+    keyring().sign(claims).unwrap()
+}
+
+/// 两步验证挑战令牌有效期（秒）
+const CHALLENGE_TTL_SECS: u64 = 5 * 60;
+
+/// 两步验证挑战令牌中的自定义声明，与 `AuthClaims` 字段不同，不能当作访问令牌使用
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct ChallengeClaims {
+    /// 已通过密码校验的用户 id
+    pub challenge_uid: u32,
+}
+
+/// 生成两步验证挑战令牌：密码校验通过后签发，需连同动态码换取访问令牌
+/// ---
+/// @parameter      user            &User
+/// @return         token           String
+/// ---
+pub fn create_challenge_token(user: &User) -> String {
+    let custom = ChallengeClaims {
+        challenge_uid: user.id.unwrap_or_default(),
+    };
+    let claims = Claims::with_custom_claims(custom, Duration::from_secs(CHALLENGE_TTL_SECS))
+        .with_issuer(user.username.clone().unwrap_or_default());
+    keyring().sign(claims).unwrap()
+}
+
+/// 校验两步验证挑战令牌
+/// ---
+/// @parameter      token           &str
+/// @return         Option<(u32, String)>   用户 id 和用户名
+/// ---
+pub fn verify_challenge_token(token: &str) -> Option<(u32, String)> {
+    let claims = keyring().verify::<ChallengeClaims>(token).ok()?;
+    Some((claims.custom.challenge_uid, claims.issuer?))
+}
+
+/// 当前登录用户
+#[derive(Serialize, Clone, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct AuthUser {
+    pub id: u32,
+    pub username: String,
+    /// 角色以数据库为准，修改后立即生效
+    pub role: Role,
 }
 
 // Token请求守卫
-pub struct Token;
+pub struct Token {
+    /// 当前登录用户
+    pub user: AuthUser,
+    /// 令牌声明
+    pub claims: JWTClaims<AuthClaims>,
+}
+
+impl Token {
+    /// 令牌 id
+    pub fn jti(&self) -> &str {
+        self.claims.jwt_id.as_deref().unwrap_or_default()
+    }
+
+    /// 令牌过期时间
+    pub fn expires_at(&self) -> i64 {
+        self.claims
+            .expires_at
+            .map(|t| t.as_secs() as i64)
+            .unwrap_or_default()
+    }
+}
 
 // 拦截请求进行token校验
 #[rocket::async_trait]
@@ -27,17 +163,48 @@ impl<'r> FromRequest<'r> for Token {
         let header_auth = request.headers().get_one("Authorization");
         if let Some(token) = header_auth {
             // println!("请求携带的token为：{:?}", token);
-            let claims_res = KEY.verify_token::<NoCustomClaims>(&token, None);
+            let claims_res = keyring().verify::<AuthClaims>(token);
             if let Ok(claims) = claims_res {
-                match claims.issuer {
-                    // 验证通过
-                    Some(_t) => return Outcome::Success(Token {}),
+                let (jti, issuer) = match (&claims.jwt_id, &claims.issuer) {
+                    (Some(jti), Some(iss)) => (jti, iss),
                     // 不通过
-                    None => return Outcome::Failure((Status::Unauthorized, ())),
+                    _ => return Outcome::Error((Status::Unauthorized, ())),
+                };
+                let issued_at = claims.custom.iat_ms;
+                let uid = claims.custom.uid;
+                let repos = match request.rocket().state::<Repositories>() {
+                    Some(repos) => repos,
+                    None => return Outcome::Error((Status::InternalServerError, ())),
+                };
+
+                // 检查吊销列表
+                match repos.users.is_token_revoked(jti, uid, issued_at).await {
+                    Ok(false) => {}
+                    Ok(true) => return Outcome::Error((Status::Unauthorized, ())),
+                    Err(_) => return Outcome::Error((Status::InternalServerError, ())),
                 }
+
+                // 根据签发者解析出用户，用户已删除或改名时令牌失效
+                return match repos.users.find_by_id(uid).await {
+                    Ok(Some(user)) if user.username.as_ref() == Some(issuer) => {
+                        // 未分配有效角色的用户没有任何权限
+                        let role = match user.role.as_deref().and_then(Role::parse) {
+                            Some(r) => r,
+                            None => return Outcome::Error((Status::Forbidden, ())),
+                        };
+                        let user = AuthUser {
+                            id: uid,
+                            username: issuer.clone(),
+                            role,
+                        };
+                        Outcome::Success(Token { user, claims })
+                    }
+                    Ok(_) => Outcome::Error((Status::Unauthorized, ())),
+                    Err(_) => Outcome::Error((Status::InternalServerError, ())),
+                };
             }
-            return Outcome::Failure((Status::Unauthorized, ()));
+            return Outcome::Error((Status::Unauthorized, ()));
         }
-        Outcome::Failure((Status::Unauthorized, ()))
+        Outcome::Error((Status::Unauthorized, ()))
     }
 }
@@ -0,0 +1,120 @@
+// 事务工作单元：多条写语句在同一个事务中按步骤执行，任一步失败立即回滚并返回失败的步骤
+use std::fmt;
+
+use rbatis::crud::{CRUDMut, CRUDTable, Skip};
+use rbatis::db::DBExecResult;
+use rbatis::executor::{ExecutorMut, RBatisTxExecutorGuard};
+use rbatis::rbatis::Rbatis;
+use rbatis::Error;
+use rbson::Bson;
+
+use crate::util::dialect::insert_returning_id;
+
+/// 事务中执行失败的步骤
+#[derive(Debug)]
+pub struct StepError {
+    /// 步骤名，如 `insert_article`
+    pub step: &'static str,
+    pub error: Error,
+}
+
+impl fmt::Display for StepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "transaction step `{}` failed: {}", self.step, self.error)
+    }
+}
+
+impl std::error::Error for StepError {}
+
+/// 转为数据库错误时保留失败的步骤
+impl From<StepError> for Error {
+    fn from(e: StepError) -> Self {
+        Error::E(e.to_string())
+    }
+}
+
+/// 事务工作单元
+/// ---
+/// ```ignore
+/// let mut uow = UnitOfWork::begin(&RB).await?;
+/// uow.exec("delete_tags", "DELETE FROM article_to_tag WHERE article_id = ?", vec![bson!(id)]).await?;
+/// uow.exec("delete_article", "DELETE FROM article WHERE id = ?", vec![bson!(id)]).await?;
+/// uow.commit().await?;
+/// ```
+/// ---
+pub struct UnitOfWork<'a> {
+    tx: RBatisTxExecutorGuard<'a>,
+}
+
+impl<'a> UnitOfWork<'a> {
+    /// 开启事务
+    pub async fn begin(rb: &'a Rbatis) -> Result<UnitOfWork<'a>, StepError> {
+        let tx = rb.acquire_begin().await.map_err(|error| StepError {
+            step: "begin",
+            error,
+        })?;
+        // 既未提交也未回滚就被丢弃时（如请求被取消）回滚
+        let tx = tx.defer_async(|mut tx1| async move {
+            if !tx1.is_done() {
+                let _ = tx1.rollback().await;
+            }
+        });
+        Ok(UnitOfWork { tx })
+    }
+
+    /// 执行一步写操作
+    pub async fn exec(
+        &mut self,
+        step: &'static str,
+        sql: &str,
+        args: Vec<Bson>,
+    ) -> Result<DBExecResult, StepError> {
+        let res = self.tx.exec(sql, args).await;
+        self.check(step, res).await
+    }
+
+    /// 执行一步插入，返回新记录的 id
+    pub async fn insert(
+        &mut self,
+        step: &'static str,
+        sql: &str,
+        args: Vec<Bson>,
+    ) -> Result<Option<i64>, StepError> {
+        let res = insert_returning_id(&mut *self.tx, sql, args).await;
+        self.check(step, res).await
+    }
+
+    /// 执行一步整行写入（按实体字段生成 INSERT，可指定跳过的字段）
+    pub async fn save<T: CRUDTable>(
+        &mut self,
+        step: &'static str,
+        table: &T,
+        skips: &[Skip<'_>],
+    ) -> Result<DBExecResult, StepError> {
+        let res = self.tx.save(table, skips).await;
+        self.check(step, res).await
+    }
+
+    /// 提交事务
+    pub async fn commit(mut self) -> Result<(), StepError> {
+        let res = self.tx.commit().await;
+        self.check("commit", res).await
+    }
+
+    /// 步骤失败时立即回滚，后续步骤不再执行
+    async fn check<T>(
+        &mut self,
+        step: &'static str,
+        res: Result<T, Error>,
+    ) -> Result<T, StepError> {
+        match res {
+            Ok(v) => Ok(v),
+            Err(error) => {
+                if let Err(e) = self.tx.rollback().await {
+                    log::error!("事务回滚失败（步骤 {}）: {}", step, e);
+                }
+                Err(StepError { step, error })
+            }
+        }
+    }
+}
@@ -0,0 +1,105 @@
+// 请求数据校验
+// 字段规则通过 validator 的派生宏声明在请求结构上，需要查询数据库的校验（引用的数据是否存在、是否重复）由控制器追加
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use validator::{Validate, ValidationError};
+
+use crate::response::error::{AppError, FieldError};
+
+/// 执行请求结构上声明的字段规则，返回全部字段错误
+pub fn field_errors<T: Validate>(data: &T) -> Vec<FieldError> {
+    let errors = match data.validate() {
+        Ok(()) => return vec![],
+        Err(e) => e,
+    };
+    let mut fields = Vec::new();
+    for (field, errs) in errors.field_errors() {
+        for e in errs {
+            let msg = match &e.message {
+                Some(m) => m.to_string(),
+                None => default_message(e),
+            };
+            fields.push(FieldError {
+                field,
+                error: e.code.clone(),
+                msg,
+            });
+        }
+    }
+    fields
+}
+
+/// 字段是否已有错误（已有错误的字段不再查库校验）
+pub fn has_error(errors: &[FieldError], field: &str) -> bool {
+    errors.iter().any(|e| e.field == field)
+}
+
+/// 存在字段错误时返回 422，错误按字段名排序
+pub fn check(mut errors: Vec<FieldError>) -> Result<(), AppError> {
+    if errors.is_empty() {
+        return Ok(());
+    }
+    errors.sort_by_key(|e| e.field);
+    Err(AppError::Validation(errors))
+}
+
+/// 未指定提示信息的规则按错误码及参数生成提示
+fn default_message(e: &ValidationError) -> String {
+    let param = |name: &str| {
+        e.params
+            .get(name)
+            .map(|v| v.to_string())
+            .unwrap_or_default()
+    };
+    match e.code.as_ref() {
+        "required" => "This field is required.".to_string(),
+        "too_short" => format!("Must be at least {} characters.", param("min")),
+        "too_long" => format!("Must be at most {} characters.", param("max")),
+        _ => "Invalid value.".to_string(),
+    }
+}
+
+/// 自定义规则的错误
+pub fn invalid(code: &'static str, msg: &'static str) -> ValidationError {
+    let mut e = ValidationError::new(code);
+    e.message = Some(Cow::Borrowed(msg));
+    e
+}
+
+/// 不能为空或只包含空白字符
+pub fn not_blank(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        return Err(invalid("required", "This field is required."));
+    }
+    Ok(())
+}
+
+/// id 列表不能重复
+pub fn unique_ids(ids: &[u32]) -> Result<(), ValidationError> {
+    let mut seen = HashSet::new();
+    if !ids.iter().all(|id| seen.insert(id)) {
+        return Err(invalid("duplicate", "Ids must not repeat."));
+    }
+    Ok(())
+}
+
+/// 邮箱，空字符串表示清空
+pub fn email_or_empty(value: &str) -> Result<(), ValidationError> {
+    if !value.is_empty() && !validator::validate_email(value) {
+        return Err(invalid("invalid_format", "Invalid email."));
+    }
+    Ok(())
+}
+
+/// http(s) 网址，空字符串表示清空
+pub fn http_url_or_empty(value: &str) -> Result<(), ValidationError> {
+    if value.is_empty() {
+        return Ok(());
+    }
+    let is_http = value.starts_with("http://") || value.starts_with("https://");
+    if !is_http || !validator::validate_url(value) {
+        return Err(invalid("invalid_format", "Invalid website."));
+    }
+    Ok(())
+}